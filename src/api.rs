@@ -0,0 +1,1527 @@
+use crate::config::ConnectionConfig;
+use crate::logger::redact_params;
+use reqwest::multipart::{Form, Part};
+use serde::de::DeserializeOwned;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Deserializer};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use syno_download_station::entities::{Task, TaskCompleted, TaskOperation, TaskStatus};
+use tokio::sync::RwLock;
+
+/// Shown with a permission error from reading tasks or settings: the login works, but the
+/// account isn't allowed to use Download Station
+pub const NO_DOWNLOAD_STATION_ACCESS: &str =
+    "This account cannot access Download Station, check its application permissions in DSM";
+
+/// Download Station task API. There is no fallback to the v1 `SYNO.DownloadStation.Task` of
+/// older Download Station versions: its tasks have other detail fields and the task methods
+/// take other parameters, so a DiskStation without this API gets [`ApiError::Unavailable`].
+const TASK_API: &str = "SYNO.DownloadStation2.Task";
+
+/// Tasks fetched per request when loading the whole task list
+const TASK_PAGE_SIZE: usize = 500;
+
+/// What the task list asks for besides the basic fields: only the transfer and detail info,
+/// which is all the task table needs. Trackers, peers and files are left out as they make up
+/// most of the payload on big task lists, `get_task` loads them for a single task.
+const TASK_LIST_ADDITIONAL: &str = r#"["transfer","detail"]"#;
+
+/// Result of the `DsApi` methods
+pub type Result<T, E = ApiError> = std::result::Result<T, E>;
+
+/// Files Download Station creates tasks from, with the MIME type they are uploaded as. A
+/// .txt file is a list of URLs.
+const TASK_FILE_TYPES: [(&str, &str); 3] = [
+    ("torrent", "application/x-bittorrent"),
+    ("nzb", "application/x-nzb"),
+    ("txt", "text/plain"),
+];
+
+/// Error codes meaning the session is gone and a new login is needed
+const SESSION_ERROR_CODES: [i32; 3] = [106, 107, 119];
+
+/// Client for the Download Station WebAPI: tasks, RSS, BT search, statistics, server
+/// settings, ... The syno-download-station crate only provides the task types, its client
+/// can't be given a CA certificate.
+///
+/// It keeps its own session, which is created lazily on the first request.
+pub struct DsApi {
+    url: String,
+    username: String,
+    password: String,
+    client: reqwest::Client,
+    sid: RwLock<String>,
+    apis: RwLock<HashMap<String, ApiInfo>>,
+    task_pages: RwLock<Vec<TaskPageBody>>, // task list as last returned by `list_tasks_changed`
+}
+
+/// Raw body of a page of the task list with what the paging needs from it, so an identical
+/// page doesn't have to be parsed again
+#[derive(Clone)]
+struct TaskPageBody {
+    body: String,
+    fetched: usize,
+    total: u64,
+}
+
+/// Entry from SYNO.API.Info describing where an API lives and which versions it supports
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiInfo {
+    pub path: String,
+    #[serde(rename = "minVersion")]
+    pub min_version: u32,
+    #[serde(rename = "maxVersion")]
+    pub max_version: u32,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse<D> {
+    success: bool,
+    data: Option<D>,
+    error: Option<ApiErrorBody>,
+}
+
+#[derive(Deserialize)]
+struct ApiErrorBody {
+    code: i32,
+}
+
+#[derive(Deserialize)]
+struct AuthData {
+    sid: String,
+}
+
+/// Error code returned by a WebAPI method
+#[derive(Debug, Clone)]
+pub struct WebApiError {
+    pub api: String,
+    pub method: String,
+    pub code: i32,
+}
+
+impl WebApiError {
+    /// The account is not allowed to call the method, usually because it isn't an admin
+    pub fn is_permission_denied(&self) -> bool {
+        // The task APIs use 402 for a destination the account can't write to
+        self.code == 105 || (self.code == 402 && !self.api.contains(".Task"))
+    }
+
+    /// This DSM version doesn't have the API, the method or the version of it
+    pub fn is_unsupported(&self) -> bool {
+        matches!(self.code, 102..=104)
+    }
+
+    /// Task APIs reuse the 400+ codes with their own meaning
+    pub fn description(&self) -> &'static str {
+        if self.api.contains(".Task") {
+            task_error_description(self.code)
+        } else {
+            error_description(self.code)
+        }
+    }
+}
+
+impl fmt::Display for WebApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} failed: {} (code {})",
+            self.api,
+            self.method,
+            self.description(),
+            self.code
+        )
+    }
+}
+
+impl std::error::Error for WebApiError {}
+
+/// Everything a `DsApi` method can fail with, so callers can tell a DiskStation that can't be
+/// reached from a refused login or a failed task action without looking at the message
+#[derive(Debug)]
+pub enum ApiError {
+    /// The CA certificate from the connection settings can't be read or isn't valid
+    CaCert {
+        path: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// The proxy from the connection settings isn't a usable proxy URL
+    Proxy {
+        url: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// The first request to the DiskStation, looking up its APIs, failed. Wraps `Http` or
+    /// `NotJson`.
+    Connect { url: String, source: Box<ApiError> },
+    /// The API list came back empty or unsuccessful, probably not a DSM address
+    NoApiList { url: String, empty: bool },
+    /// The API isn't offered by this DiskStation, e.g. File Station isn't installed, or
+    /// [`TASK_API`] on a Download Station too old for dstui
+    Unavailable(String),
+    /// The request failed on the way: timeout, connection reset, TLS, ...
+    Http(reqwest::Error),
+    /// The server answered with an HTTP error status
+    Status(reqwest::StatusCode),
+    /// The server answered with something other than JSON
+    NotJson(NonJsonResponse),
+    /// The JSON doesn't look like what the method returns
+    Parse(serde_json::Error),
+    /// Login refused, with the WebAPI error code if there was one
+    Auth(Option<i32>),
+    /// A WebAPI method failed with an error code
+    Api(WebApiError),
+}
+
+impl ApiError {
+    /// The account is not allowed to call the method, usually because it isn't an admin
+    pub fn is_permission_denied(&self) -> bool {
+        matches!(self, ApiError::Api(e) if e.is_permission_denied())
+    }
+
+    /// The DiskStation couldn't be reached at all, as opposed to answering with an error
+    pub fn is_unreachable(&self) -> bool {
+        matches!(self, ApiError::Connect { .. } | ApiError::Http(_))
+    }
+
+    /// The DiskStation doesn't offer what was asked for, as opposed to refusing it
+    pub fn is_unsupported(&self) -> bool {
+        match self {
+            ApiError::Unavailable(_) => true,
+            ApiError::Api(e) => e.is_unsupported(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::CaCert { path, .. } => write!(f, "Can't use CA certificate {}", path),
+            ApiError::Proxy { url, .. } => write!(f, "Can't use proxy {}", url),
+            ApiError::Connect { url, source } => match **source {
+                ApiError::NotJson(_) => write!(f, "Unexpected answer from {}", url),
+                _ => write!(f, "Could not reach {}", url),
+            },
+            ApiError::NoApiList { url, empty: true } => write!(
+                f,
+                "{} answered, but with an empty API list. Is it the DSM address and port?",
+                url
+            ),
+            ApiError::NoApiList { url, .. } => {
+                write!(f, "Failed to query the available APIs of {}", url)
+            }
+            ApiError::Unavailable(api) if api == TASK_API => write!(
+                f,
+                "{} is not available on this DiskStation, dstui needs a newer Download Station, like the one of DSM 7",
+                api
+            ),
+            ApiError::Unavailable(api) => write!(f, "{} is not available on this DiskStation", api),
+            ApiError::Http(_) => write!(f, "Failed to make API request"),
+            ApiError::Status(status) => write!(
+                f,
+                "HTTP request failed with status: {} ({})",
+                status.as_u16(),
+                status.canonical_reason().unwrap_or("Unknown")
+            ),
+            ApiError::NotJson(e) => e.fmt(f),
+            ApiError::Parse(_) => write!(f, "Failed to parse API response"),
+            ApiError::Auth(Some(code)) => write!(
+                f,
+                "Authentication failed: {} (code {})",
+                error_description(*code),
+                code
+            ),
+            ApiError::Auth(None) => write!(f, "Authentication failed, unknown error"),
+            ApiError::Api(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::CaCert { source, .. } | ApiError::Proxy { source, .. } => {
+                Some(source.as_ref())
+            }
+            ApiError::Connect { source, .. } => match source.as_ref() {
+                // Its message is the interesting part, "Failed to make API request" isn't
+                ApiError::Http(e) => Some(e),
+                other => Some(other),
+            },
+            ApiError::Http(e) => Some(e),
+            ApiError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        // The URL can hold the session id (the file upload sends it in the query), and the
+        // error ends up in popups, the log and the clipboard
+        ApiError::Http(e.without_url())
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::Parse(e)
+    }
+}
+
+/// The server answered with something other than JSON, usually a DSM login page or the
+/// error page of a reverse proxy in front of it
+#[derive(Debug, Clone)]
+pub struct NonJsonResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub title: Option<String>, // title of an HTML page, often says what went wrong
+}
+
+impl NonJsonResponse {
+    /// Check a response body, anything that doesn't start like a JSON object or array and
+    /// isn't sent as JSON is rejected
+    fn check(status: u16, content_type: &str, body: &str) -> Result<(), Self> {
+        let first = body.trim_start().chars().next();
+        if content_type.contains("json") || matches!(first, Some('{') | Some('[')) {
+            return Ok(());
+        }
+        Err(Self {
+            status,
+            content_type: content_type.to_string(),
+            title: html_title(body),
+        })
+    }
+}
+
+impl fmt::Display for NonJsonResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let content_type = if self.content_type.is_empty() {
+            "no content type"
+        } else {
+            &self.content_type
+        };
+        write!(
+            f,
+            "Server returned non-JSON ({}, status {})",
+            content_type, self.status
+        )?;
+        if let Some(title) = &self.title {
+            write!(f, ": \"{}\"", title)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NonJsonResponse {}
+
+/// Contents of the `<title>` element of an HTML page
+fn html_title(body: &str) -> Option<String> {
+    let lower = body.to_lowercase();
+    let start = lower.find("<title")?;
+    let start = start + lower[start..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = body
+        .get(start..end)?
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// RSS site configured in Download Station
+#[derive(Debug, Clone, Deserialize)]
+pub struct RssSite {
+    pub id: u64,
+    pub title: String,
+    pub url: String,
+    #[serde(default)]
+    pub is_updating: bool,
+    #[serde(default)]
+    pub last_update: u64,
+}
+
+#[derive(Deserialize)]
+struct RssSiteList {
+    sites: Vec<RssSite>,
+}
+
+/// Single item of an RSS feed
+#[derive(Debug, Clone, Deserialize)]
+pub struct RssFeedItem {
+    pub title: String,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    pub size: u64,
+    #[serde(default)]
+    pub time: u64,
+    #[serde(default)]
+    pub download_uri: String,
+    #[serde(default)]
+    pub external_link: String,
+}
+
+#[derive(Deserialize)]
+struct RssFeedList {
+    feeds: Vec<RssFeedItem>,
+}
+
+/// Global Download Station settings from SYNO.DownloadStation.Info
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerConfig {
+    #[serde(default)]
+    pub default_destination: String,
+    #[serde(default)]
+    pub emule_enabled: bool,
+    #[serde(default)]
+    pub unzip_service_enabled: bool,
+}
+
+/// Download schedule settings from SYNO.DownloadStation.Schedule
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScheduleConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub emule_enabled: bool,
+}
+
+/// Current total transfer speeds from SYNO.DownloadStation.Statistic, in bytes per second
+#[derive(Debug, Clone, Deserialize)]
+pub struct Statistics {
+    #[serde(default, deserialize_with = "lenient_u64")]
+    pub speed_download: u64,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    pub speed_upload: u64,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    pub emule_speed_download: u64,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    pub emule_speed_upload: u64,
+}
+
+/// Shared folder or folder from SYNO.FileStation.List
+#[derive(Debug, Clone, Deserialize)]
+pub struct Folder {
+    pub name: String,
+    pub path: String, // absolute, e.g. "/downloads/movies"
+}
+
+#[derive(Deserialize)]
+struct ShareList {
+    shares: Vec<Folder>,
+}
+
+#[derive(Deserialize)]
+struct FolderList {
+    files: Vec<Folder>,
+}
+
+/// Single result row of a BT search
+#[derive(Debug, Clone, Deserialize)]
+pub struct BtSearchResult {
+    pub title: String,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    pub size: u64,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    pub seeds: u64,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    pub peers: u64,
+    #[serde(default)]
+    pub download_uri: String,
+    #[serde(default)]
+    pub module_title: String,
+}
+
+/// Results collected so far for a running BT search
+#[derive(Debug, Clone, Deserialize)]
+pub struct BtSearchList {
+    #[serde(default)]
+    pub finished: bool,
+    #[serde(default)]
+    pub items: Vec<BtSearchResult>,
+}
+
+/// A slice of the task list and how many tasks there are in total. The crate's `Tasks` can't
+/// be used for this as its offset is an i8.
+#[derive(Debug, Deserialize)]
+pub struct TaskPage {
+    #[serde(rename = "task", default, deserialize_with = "lenient_tasks")]
+    pub tasks: Vec<Task>,
+    #[serde(default, deserialize_with = "lenient_u64")]
+    pub total: u64,
+}
+
+/// Optional parameters for creating a task from a URL, empty ones aren't sent
+#[derive(Debug, Clone, Default)]
+pub struct CreateTaskOptions {
+    pub username: String, // HTTP / FTP login of the source
+    pub password: String,
+    pub unzip_password: String, // for archives Download Station extracts after downloading
+}
+
+#[derive(Deserialize)]
+struct BtSearchTask {
+    taskid: String,
+}
+
+impl DsApi {
+    pub fn new(connection: &ConnectionConfig) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_millis(connection.timeout_ms))
+            .danger_accept_invalid_certs(connection.accept_invalid_certs);
+        // Trusted in addition to the system certificates, for a self-signed DSM certificate
+        // that should still be verified
+        if let Some(path) = connection.ca_cert.as_deref().filter(|p| !p.is_empty()) {
+            let ca_cert_error =
+                |source: Box<dyn std::error::Error + Send + Sync>| ApiError::CaCert {
+                    path: path.to_string(),
+                    source,
+                };
+            let pem = std::fs::read(path).map_err(|e| ca_cert_error(e.into()))?;
+            let certificate =
+                reqwest::Certificate::from_pem(&pem).map_err(|e| ca_cert_error(e.into()))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        // For a DiskStation only reachable through a corporate proxy or an SSH tunnel
+        if let Some(url) = connection.proxy.as_deref().filter(|u| !u.is_empty()) {
+            builder = builder.proxy(proxy(url)?);
+        }
+        Ok(Self::with_client(connection, builder.build()?))
+    }
+
+    /// Client for `connection` sending its requests through `client`, whose timeout and
+    /// certificate settings are used instead of the ones in `connection`
+    pub fn with_client(connection: &ConnectionConfig, client: reqwest::Client) -> Self {
+        Self {
+            url: connection.url.trim_end_matches('/').to_string(),
+            username: connection.username.clone(),
+            password: connection.password.clone(),
+            client,
+            sid: RwLock::new(String::new()),
+            apis: RwLock::new(HashMap::new()),
+            task_pages: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Log in and store the session id
+    pub async fn authorize(&self) -> Result<()> {
+        // Version 6 and up accept the "sid" format, older DSMs fall back to their max
+        let (path, version) = self.api_version_clamped("SYNO.API.Auth", 7).await?;
+        let version = version.to_string();
+        let params = [
+            ("api", "SYNO.API.Auth"),
+            ("version", version.as_str()),
+            ("method", "login"),
+            ("account", self.username.as_str()),
+            ("passwd", self.password.as_str()),
+            ("format", "sid"),
+        ];
+        let response: ApiResponse<AuthData> = self.post(&path, &params).await?;
+
+        match (response.success, response.data, response.error) {
+            (true, Some(data), _) => {
+                *self.sid.write().await = data.sid;
+                Ok(())
+            }
+            (_, _, Some(error)) => Err(ApiError::Auth(Some(error.code))),
+            _ => Err(ApiError::Auth(None)),
+        }
+    }
+
+    /// End the session
+    pub async fn logout(&self) -> Result<()> {
+        let sid = self.sid.read().await.clone();
+        if sid.is_empty() {
+            return Ok(());
+        }
+        let (path, version) = self.api_version_clamped("SYNO.API.Auth", 7).await?;
+        let version = version.to_string();
+        let params = [
+            ("api", "SYNO.API.Auth"),
+            ("version", version.as_str()),
+            ("method", "logout"),
+            ("_sid", sid.as_str()),
+        ];
+        let _: ApiResponse<IgnoredAny> = self.post(&path, &params).await?;
+        self.sid.write().await.clear();
+        Ok(())
+    }
+
+    /// Query SYNO.API.Info for every API the DiskStation offers
+    async fn load_api_info(&self) -> Result<()> {
+        let params = [
+            ("api", "SYNO.API.Info"),
+            ("version", "1"),
+            ("method", "query"),
+            ("query", "ALL"),
+        ];
+        let response: ApiResponse<HashMap<String, ApiInfo>> = self
+            .post("query.cgi", &params)
+            .await
+            .map_err(|e| ApiError::Connect {
+                url: self.url.clone(),
+                source: Box::new(e),
+            })?;
+
+        match response.data {
+            Some(apis) if response.success && !apis.is_empty() => {
+                *self.apis.write().await = apis;
+                Ok(())
+            }
+            // Some error pages answer with a 200 and an empty or unrelated JSON body
+            Some(_) if response.success => Err(ApiError::NoApiList {
+                url: self.url.clone(),
+                empty: true,
+            }),
+            _ => Err(ApiError::NoApiList {
+                url: self.url.clone(),
+                empty: false,
+            }),
+        }
+    }
+
+    /// Look up an API, loading the API list on first use
+    async fn api_info(&self, api: &str) -> Result<ApiInfo> {
+        if self.apis.read().await.is_empty() {
+            self.load_api_info().await?;
+        }
+        self.apis
+            .read()
+            .await
+            .get(api)
+            .cloned()
+            .ok_or_else(|| ApiError::Unavailable(api.to_string()))
+    }
+
+    /// Path of an API and the version to call it with: `desired_max` clamped into the range
+    /// the DiskStation advertises. Blindly using the advertised max breaks on DSM versions that
+    /// advertise versions where some methods behave differently or are missing.
+    pub async fn api_version_clamped(&self, api: &str, desired_max: u32) -> Result<(String, u32)> {
+        let info = self.api_info(api).await?;
+        let version = desired_max.clamp(info.min_version, info.max_version.max(info.min_version));
+        Ok((info.path, version))
+    }
+
+    /// Call an API method with the current session, logging in again once if the session
+    /// has expired. `version` is the version the method is known to work with, see
+    /// `api_version_clamped`.
+    async fn call<D: DeserializeOwned>(
+        &self,
+        api: &str,
+        version: u32,
+        method: &str,
+        params: &[(&str, &str)],
+    ) -> Result<D> {
+        let (path, version) = self.api_version_clamped(api, version).await?;
+        let version = version.to_string();
+
+        self.with_session(api, method, || {
+            self.call_with_sid::<D>(&path, api, &version, method, params)
+        })
+        .await
+    }
+
+    /// Send a request built by `send` (which reads the current session id itself), logging in
+    /// first if there is no session yet and again once if it has expired, and unpack the
+    /// response data
+    async fn with_session<D, F, Fut>(&self, api: &str, method: &str, send: F) -> Result<D>
+    where
+        D: DeserializeOwned,
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<ApiResponse<D>>>,
+    {
+        if self.sid.read().await.is_empty() {
+            self.authorize().await?;
+        }
+
+        let mut response = send().await?;
+        if !response.success
+            && response
+                .error
+                .as_ref()
+                .is_some_and(|e| SESSION_ERROR_CODES.contains(&e.code))
+        {
+            log::info!(
+                "Session expired during {} {}, logging in again",
+                api,
+                method
+            );
+            self.authorize().await?;
+            response = send().await?;
+        }
+
+        if response.success {
+            match response.data {
+                Some(data) => Ok(data),
+                // Methods without a payload, `()` and `Option` deserialize from null
+                None => Ok(D::deserialize(serde_json::Value::Null)?),
+            }
+        } else {
+            let error = WebApiError {
+                api: api.to_string(),
+                method: method.to_string(),
+                code: response.error.map(|e| e.code).unwrap_or_default(),
+            };
+            log::warn!("{}", error);
+            Err(ApiError::Api(error))
+        }
+    }
+
+    async fn call_with_sid<D: DeserializeOwned>(
+        &self,
+        path: &str,
+        api: &str,
+        version: &str,
+        method: &str,
+        params: &[(&str, &str)],
+    ) -> Result<ApiResponse<D>> {
+        let sid = self.sid.read().await.clone();
+        let mut all_params = vec![
+            ("api", api),
+            ("version", version),
+            ("method", method),
+            ("_sid", sid.as_str()),
+        ];
+        all_params.extend_from_slice(params);
+        self.post(path, &all_params).await
+    }
+
+    async fn post<R: DeserializeOwned>(&self, path: &str, params: &[(&str, &str)]) -> Result<R> {
+        let body = self.post_body(path, params).await?;
+        Self::parse_body(path, &body)
+    }
+
+    /// Send a request and return the body without parsing it
+    async fn post_body(&self, path: &str, params: &[(&str, &str)]) -> Result<String> {
+        let url = format!("{}/webapi/{}", self.url, path);
+        log::debug!("POST {} {}", path, redact_params(params));
+        let response = self
+            .client
+            .post(&url)
+            .form(params)
+            .send()
+            .await
+            .inspect_err(|e| log::warn!("POST {} failed: {}", path, e))?;
+
+        Self::response_body(path, response).await
+    }
+
+    async fn parse_response<R: DeserializeOwned>(
+        path: &str,
+        response: reqwest::Response,
+    ) -> Result<R> {
+        let body = Self::response_body(path, response).await?;
+        Self::parse_body(path, &body)
+    }
+
+    /// Body of a successful response that claims to be JSON
+    async fn response_body(path: &str, response: reqwest::Response) -> Result<String> {
+        let status = response.status();
+        log::debug!("POST {} -> HTTP {}", path, status.as_u16());
+        if !status.is_success() {
+            log::warn!("POST {} returned HTTP {}", path, status.as_u16());
+            return Err(ApiError::Status(status));
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let body = response.text().await?;
+        if let Err(e) = NonJsonResponse::check(status.as_u16(), &content_type, &body) {
+            let snippet: String = body.chars().take(500).collect();
+            log::warn!("POST {} {} in {:?}", path, e, snippet);
+            return Err(ApiError::NotJson(e));
+        }
+        Ok(body)
+    }
+
+    fn parse_body<R: DeserializeOwned>(path: &str, body: &str) -> Result<R> {
+        Ok(serde_json::from_str(body).inspect_err(|e| {
+            let snippet: String = body.chars().take(500).collect();
+            log::warn!("POST {} parse error: {} in {:?}", path, e, snippet);
+        })?)
+    }
+
+    /// RSS sites configured in Download Station
+    pub async fn list_rss_sites(&self) -> Result<Vec<RssSite>> {
+        let list: RssSiteList = self
+            .call(
+                "SYNO.DownloadStation.RSS.Site",
+                1,
+                "list",
+                &[("offset", "0"), ("limit", "-1")],
+            )
+            .await?;
+        Ok(list.sites)
+    }
+
+    /// Items of a single RSS site's feed
+    pub async fn list_rss_feed_items(&self, site_id: u64) -> Result<Vec<RssFeedItem>> {
+        let id = site_id.to_string();
+        let list: RssFeedList = self
+            .call(
+                "SYNO.DownloadStation.RSS.Feed",
+                1,
+                "list",
+                &[("id", &id), ("offset", "0"), ("limit", "-1")],
+            )
+            .await?;
+        Ok(list.feeds)
+    }
+
+    /// Raw body of a page of the task list. Only the envelope is parsed, to catch errors and
+    /// an expired session, and not even that when the body is the same as `cached`.
+    async fn task_page_body(&self, offset: usize, cached: Option<&str>) -> Result<String> {
+        let (path, version) = self.api_version_clamped(TASK_API, 2).await?;
+        let version = version.to_string();
+        let offset = offset.to_string();
+        let limit = TASK_PAGE_SIZE.to_string();
+
+        let send = || async {
+            let sid = self.sid.read().await.clone();
+            let params = [
+                ("api", TASK_API),
+                ("version", version.as_str()),
+                ("method", "list"),
+                ("_sid", sid.as_str()),
+                ("offset", offset.as_str()),
+                ("limit", limit.as_str()),
+                ("additional", TASK_LIST_ADDITIONAL),
+            ];
+            let body = self.post_body(&path, &params).await?;
+            // Only successful pages are cached
+            if cached == Some(body.as_str()) {
+                return Ok(ApiResponse {
+                    success: true,
+                    data: Some(body),
+                    error: None,
+                });
+            }
+            let envelope: ApiResponse<IgnoredAny> = Self::parse_body(&path, &body)?;
+            Ok(ApiResponse {
+                success: envelope.success,
+                data: Some(body),
+                error: envelope.error,
+            })
+        };
+
+        self.with_session(TASK_API, "list", send).await
+    }
+
+    /// All tasks like `list_tasks_summary`, or None when every page came back byte for byte
+    /// the same as on the last call. Parsing a big task list on every refresh adds up, and
+    /// most of the time nothing changed.
+    pub async fn list_tasks_changed(&self) -> Result<Option<Vec<Task>>> {
+        let cached = self.task_pages.read().await.clone();
+        let (pages, parsed) = self.task_pages(&cached).await?;
+        if pages.len() == cached.len() && parsed.iter().all(Option::is_none) {
+            log::debug!("Task list unchanged, {} page(s) not parsed", pages.len());
+            return Ok(None);
+        }
+
+        let tasks = Self::tasks_from_pages(&pages, parsed)?;
+        *self.task_pages.write().await = pages;
+        Ok(Some(tasks))
+    }
+
+    /// Every page of the task list. A page is parsed unless its body is the same as the one at
+    /// its position in `cached`, then its entry in the second list is None.
+    async fn task_pages(
+        &self,
+        cached: &[TaskPageBody],
+    ) -> Result<(Vec<TaskPageBody>, Vec<Option<TaskPage>>)> {
+        let mut pages: Vec<TaskPageBody> = Vec::new();
+        let mut parsed: Vec<Option<TaskPage>> = Vec::new();
+        let mut offset = 0;
+        loop {
+            let previous = cached.get(pages.len());
+            let body = self
+                .task_page_body(offset, previous.map(|p| p.body.as_str()))
+                .await?;
+            let page = match previous.filter(|p| p.body == body) {
+                Some(previous) => {
+                    parsed.push(None);
+                    previous.clone()
+                }
+                None => {
+                    let page = Self::parse_task_page(&body)?;
+                    let page_body = TaskPageBody {
+                        body,
+                        fetched: page.tasks.len(),
+                        total: page.total,
+                    };
+                    parsed.push(Some(page));
+                    page_body
+                }
+            };
+            offset += page.fetched;
+            // A short page doesn't mean the end, DSM may cap the limit below TASK_PAGE_SIZE
+            let last = page.fetched == 0 || offset as u64 >= page.total;
+            pages.push(page);
+            if last {
+                break;
+            }
+        }
+        Ok((pages, parsed))
+    }
+
+    /// The tasks of the pages from `task_pages`, parsing the ones it skipped
+    fn tasks_from_pages(
+        pages: &[TaskPageBody],
+        parsed: Vec<Option<TaskPage>>,
+    ) -> Result<Vec<Task>> {
+        let mut tasks: Vec<Task> = Vec::new();
+        let mut seen = HashSet::new();
+        for (page, parsed) in pages.iter().zip(parsed) {
+            let page = match parsed {
+                Some(page) => page,
+                None => Self::parse_task_page(&page.body)?,
+            };
+            // A task added between two pages pushes the rest down, don't list one twice
+            tasks.extend(page.tasks.into_iter().filter(|t| seen.insert(t.id.clone())));
+        }
+        Ok(tasks)
+    }
+
+    /// Make the next `list_tasks_changed` parse the task list again, for when the tasks were
+    /// changed locally and the same list from the DiskStation has to replace them
+    pub async fn forget_task_list(&self) {
+        self.task_pages.write().await.clear();
+    }
+
+    fn parse_task_page(body: &str) -> Result<TaskPage> {
+        let started = Instant::now();
+        let response: ApiResponse<TaskPage> = Self::parse_body(TASK_API, body)?;
+        let page = response.data.unwrap_or(TaskPage {
+            tasks: Vec::new(),
+            total: 0,
+        });
+        log::debug!(
+            "Parsed {} tasks ({} bytes) in {:?}",
+            page.tasks.len(),
+            body.len(),
+            started.elapsed()
+        );
+        Ok(page)
+    }
+
+    /// All tasks, with the fields in `TASK_LIST_ADDITIONAL`. Fetched a page at a time so a NAS
+    /// with thousands of tasks doesn't have to put them all into a single response.
+    pub async fn list_tasks_summary(&self) -> Result<Vec<Task>> {
+        let (pages, parsed) = self.task_pages(&[]).await?;
+        Self::tasks_from_pages(&pages, parsed)
+    }
+
+    /// A single task with everything the info panel shows: transfer, detail, trackers, peers
+    /// and files
+    pub async fn get_task(&self, id: &str) -> Result<Option<Task>> {
+        let info: TaskPage = self
+            .call(
+                TASK_API,
+                2,
+                "get",
+                &[
+                    ("id", id),
+                    (
+                        "additional",
+                        r#"["transfer","tracker","peer","file","detail"]"#,
+                    ),
+                ],
+            )
+            .await?;
+        Ok(info.tasks.into_iter().next())
+    }
+
+    /// Create a task from any URL or link [`crate::util::validate_url`] accepts
+    pub async fn create_task(
+        &self,
+        uri: &str,
+        destination: &str,
+        options: &CreateTaskOptions,
+    ) -> Result<()> {
+        let mut params = vec![
+            ("type", "\"url\""),
+            ("destination", destination),
+            ("url", uri),
+            ("create_list", "false"),
+        ];
+        for (name, value) in [
+            ("username", &options.username),
+            ("password", &options.password),
+            ("unzip_password", &options.unzip_password),
+        ] {
+            if !value.is_empty() {
+                params.push((name, value.as_str()));
+            }
+        }
+        let _: IgnoredAny = self.call(TASK_API, 2, "create", &params).await?;
+        Ok(())
+    }
+
+    /// Create a task from the contents of a .torrent, .nzb or .txt file, uploaded as multipart
+    /// form data
+    pub async fn create_task_from_file(
+        &self,
+        file_data: &[u8],
+        file_name: &str,
+        destination: &str,
+    ) -> Result<()> {
+        let (path, version) = self.api_version_clamped(TASK_API, 2).await?;
+        let version = version.to_string();
+        let url = format!("{}/webapi/{}", self.url, path);
+
+        let send = || async {
+            let torrent = Part::bytes(file_data.to_vec())
+                .file_name(file_name.to_string())
+                .mime_str(task_file_mime(file_name).unwrap_or("application/x-bittorrent"))?;
+            let form = Form::new()
+                .text("api", TASK_API)
+                .text("version", version.clone())
+                .text("method", "create")
+                .text("type", "\"file\"")
+                .text("file", "[\"torrent\"]")
+                .text("destination", format!("\"{}\"", destination))
+                .text("create_list", "false")
+                .part("torrent", torrent);
+            log::debug!(
+                "POST {} create {} ({} bytes)",
+                path,
+                file_name,
+                file_data.len()
+            );
+
+            // The session id has to be in the query, DSM ignores it inside the form
+            let sid = self.sid.read().await.clone();
+            let response = self
+                .client
+                .post(&url)
+                .query(&[("_sid", sid.as_str())])
+                .multipart(form)
+                .send()
+                .await?;
+            Self::parse_response::<ApiResponse<IgnoredAny>>(&path, response).await
+        };
+
+        self.with_session(TASK_API, "create", send).await?;
+        Ok(())
+    }
+
+    /// Pause tasks, `ids` is a comma separated list. Tasks that couldn't be paused are
+    /// listed in the result.
+    pub async fn pause(&self, ids: &str) -> Result<TaskOperation> {
+        self.call(TASK_API, 2, "pause", &[("id", ids)]).await
+    }
+
+    /// Resume tasks, `ids` is a comma separated list. Tasks that couldn't be resumed are
+    /// listed in the result.
+    pub async fn resume(&self, ids: &str) -> Result<TaskOperation> {
+        self.call(TASK_API, 2, "resume", &[("id", ids)]).await
+    }
+
+    /// Mark an unfinished task as complete
+    pub async fn complete(&self, id: &str) -> Result<TaskCompleted> {
+        self.call(
+            "SYNO.DownloadStation2.Task.Complete",
+            1,
+            "start",
+            &[("id", id)],
+        )
+        .await
+    }
+
+    /// Delete a task, `force_complete` keeps the downloaded files of an unfinished task
+    pub async fn delete_task(&self, id: &str, force_complete: bool) -> Result<TaskOperation> {
+        let mut params = vec![("id", id)];
+        if force_complete {
+            params.push(("force_complete", "true"));
+        }
+        self.call(TASK_API, 2, "delete", &params).await
+    }
+
+    /// Cap the bandwidth of a single task, in KB/s, 0 is unlimited. Older Download Station
+    /// versions only have the global limits and answer with one of the unsupported codes.
+    pub async fn set_task_speed_limit(&self, id: &str, down_kbps: u64, up_kbps: u64) -> Result<()> {
+        self.call(
+            TASK_API,
+            2,
+            "edit",
+            &[
+                ("id", id),
+                ("max_download_rate", &down_kbps.to_string()),
+                ("max_upload_rate", &up_kbps.to_string()),
+            ],
+        )
+        .await
+    }
+
+    /// Remove all finished tasks from the list
+    pub async fn clear_completed(&self) -> Result<()> {
+        let finished = (TaskStatus::Finished as u8).to_string();
+        self.call(TASK_API, 2, "delete_condition", &[("status", &finished)])
+            .await
+    }
+
+    /// Global Download Station settings
+    pub async fn get_server_config(&self) -> Result<ServerConfig> {
+        self.call("SYNO.DownloadStation.Info", 1, "getconfig", &[])
+            .await
+    }
+
+    /// Change global Download Station settings, `params` are setserverconfig key/value pairs
+    /// such as `("emule_enabled", "true")` or `("bt_max_download", "1024")`.
+    /// Needs an admin account.
+    pub async fn set_server_config(&self, params: &[(&str, &str)]) -> Result<()> {
+        self.call("SYNO.DownloadStation.Info", 1, "setserverconfig", params)
+            .await
+    }
+
+    /// Download schedule settings
+    pub async fn get_schedule_config(&self) -> Result<ScheduleConfig> {
+        self.call("SYNO.DownloadStation.Schedule", 1, "getconfig", &[])
+            .await
+    }
+
+    /// Change the download schedule settings. Needs an admin account.
+    pub async fn set_schedule_config(&self, params: &[(&str, &str)]) -> Result<()> {
+        self.call("SYNO.DownloadStation.Schedule", 1, "setconfig", params)
+            .await
+    }
+
+    /// Current total download and upload speeds
+    pub async fn get_statistics(&self) -> Result<Statistics> {
+        self.call("SYNO.DownloadStation.Statistic", 1, "getinfo", &[])
+            .await
+    }
+
+    /// Shared folders the account can see
+    pub async fn list_shared_folders(&self) -> Result<Vec<Folder>> {
+        let list: ShareList = self
+            .call(
+                "SYNO.FileStation.List",
+                2,
+                "list_share",
+                &[("offset", "0"), ("limit", "-1"), ("sort_by", "name")],
+            )
+            .await?;
+        Ok(list.shares)
+    }
+
+    /// Subfolders of a folder, `path` is absolute like the paths in `Folder`
+    pub async fn list_folders(&self, path: &str) -> Result<Vec<Folder>> {
+        let list: FolderList = self
+            .call(
+                "SYNO.FileStation.List",
+                2,
+                "list",
+                &[
+                    ("folder_path", path),
+                    ("filetype", "dir"),
+                    ("offset", "0"),
+                    ("limit", "-1"),
+                    ("sort_by", "name"),
+                ],
+            )
+            .await?;
+        Ok(list.files)
+    }
+
+    /// Start a BT search and return its task id. `module` is a search engine id or "enabled"
+    /// to search with every enabled engine.
+    pub async fn bt_search_start(&self, keyword: &str, module: &str) -> Result<String> {
+        let task: BtSearchTask = self
+            .call(
+                "SYNO.DownloadStation.BTSearch",
+                1,
+                "start",
+                &[("keyword", keyword), ("module", module)],
+            )
+            .await?;
+        Ok(task.taskid)
+    }
+
+    /// Results of a BT search, best seeded first
+    pub async fn bt_search_list(&self, task_id: &str) -> Result<BtSearchList> {
+        self.call(
+            "SYNO.DownloadStation.BTSearch",
+            1,
+            "list",
+            &[
+                ("taskid", task_id),
+                ("offset", "0"),
+                ("limit", "-1"),
+                ("sort_by", "seeds"),
+                ("sort_direction", "desc"),
+            ],
+        )
+        .await
+    }
+
+    /// Drop a BT search and its results on the DiskStation
+    pub async fn bt_search_clean(&self, task_id: &str) -> Result<()> {
+        self.call(
+            "SYNO.DownloadStation.BTSearch",
+            1,
+            "clean",
+            &[("taskid", task_id)],
+        )
+        .await
+    }
+}
+
+/// Proxy for every request from a proxy URL in the connection settings
+fn proxy(url: &str) -> Result<reqwest::Proxy> {
+    let proxy_error = |source: Box<dyn std::error::Error + Send + Sync>| ApiError::Proxy {
+        url: url.to_string(),
+        source,
+    };
+    let scheme = url
+        .split_once("://")
+        .map(|(scheme, _)| scheme.to_lowercase());
+    if !matches!(
+        scheme.as_deref(),
+        Some("http" | "https" | "socks5" | "socks5h")
+    ) {
+        return Err(proxy_error(
+            "the proxy URL must start with http://, https:// or socks5://".into(),
+        ));
+    }
+    reqwest::Proxy::all(url).map_err(|e| proxy_error(e.into()))
+}
+
+/// Human readable text for the common WebAPI error codes
+pub fn error_description(code: i32) -> &'static str {
+    match code {
+        100 => "Unknown error",
+        101 => "Invalid parameter",
+        102 => "The requested API does not exist",
+        103 => "The requested method does not exist",
+        104 => "The requested version does not support the functionality",
+        105 => "The logged in session does not have permission",
+        106 => "Session timeout",
+        107 => "Session interrupted by duplicate login",
+        119 => "Session id not found",
+        400 => "No such account or incorrect password",
+        401 => "Account disabled",
+        402 => "Permission denied",
+        403 => "2-step verification code required",
+        404 => "Failed to authenticate 2-step verification code",
+        _ => "Unknown error",
+    }
+}
+
+/// Human readable text for the task API error codes, falling back to the common ones
+pub fn task_error_description(code: i32) -> &'static str {
+    match code {
+        400 => "File upload failed",
+        401 => "Max number of tasks reached",
+        402 => "Destination denied",
+        403 => "Destination does not exist",
+        404 => "Invalid task id",
+        405 => "Invalid task action",
+        406 => "No default destination set",
+        407 => "Set destination failed",
+        408 => "File does not exist",
+        _ => error_description(code),
+    }
+}
+
+/// MIME type to upload `file_name` as, None when Download Station can't create a task from it
+pub fn task_file_mime(file_name: &str) -> Option<&'static str> {
+    let extension = Path::new(file_name).extension()?.to_str()?;
+    TASK_FILE_TYPES
+        .iter()
+        .find(|(ext, _)| ext.eq_ignore_ascii_case(extension))
+        .map(|(_, mime)| *mime)
+}
+
+/// Whether a task status is one of the error states, i.e. a status code of 101 and up
+pub fn is_error_status(status: &TaskStatus) -> bool {
+    serde_json::to_value(status)
+        .ok()
+        .and_then(|code| code.as_u64())
+        .is_some_and(|code| code >= TaskStatus::Error as u64)
+}
+
+/// Error states that can go away by themselves or once the cause is fixed on the NAS, e.g.
+/// a full disk or a tracker that timed out. Resuming such a task makes DSM queue it again.
+pub fn is_retriable_status(status: &TaskStatus) -> bool {
+    matches!(
+        status,
+        TaskStatus::Error
+            | TaskStatus::ErrorBrokenLink
+            | TaskStatus::ErrorDestNoExist
+            | TaskStatus::ErrorDestDeny
+            | TaskStatus::ErrorDiskFull
+            | TaskStatus::ErrorQuotaReached
+            | TaskStatus::ErrorTimeout
+            | TaskStatus::ErrorExceedMaxFsSize
+            | TaskStatus::ErrorExceedMaxTempFsSize
+            | TaskStatus::ErrorExceedMaxDestFsSize
+            | TaskStatus::ErrorExtractFail
+            | TaskStatus::ErrorExtractDiskFull
+            | TaskStatus::ErrorExtractQuotaReached
+            | TaskStatus::ErrorTryItLater
+    )
+}
+
+/// Start of `status_extra.error_detail` for a task whose status `TaskStatus` has no variant
+/// for, followed by the status as DSM sent it. See [`lenient_tasks`].
+const UNKNOWN_STATUS: &str = "\u{0}status:";
+
+/// Tasks that don't fail the whole list on a status the crate doesn't know. `TaskStatus`
+/// only takes the numeric codes it has variants for, so before parsing a task its status is
+/// turned into one of those:
+/// - a number in a string, like `"5"`, is read as the number
+/// - text like `"seeding"` or `"hash_checking"` (Download Station 1) maps to its variant
+/// - anything else becomes `Error` for codes of 101 and up and `Waiting` otherwise, with the
+///   original kept for [`status_label`]
+fn lenient_tasks<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Task>, D::Error> {
+    let tasks = Vec::<serde_json::Value>::deserialize(deserializer)?;
+    tasks
+        .into_iter()
+        .map(|mut task| {
+            normalize_status(&mut task);
+            serde_json::from_value(task).map_err(serde::de::Error::custom)
+        })
+        .collect()
+}
+
+fn normalize_status(task: &mut serde_json::Value) {
+    let Some(status) = task.get("status").cloned() else {
+        return;
+    };
+    let code = match &status {
+        serde_json::Value::Number(n) => n.as_u64(),
+        serde_json::Value::String(s) => s.trim().parse().ok().or_else(|| status_code(s)),
+        _ => None,
+    };
+    if let Some(code) = code.filter(|&code| status_from_code(code).is_some()) {
+        task["status"] = code.into();
+        return;
+    }
+    let raw = match status {
+        serde_json::Value::String(s) => s,
+        other => other.to_string(),
+    };
+    let placeholder = match code {
+        Some(code) if code >= TaskStatus::Error as u64 => TaskStatus::Error,
+        _ => TaskStatus::Waiting,
+    };
+    task["status"] = (placeholder as u8).into();
+    task["status_extra"] = serde_json::json!({
+        "error_detail": format!("{}{}", UNKNOWN_STATUS, raw),
+    });
+}
+
+fn status_from_code(code: u64) -> Option<TaskStatus> {
+    serde_json::from_value(code.into()).ok()
+}
+
+/// Code of a status given as text, compared to the variant names so "hash_checking" finds
+/// `HashChecking`
+fn status_code(text: &str) -> Option<u64> {
+    let wanted: String = text
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    (1..=u8::MAX as u64)
+        .filter_map(|code| status_from_code(code).map(|status| (code, status)))
+        .find(|(_, status)| format!("{:?}", status).to_lowercase() == wanted)
+        .map(|(code, _)| code)
+}
+
+/// The status as it should be shown: the variant name, "Unknown" for a code `TaskStatus`
+/// doesn't have, or the text DSM sent when it isn't one of the known ones
+pub fn status_label(task: &Task) -> String {
+    match unknown_status(task) {
+        Some(raw) if raw.parse::<u64>().is_ok() => "Unknown".to_string(),
+        Some(raw) => raw.to_string(),
+        None => format!("{:?}", task.status),
+    }
+}
+
+fn unknown_status(task: &Task) -> Option<&str> {
+    task.status_extra
+        .as_ref()
+        .and_then(|e| e.error_detail.as_deref())
+        .and_then(|detail| detail.strip_prefix(UNKNOWN_STATUS))
+}
+
+/// DSM's description of what went wrong with a task, if it sent one
+pub fn task_error_detail(task: &Task) -> Option<&str> {
+    task.status_extra
+        .as_ref()
+        .and_then(|e| e.error_detail.as_deref())
+        .filter(|detail| !detail.starts_with(UNKNOWN_STATUS))
+}
+
+/// Some DSM versions send numbers as strings, accept both
+fn lenient_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(u64),
+        String(String),
+    }
+
+    Ok(match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Number(n) => n,
+        NumberOrString::String(s) => s.trim().parse().unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod server_tests;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task_json(status: &str) -> String {
+        format!(
+            r#"{{"id":"dbid_1","username":"admin","type":"bt","title":"debian.iso","size":1,"status":{}}}"#,
+            status
+        )
+    }
+
+    #[test]
+    fn numeric_status_codes_map_to_variants() {
+        let task: Task = serde_json::from_str(&task_json("5")).unwrap();
+        assert!(matches!(task.status, TaskStatus::Finished));
+        let task: Task = serde_json::from_str(&task_json("8")).unwrap();
+        assert!(matches!(task.status, TaskStatus::Seeding));
+        let task: Task = serde_json::from_str(&task_json("105")).unwrap();
+        assert!(matches!(task.status, TaskStatus::ErrorDiskFull));
+    }
+
+    fn parse_task(status: &str) -> Task {
+        let page = format!(r#"{{"task":[{}],"total":1}}"#, task_json(status));
+        let page: TaskPage = serde_json::from_str(&page).unwrap();
+        page.tasks.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn text_statuses_map_to_variants() {
+        let task = parse_task(r#""seeding""#);
+        assert!(matches!(task.status, TaskStatus::Seeding));
+        assert_eq!(status_label(&task), "Seeding");
+        assert!(matches!(
+            parse_task(r#""hash_checking""#).status,
+            TaskStatus::HashChecking
+        ));
+        assert!(matches!(parse_task(r#""5""#).status, TaskStatus::Finished));
+    }
+
+    #[test]
+    fn unknown_statuses_fall_back() {
+        let task = parse_task("250");
+        assert!(matches!(task.status, TaskStatus::Error));
+        assert_eq!(status_label(&task), "Unknown");
+        assert_eq!(task_error_detail(&task), None);
+
+        let task = parse_task("42");
+        assert!(matches!(task.status, TaskStatus::Waiting));
+        assert_eq!(status_label(&task), "Unknown");
+
+        let task = parse_task(r#""relocating""#);
+        assert!(matches!(task.status, TaskStatus::Waiting));
+        assert_eq!(status_label(&task), "relocating");
+    }
+
+    #[test]
+    fn proxy_schemes() {
+        assert!(proxy("socks5://127.0.0.1:1080").is_ok());
+        assert!(proxy("HTTP://proxy.example.com:3128").is_ok());
+        assert!(matches!(
+            proxy("proxy.example.com:3128"),
+            Err(ApiError::Proxy { .. })
+        ));
+        assert!(matches!(
+            proxy("ftp://proxy.example.com"),
+            Err(ApiError::Proxy { .. })
+        ));
+    }
+
+    #[test]
+    fn error_statuses_start_at_101() {
+        assert!(!is_error_status(&TaskStatus::Seeding));
+        assert!(!is_error_status(&TaskStatus::CaptchaNeeded));
+        assert!(is_error_status(&TaskStatus::Error));
+        assert!(is_error_status(&TaskStatus::ErrorDiskFull));
+        assert!(is_error_status(&TaskStatus::ErrorTryItLater));
+    }
+
+    #[test]
+    fn api_errors_can_be_told_apart() {
+        let denied = ApiError::Api(WebApiError {
+            api: "SYNO.DownloadStation.Info".into(),
+            method: "setserverconfig".into(),
+            code: 105,
+        });
+        assert!(denied.is_permission_denied());
+        assert!(!ApiError::Auth(Some(400)).is_permission_denied());
+        let destination_denied = ApiError::Api(WebApiError {
+            api: TASK_API.into(),
+            method: "create".into(),
+            code: 402,
+        });
+        assert!(!destination_denied.is_permission_denied());
+        assert_eq!(
+            ApiError::Auth(Some(400)).to_string(),
+            "Authentication failed: No such account or incorrect password (code 400)"
+        );
+
+        // The cause shows up in the error chain, as with the context messages before
+        let connect = anyhow::Error::from(ApiError::Connect {
+            url: "http://nas:5000".into(),
+            source: Box::new(ApiError::NotJson(NonJsonResponse {
+                status: 200,
+                content_type: "text/html".into(),
+                title: Some("Login".into()),
+            })),
+        });
+        assert_eq!(
+            format!("{:#}", connect),
+            "Unexpected answer from http://nas:5000: Server returned non-JSON (text/html, status 200): \"Login\""
+        );
+    }
+
+    #[test]
+    fn task_files_are_uploaded_with_their_mime_type() {
+        assert_eq!(
+            task_file_mime("linux.iso.torrent"),
+            Some("application/x-bittorrent")
+        );
+        assert_eq!(task_file_mime("Show.S01E01.NZB"), Some("application/x-nzb"));
+        assert_eq!(task_file_mime("urls.txt"), Some("text/plain"));
+        assert_eq!(task_file_mime("archive.zip"), None);
+        assert_eq!(task_file_mime("torrent"), None);
+    }
+
+    #[test]
+    fn only_transient_errors_are_retriable() {
+        assert!(is_retriable_status(&TaskStatus::ErrorTimeout));
+        assert!(is_retriable_status(&TaskStatus::ErrorDiskFull));
+        assert!(!is_retriable_status(&TaskStatus::ErrorTorrentDuplicate));
+        assert!(!is_retriable_status(&TaskStatus::ErrorTorrentInvalid));
+        assert!(!is_retriable_status(&TaskStatus::Paused));
+    }
+
+    #[test]
+    fn task_page_accepts_total_as_string() {
+        let json = format!(
+            r#"{{"offset":200,"total":"1234","task":[{}]}}"#,
+            task_json("2")
+        );
+        let page: TaskPage = serde_json::from_str(&json).unwrap();
+        assert_eq!(page.total, 1234);
+        assert_eq!(page.tasks.len(), 1);
+        assert!(matches!(page.tasks[0].status, TaskStatus::Downloading));
+    }
+}
@@ -0,0 +1,315 @@
+//! DsApi against a mock DiskStation, checking the requests it sends and how the answers are
+//! parsed and mapped to errors
+
+use super::*;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const API_LIST: &str = r#"{"success":true,"data":{
+    "SYNO.API.Auth":{"path":"entry.cgi","minVersion":1,"maxVersion":7},
+    "SYNO.DownloadStation2.Task":{"path":"entry.cgi","minVersion":1,"maxVersion":2}}}"#;
+
+fn json(body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body.to_string(), "application/json")
+}
+
+fn api(server: &MockServer) -> DsApi {
+    let connection = ConnectionConfig {
+        url: format!("{}/", server.uri()),
+        username: String::from("admin"),
+        password: String::from("secret"),
+        accept_invalid_certs: false,
+        ca_cert: None,
+        proxy: None,
+        timeout_ms: 3000,
+    };
+    DsApi::with_client(&connection, reqwest::Client::new())
+}
+
+/// A DiskStation with the task API that accepts the login
+async fn diskstation() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/query.cgi"))
+        .respond_with(json(API_LIST))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=login"))
+        .and(body_string_contains("account=admin"))
+        .respond_with(json(r#"{"success":true,"data":{"sid":"abc"}}"#))
+        .expect(1)
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn task_list_is_parsed() {
+    let server = diskstation().await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=list"))
+        .and(body_string_contains("_sid=abc"))
+        .respond_with(json(
+            r#"{"success":true,"data":{"offset":0,"total":2,"task":[
+                {"id":"dbid_1","username":"admin","type":"bt","title":"debian.iso","size":1,"status":2},
+                {"id":"dbid_2","username":"admin","type":"bt","title":"arch.iso","size":2,"status":8}]}}"#,
+        ))
+        .mount(&server)
+        .await;
+
+    let tasks = api(&server).list_tasks_summary().await.unwrap();
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].title, "debian.iso");
+    assert!(matches!(tasks[0].status, TaskStatus::Downloading));
+    assert!(matches!(tasks[1].status, TaskStatus::Seeding));
+}
+
+#[tokio::test]
+async fn short_pages_are_followed_up_to_the_total() {
+    let server = diskstation().await;
+    // A DiskStation that returns fewer tasks per page than asked for
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=list"))
+        .and(body_string_contains("offset=0"))
+        .respond_with(json(
+            r#"{"success":true,"data":{"offset":0,"total":3,"task":[
+                {"id":"dbid_1","username":"admin","type":"bt","title":"a.iso","size":1,"status":2},
+                {"id":"dbid_2","username":"admin","type":"bt","title":"b.iso","size":1,"status":2}]}}"#,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=list"))
+        .and(body_string_contains("offset=2"))
+        .respond_with(json(
+            r#"{"success":true,"data":{"offset":2,"total":3,"task":[
+                {"id":"dbid_3","username":"admin","type":"bt","title":"c.iso","size":1,"status":2}]}}"#,
+        ))
+        .mount(&server)
+        .await;
+
+    let api = api(&server);
+    assert_eq!(api.list_tasks_summary().await.unwrap().len(), 3);
+    assert_eq!(api.list_tasks_changed().await.unwrap().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn unchanged_task_list_is_not_parsed_again() {
+    let server = diskstation().await;
+    let list = |status: u8| {
+        json(&format!(
+            r#"{{"success":true,"data":{{"offset":0,"total":1,"task":[
+                {{"id":"dbid_1","username":"admin","type":"bt","title":"debian.iso","size":1,"status":{}}}]}}}}"#,
+            status
+        ))
+    };
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=list"))
+        .respond_with(list(2))
+        .up_to_n_times(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=list"))
+        .respond_with(list(3))
+        .mount(&server)
+        .await;
+
+    let api = api(&server);
+    let tasks = api.list_tasks_changed().await.unwrap().unwrap();
+    assert!(matches!(tasks[0].status, TaskStatus::Downloading));
+    assert!(api.list_tasks_changed().await.unwrap().is_none());
+    let tasks = api.list_tasks_changed().await.unwrap().unwrap();
+    assert!(matches!(tasks[0].status, TaskStatus::Paused));
+}
+
+#[tokio::test]
+async fn pause_sends_the_ids() {
+    let server = diskstation().await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=pause"))
+        .and(body_string_contains("id=dbid_1%2Cdbid_2"))
+        .respond_with(json(
+            r#"{"success":true,"data":{"failed_task":[{"id":"dbid_2","error":544}]}}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let operation = api(&server).pause("dbid_1,dbid_2").await.unwrap();
+    assert_eq!(operation.failed_task.len(), 1);
+    assert_eq!(operation.failed_task[0].id, "dbid_2");
+    assert_eq!(operation.failed_task[0].error, 544);
+}
+
+#[tokio::test]
+async fn refused_task_keeps_the_error_code() {
+    let server = diskstation().await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=create"))
+        .respond_with(json(r#"{"success":false,"error":{"code":403}}"#))
+        .mount(&server)
+        .await;
+
+    let error = api(&server)
+        .create_task(
+            "magnet:?xt=urn:btih:0",
+            "downloads",
+            &CreateTaskOptions::default(),
+        )
+        .await
+        .unwrap_err();
+    match error {
+        ApiError::Api(e) => {
+            assert_eq!(e.method, "create");
+            assert_eq!(e.code, 403);
+        }
+        e => panic!("expected an API error, got {:?}", e),
+    }
+}
+
+#[tokio::test]
+async fn task_speed_limit_without_edit_method_is_unsupported() {
+    let server = diskstation().await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=edit"))
+        .and(body_string_contains("max_download_rate=512"))
+        .and(body_string_contains("max_upload_rate=0"))
+        .respond_with(json(r#"{"success":false,"error":{"code":103}}"#))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let error = api(&server)
+        .set_task_speed_limit("dbid_1", 512, 0)
+        .await
+        .unwrap_err();
+    assert!(error.is_unsupported());
+    assert!(!error.is_permission_denied());
+}
+
+#[tokio::test]
+async fn only_filled_in_create_options_are_sent() {
+    let server = diskstation().await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=create"))
+        .and(body_string_contains("username=alice"))
+        .and(body_string_contains("password=s3cret"))
+        .respond_with(json(r#"{"success":true,"data":{"task_id":["dbid_3"]}}"#))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let options = CreateTaskOptions {
+        username: String::from("alice"),
+        password: String::from("s3cret"),
+        unzip_password: String::new(),
+    };
+    api(&server)
+        .create_task("https://example.com/file.zip", "downloads", &options)
+        .await
+        .unwrap();
+    let requests = server.received_requests().await.unwrap();
+    let create = String::from_utf8_lossy(&requests.last().unwrap().body).to_string();
+    assert!(!create.contains("unzip_password"));
+}
+
+#[tokio::test]
+async fn old_download_station_says_what_is_needed() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/query.cgi"))
+        .respond_with(json(
+            r#"{"success":true,"data":{
+                "SYNO.API.Auth":{"path":"auth.cgi","minVersion":1,"maxVersion":6},
+                "SYNO.DownloadStation.Task":{"path":"DownloadStation/task.cgi","minVersion":1,"maxVersion":3}}}"#,
+        ))
+        .mount(&server)
+        .await;
+
+    let error = api(&server).pause("dbid_1").await.unwrap_err();
+    assert!(matches!(error, ApiError::Unavailable(_)));
+    assert!(error.to_string().contains("newer Download Station"));
+}
+
+#[tokio::test]
+async fn wrong_password_is_an_auth_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/query.cgi"))
+        .respond_with(json(API_LIST))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=login"))
+        .respond_with(json(r#"{"success":false,"error":{"code":400}}"#))
+        .mount(&server)
+        .await;
+
+    let error = api(&server).pause("dbid_1").await.unwrap_err();
+    assert!(matches!(error, ApiError::Auth(Some(400))));
+}
+
+#[tokio::test]
+async fn expired_session_logs_in_again() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/query.cgi"))
+        .respond_with(json(API_LIST))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=login"))
+        .respond_with(json(r#"{"success":true,"data":{"sid":"abc"}}"#))
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=pause"))
+        .respond_with(json(r#"{"success":false,"error":{"code":119}}"#))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/entry.cgi"))
+        .and(body_string_contains("method=pause"))
+        .respond_with(json(r#"{"success":true,"data":{"failed_task":[]}}"#))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    api(&server).pause("dbid_1").await.unwrap();
+}
+
+#[tokio::test]
+async fn login_page_instead_of_api_list_is_a_connect_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/webapi/query.cgi"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<html><head><title>Login</title></head></html>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+
+    let error = api(&server).list_tasks_summary().await.unwrap_err();
+    match error {
+        ApiError::Connect { source, .. } => assert!(matches!(*source, ApiError::NotJson(_))),
+        e => panic!("expected a connect error, got {:?}", e),
+    }
+}
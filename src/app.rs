@@ -1,20 +1,53 @@
-use crate::config::{Config, config_path};
+use crate::api::{
+    ApiError, BtSearchResult, CreateTaskOptions, DsApi, Folder, NO_DOWNLOAD_STATION_ACCESS,
+    RssFeedItem, RssSite, ScheduleConfig, ServerConfig, Statistics, is_error_status,
+    is_retriable_status, status_label, task_error_description, task_file_mime,
+};
+use crate::config::{Config, ServerProfile, config_path};
 use crate::event::{AppEvent, Event, EventHandler, TICK_FPS};
+use crate::export::{save_task_details, task_details_text};
+use crate::keys::{Action, KeyBinding, KeyMap, keymap, keys_for};
+use crate::theme::Theme;
+use crate::torrent::parse_torrent;
+use crate::util::{
+    desktop_notification, file_station_url, format_bytes, format_speed, fuzzy_match,
+    normalize_server_url, open_in_browser, parse_speed_limit, set_clipboard, validate_url,
+};
+use anyhow::Context;
 use ratatui::{
     DefaultTerminal,
-    crossterm::event::{KeyCode, KeyEvent},
-    crossterm::{cursor, execute},
-    style::{Color, Style},
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    crossterm::{
+        cursor,
+        event::{
+            DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+            MouseButton, MouseEvent, MouseEventKind,
+        },
+        execute,
+        style::Print,
+        terminal::{
+            EnterAlternateScreen, LeaveAlternateScreen, SetTitle, disable_raw_mode, enable_raw_mode,
+        },
+    },
+    layout::{Position, Rect},
+    style::Style,
     widgets::{Block, BorderType, TableState},
 };
-use ratatui_explorer::{FileExplorer, FileExplorerBuilder, Theme};
+use ratatui_explorer::{FileExplorer, FileExplorerBuilder, Theme as ExplorerTheme};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::stdout;
-use syno_download_station::{
-    client::SynoDS,
-    entities::{Task, TaskStatus},
-};
-use tui_input::Input;
+use std::sync::atomic::{AtomicBool, Ordering};
+use syno_download_station::entities::{Task, TaskOperation, TaskStatus};
+use tokio::sync::broadcast;
+use tokio::time::{Duration, Instant, interval};
 use tui_input::backend::crossterm::EventHandler as InputEventHandler;
+use tui_input::{Input, InputRequest};
+
+// Number of speed samples kept for the statistics graphs
+pub const STATISTICS_HISTORY_LEN: usize = 300;
+
+// Number of download speed samples kept per task for the Transfer tab graph
+pub const TASK_SPEED_HISTORY_LEN: usize = 120;
 
 // Spinner frames
 pub const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
@@ -44,6 +77,22 @@ pub enum SortColumn {
     Status,
 }
 
+/// Columns of the task table, the shown set and their order comes from the config
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ColumnId {
+    Name,
+    Size,
+    Downloaded,
+    Uploaded,
+    Progress,
+    UploadSpeed,
+    DownloadSpeed,
+    Ratio,
+    Status,
+    Eta,
+    Added,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum SortOrder {
     Ascending,
@@ -56,63 +105,466 @@ pub enum ActivePanel {
     Info,
 }
 
+/// What a popup shows, which decides its size, colors and title
+#[derive(Debug, Clone, PartialEq)]
+pub enum PopupKind {
+    Help,
+    Error,
+    Details(String), // everything about a task, titled with its name
+}
+
 /// Enum for handling the popups
 #[derive(Debug, Clone)]
 pub struct PopupState {
     pub lines: Vec<String>,
-    pub error: bool, // true = red border, false = normal
+    pub kind: PopupKind,
     pub scroll: usize,
 }
 
+/// State of the RSS feed screen
+pub struct RssView {
+    pub sites: Vec<RssSite>,
+    pub items: Vec<RssFeedItem>,
+    pub selected_site: TableState,
+    pub selected_item: TableState,
+    pub items_focused: bool,
+}
+
+/// State of the BT search screen
+pub struct BtSearchView {
+    pub input: Input,
+    pub cursor_pos: Option<(u16, u16)>,
+    pub input_focused: bool,
+    pub task_id: Option<String>, // running search on the DiskStation
+    pub finished: bool,
+    pub results: Vec<BtSearchResult>,
+    pub selected: TableState,
+    pub poll_ticks: u64,
+}
+
+/// Global Download Station switches shown in the server settings popup
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ServerSetting {
+    Emule,
+    UnzipService,
+    Schedule,
+}
+
+impl ServerSetting {
+    pub const ALL: [ServerSetting; 3] = [
+        ServerSetting::Emule,
+        ServerSetting::UnzipService,
+        ServerSetting::Schedule,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ServerSetting::Emule => "eMule",
+            ServerSetting::UnzipService => "Auto unzip",
+            ServerSetting::Schedule => "Download schedule",
+        }
+    }
+}
+
+/// State of the server settings popup
+pub struct ServerSettingsView {
+    pub config: ServerConfig,
+    pub schedule: ScheduleConfig,
+    pub selected: TableState,
+}
+
+impl ServerSettingsView {
+    pub fn is_enabled(&self, setting: ServerSetting) -> bool {
+        match setting {
+            ServerSetting::Emule => self.config.emule_enabled,
+            ServerSetting::UnzipService => self.config.unzip_service_enabled,
+            ServerSetting::Schedule => self.schedule.enabled,
+        }
+    }
+}
+
+/// State of the destination folder browser
+pub struct FolderBrowser {
+    pub path: Option<String>, // folder being listed, None = the shared folders
+    pub folders: Vec<Folder>,
+    pub selected: TableState,
+}
+
+/// State of the server profile switcher
+pub struct ProfilePicker {
+    pub profiles: Vec<(String, String)>, // name and url
+    pub selected: TableState,
+}
+
+/// Fields of the settings editor, in display order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingsField {
+    Url,
+    Username,
+    Password,
+    RefreshInterval,
+    Destination,
+}
+
+impl SettingsField {
+    pub const ALL: [SettingsField; 5] = [
+        SettingsField::Url,
+        SettingsField::Username,
+        SettingsField::Password,
+        SettingsField::RefreshInterval,
+        SettingsField::Destination,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingsField::Url => "DiskStation URL",
+            SettingsField::Username => "Username",
+            SettingsField::Password => "Password",
+            SettingsField::RefreshInterval => "Auto-refresh (s)",
+            SettingsField::Destination => "Destination",
+        }
+    }
+}
+
+/// State of the settings editor for the connected profile
+pub struct SettingsEditor {
+    pub inputs: Vec<Input>, // one per `SettingsField::ALL` entry
+    pub focused: usize,
+    pub error: Option<String>, // why the last save was refused
+    pub cursor_pos: Option<(u16, u16)>,
+}
+
+impl SettingsEditor {
+    pub fn focused_input(&mut self) -> &mut Input {
+        &mut self.inputs[self.focused]
+    }
+}
+
+/// Fields of the add URL popup, in display order. Only the URL is required.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UrlField {
+    Url,
+    Username,
+    Password,
+    UnzipPassword,
+}
+
+impl UrlField {
+    pub const ALL: [UrlField; 4] = [
+        UrlField::Url,
+        UrlField::Username,
+        UrlField::Password,
+        UrlField::UnzipPassword,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            UrlField::Url => "URL",
+            UrlField::Username => "Username",
+            UrlField::Password => "Password",
+            UrlField::UnzipPassword => "Unzip password",
+        }
+    }
+}
+
+/// State of the add URL popup
+#[derive(Default)]
+pub struct UrlForm {
+    pub inputs: [Input; 4], // one per `UrlField::ALL` entry
+    pub focused: usize,
+}
+
+impl UrlForm {
+    pub fn value(&self, field: UrlField) -> &str {
+        let index = UrlField::ALL.iter().position(|f| *f == field).unwrap();
+        self.inputs[index].value()
+    }
+
+    pub fn focused_input(&mut self) -> &mut Input {
+        &mut self.inputs[self.focused]
+    }
+
+    /// The optional fields, passed on when creating the task
+    pub fn options(&self) -> CreateTaskOptions {
+        CreateTaskOptions {
+            username: self.value(UrlField::Username).trim().to_string(),
+            password: self.value(UrlField::Password).to_string(),
+            unzip_password: self.value(UrlField::UnzipPassword).to_string(),
+        }
+    }
+}
+
+/// Fields of the speed limit popup, in display order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeedLimitField {
+    Download,
+    Upload,
+}
+
+impl SpeedLimitField {
+    pub const ALL: [SpeedLimitField; 2] = [SpeedLimitField::Download, SpeedLimitField::Upload];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SpeedLimitField::Download => "Download (KB/s)",
+            SpeedLimitField::Upload => "Upload (KB/s)",
+        }
+    }
+}
+
+/// State of the speed limit popup of a single task
+pub struct SpeedLimitForm {
+    pub task_id: String,
+    pub title: String,
+    pub inputs: [Input; 2], // one per `SpeedLimitField::ALL` entry
+    pub focused: usize,
+    pub error: Option<String>, // why the last save was refused
+    pub cursor_pos: Option<(u16, u16)>,
+}
+
+impl SpeedLimitForm {
+    pub fn focused_input(&mut self) -> &mut Input {
+        &mut self.inputs[self.focused]
+    }
+}
+
+/// While no task is transferring, auto-refresh runs this many times less often
+const IDLE_REFRESH_FACTOR: u64 = 4;
+/// Longest interval the idle back-off goes to, in seconds
+const IDLE_REFRESH_MAX_SECS: u64 = 300;
+
+/// Number of title characters to type when confirming a typed delete
+const TYPED_DELETE_CHARS: usize = 5;
+
+/// Typed confirmation for deleting a large or seeding task
+pub struct TypedConfirm {
+    pub expected: String, // start of the task title
+    pub input: Input,
+    pub cursor_pos: Option<(u16, u16)>,
+}
+
+impl TypedConfirm {
+    pub fn matches(&self) -> bool {
+        self.input.value().trim().to_lowercase() == self.expected.to_lowercase()
+    }
+}
+
+/// Pause or resume sent for a task that already shows its new status
+pub struct PendingToggle {
+    pub task_id: String,
+    pub previous: TaskStatus, // put back if the request fails
+}
+
+/// Status a task shows right after `p` and the status it had, None when `p` doesn't apply to
+/// it. The old status is rebuilt as `TaskStatus` isn't `Clone`.
+fn toggled_status(task: &Task) -> Option<(TaskStatus, TaskStatus)> {
+    // A complete torrent seeds when resumed, showing it as downloading would make the next
+    // refresh announce it as a finished download
+    let complete = task
+        .additional
+        .as_ref()
+        .and_then(|a| a.transfer.as_ref())
+        .is_some_and(|t| task.size > 0 && t.size_downloaded >= task.size);
+    let resumed = if complete {
+        TaskStatus::Seeding
+    } else {
+        TaskStatus::Downloading
+    };
+    match task.status {
+        TaskStatus::Downloading => Some((TaskStatus::Paused, TaskStatus::Downloading)),
+        TaskStatus::Waiting => Some((TaskStatus::Paused, TaskStatus::Waiting)),
+        TaskStatus::Seeding => Some((TaskStatus::Paused, TaskStatus::Seeding)),
+        TaskStatus::Paused => Some((resumed, TaskStatus::Paused)),
+        TaskStatus::Finished => Some((resumed, TaskStatus::Finished)),
+        _ => None,
+    }
+}
+
 /// Enum for confirmation
 #[derive(Debug, Clone)]
 pub enum PendingAction {
-    DeleteTask(String), // stores the task id
+    DeleteTask(String),                           // stores the task id
+    AddFile { bytes: Vec<u8>, filename: String }, // a .torrent shown before uploading it
+    Quit,
 }
 
 pub struct App {
     pub running: bool,
     pub active_panel: ActivePanel,
-    pub headers: Vec<&'static str>,
-    pub refreshing_tasks: bool,
+    pub columns: Vec<ColumnId>,
     pub events: EventHandler,
     pub tabs: Vec<&'static str>,
     pub selected_tab: usize,
+    pub tab_per_task: bool, // restore the last tab of a task when selecting it
+    pub task_tabs: HashMap<String, usize>, // last tab of each task, with `tab_per_task`
     pub selected_task: TableState,
     pub selected_file: TableState,
     pub selected_peer: TableState,
     pub tasks: Vec<Task>,
-    pub client: Option<SynoDS>,
+    pub details_task_id: Option<String>, // task whose trackers, peers and files are loaded
+    pub details_loaded_at: Option<Instant>, // when they were, for counting down announces
+    pub api: Option<DsApi>,
     pub destination: String,
+    pub server_url: String,
     pub tick_count: u64,
     pub refresh_interval: Option<u64>, // number of ticks between refreshes, None means disabled
+    pub adaptive_refresh: bool,        // back off while no task is transferring
+    pub seed_ratio_target: Option<f64>, // seeding tasks are paused at this ratio
+    pub auto_paused: HashSet<String>,  // ids paused at the ratio target, never paused twice
+    pub refresh_paused: bool,          // auto-refresh paused at runtime, the interval is kept
     pub tracker_scroll: usize,
     pub peer_scroll: usize,
     pub file_scroll: usize,
-    pub tracker_count: usize,
-    pub peer_count: usize,
-    pub file_count: usize,
+    pub peer_count: usize, // rows in the Peers tab, including the "and N more" line
+    pub max_peers: usize,
     pub file_explorer: Option<FileExplorer>,
-    pub url_input: Option<Input>,
+    pub picker_message: Option<String>, // shown inside the file picker, e.g. unreadable directory
+    pub picker_show_all: bool,          // false hides files Download Station can't take
+    pub url_input: Option<UrlForm>,
     pub url_input_cursor_pos: Option<(u16, u16)>,
     pub popup: Option<PopupState>,
     // Tracking scrollable areas
     pub popup_inner_height: usize,
-    pub tracker_inner_height: usize,
+    pub popup_line_count: usize, // lines of the popup after wrapping
+    pub task_inner_height: usize,
+    // Screen areas from the last render, used for mouse hit testing
+    pub task_table_area: Rect,
+    pub tabs_area: Rect,
+    pub info_area: Rect,
+    pub tracker_max_scroll: usize, // set while rendering, trackers and files wrap
     pub peer_inner_height: usize,
-    pub file_inner_height: usize,
+    pub file_max_scroll: usize,
     pub pending_action: Option<PendingAction>,
+    pub pending_toggle: Option<PendingToggle>,
+    pub delete_confirm: Option<TypedConfirm>,
+    pub typed_delete_size: u64, // in bytes, 0 = typed confirmation disabled
+    pub confirm_add_above: u64, // in bytes, smaller .torrent files are added right away
     pub spinner_frame: usize,
-    pub loading: bool,
+    pub in_flight: Option<&'static str>, // request announced by send_busy, shown with the spinner
+    pub spinner_pos: Option<Position>,   // where the title spinner was drawn
     pub config_path: String,
     pub sort_column: SortColumn,
     pub sort_order: SortOrder,
     pub connection_status: ConnectionStatus,
     pub notification: Option<Notification>,
+    pub rss: Option<RssView>,
+    pub bt_search: Option<BtSearchView>,
+    pub server_settings: Option<ServerSettingsView>,
+    pub folder_browser: Option<FolderBrowser>,
+    pub profile_picker: Option<ProfilePicker>,
+    pub settings_editor: Option<SettingsEditor>,
+    pub speed_limit: Option<SpeedLimitForm>,
+    pub profile_name: String, // server profile the app is connected to
+    pub statistics: Option<Statistics>,
+    pub speed_history: VecDeque<(u64, u64)>, // total (download, upload) per refresh, oldest first
+    pub task_speed_history: HashMap<String, VecDeque<u64>>, // download speed per task id, oldest first
+    pub task_transferred: HashMap<String, (u64, u64)>, // downloaded / uploaded bytes at the last refresh
+    pub session_transferred: (u64, u64), // bytes downloaded / uploaded since dstui started
+    pub zero_speed_since: HashMap<String, Instant>, // downloading tasks at 0 B/s, since when
+    pub stalled_after: Option<Duration>, // at 0 B/s this long a download is shown as stalled
+    pub error_count: usize,              // tasks in an error state after the last refresh
     // Filtering settins
     pub filter_input: Option<Input>,
     pub filter_text: String,
+    pub fuzzy_filter: bool, // filter_text matches in order instead of as a substring
+    pub hide_finished: bool, // leave finished and seeding tasks out of the table
+    pub file_filter: String, // narrows the Files tab to file names containing it
+    pub filtering_files: bool, // the open filter input is the Files tab's
     pub filter_cursor_pos: Option<(u16, u16)>,
+    pub binary_units: bool,
+    pub use_local_time: bool, // task times in local time instead of UTC
+    pub table_percent: u16,   // share of the height for the task table
+    pub info_panel_hidden: bool,
+    pub confirm_quit: bool,         // ask before q / Esc quits
+    pub enable_notifications: bool, // desktop notification for completed downloads
+    pub terminal_title: bool,       // show task counts and speed in the terminal's title
+    pub keymap: KeyMap,             // keys of the main screen, defaults merged with [keybindings]
+    pub theme: Theme,
+}
+
+/// Whether the terminal's title was changed, see [`reset_terminal_title`]
+static TITLE_SET: AtomicBool = AtomicBool::new(false);
+
+/// Give the terminal its title back if `display.terminal_title` changed it. Terminals without
+/// a title stack get an empty one, the shell prompt usually sets its own again.
+pub fn reset_terminal_title() {
+    if TITLE_SET.swap(false, Ordering::Relaxed) {
+        let _ = execute!(stdout(), SetTitle(""), Print("\x1b[23;0t"));
+    }
+}
+
+/// Await `work` unless one of `quit_keys` is pressed first, which drops it and with it any
+/// request still waiting for the NAS. With `spinner` (position and frame), the next frames of
+/// the title spinner are drawn straight to the terminal meanwhile.
+///
+/// Returns the frame the spinner stopped at so the next render continues from there, or None
+/// when interrupted.
+async fn run_interruptible(
+    work: impl Future<Output = anyhow::Result<()>>,
+    spinner: Option<(Position, usize)>,
+    quit_keys: &[KeyBinding],
+    mut key_presses: broadcast::Receiver<KeyEvent>,
+) -> anyhow::Result<Option<usize>> {
+    let mut ticker = interval(Duration::from_secs_f64(1.0 / TICK_FPS));
+    let mut frame = spinner.map_or(0, |(_, frame)| frame);
+    tokio::pin!(work);
+    loop {
+        tokio::select! {
+            result = &mut work => return result.map(|_| Some(frame)),
+            Ok(key) = key_presses.recv() => {
+                if quit_keys.contains(&KeyBinding::from_event(&key)) {
+                    return Ok(None);
+                }
+            }
+            _ = ticker.tick(), if spinner.is_some() => {
+                let Some((pos, _)) = spinner else { continue };
+                frame = (frame + 1) % SPINNER_FRAMES.len();
+                execute!(
+                    stdout(),
+                    cursor::MoveTo(pos.x, pos.y),
+                    Print(SPINNER_FRAMES[frame])
+                )?;
+            }
+        }
+    }
+}
+
+/// File picker filter keeping directories and the files Download Station creates tasks from
+fn supported_files_only(file: ratatui_explorer::File) -> Option<ratatui_explorer::File> {
+    let supported = task_file_mime(&file.name).is_some();
+    (file.is_dir || supported).then_some(file)
+}
+
+/// How long a download has to sit at 0 B/s to count as stalled, None when disabled
+fn stalled_after(config: &Config) -> Option<Duration> {
+    Some(config.downloads.stalled_after_secs)
+        .filter(|&secs| secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// Add what each task transferred since `previous`, its downloaded / uploaded bytes at the last
+/// refresh, to `session` and return the bytes for the next refresh. Tasks seen for the first
+/// time only set a baseline, and a counter that went down started over, so its whole value is
+/// new. Deleted tasks drop out, their bytes stay counted.
+fn count_transferred(
+    tasks: &[Task],
+    previous: &HashMap<String, (u64, u64)>,
+    session: &mut (u64, u64),
+) -> HashMap<String, (u64, u64)> {
+    let mut transferred = HashMap::with_capacity(tasks.len());
+    for task in tasks {
+        let Some(t) = task.additional.as_ref().and_then(|a| a.transfer.as_ref()) else {
+            continue;
+        };
+        let now = (t.size_downloaded, t.size_uploaded);
+        if let Some(&before) = previous.get(&task.id) {
+            let delta = |now: u64, before: u64| now.checked_sub(before).unwrap_or(now);
+            session.0 += delta(now.0, before.0);
+            session.1 += delta(now.1, before.1);
+        }
+        transferred.insert(task.id.clone(), now);
+    }
+    transferred
 }
 
 fn move_next(state: &mut TableState, row_count: usize) {
@@ -135,16 +587,10 @@ fn move_previous(state: &mut TableState) {
 }
 
 impl App {
-    pub async fn new(config: Config) -> anyhow::Result<Self> {
-        let client = SynoDS::builder()
-            .url(&config.connection.url)
-            .username(&config.connection.username)
-            .password(&config.connection.password)
-            .danger_accept_invalid_certs(config.connection.accept_invalid_certs)
-            .timeout(config.connection.timeout_ms)
-            .build()?;
-
-        client.authorize().await?;
+    pub async fn new(config: Config, profile: usize) -> anyhow::Result<Self> {
+        let profile = &config.profiles[profile];
+        let api = DsApi::new(&profile.connection)?;
+        api.authorize().await?;
 
         let config_path = config_path()
             .map(|p| p.display().to_string())
@@ -152,9 +598,7 @@ impl App {
 
         let destination = config.downloads.destination.clone();
         let refresh_interval = config
-            .downloads
-            .refresh_interval
-            .filter(|&s| s > 0)
+            .refresh_interval(profile)
             .map(|s| (s as f64 * TICK_FPS) as u64);
 
         // Sorting setting from config file
@@ -163,69 +607,156 @@ impl App {
 
         let mut app = Self {
             running: true,
-            headers: vec![
-                "Name",
-                "Size",
-                "Downloaded",
-                "Uploaded",
-                "Progress",
-                "Upload Speed",
-                "Download Speed",
-                "Ratio",
-                "Status",
-            ],
+            columns: ColumnId::from_config(&config.display.columns),
             active_panel: ActivePanel::Tasks,
-            refreshing_tasks: false,
             events: EventHandler::new(),
-            tabs: vec!["General", "Transfer", "Tracker", "Peers", "Files"],
+            tabs: vec![
+                "General",
+                "Transfer",
+                "Tracker",
+                "Peers",
+                "Files",
+                "Statistics",
+            ],
             selected_tab: 0,
+            tab_per_task: config.display.tab_per_task,
+            task_tabs: HashMap::new(),
             selected_task: TableState::default(),
             selected_file: TableState::default(),
             selected_peer: TableState::default(),
             tasks: vec![],
-            client: Some(client),
+            api: Some(api),
             destination,
+            server_url: profile.connection.url.clone(),
             tick_count: 0,
             refresh_interval,
+            adaptive_refresh: config.downloads.adaptive_refresh,
+            seed_ratio_target: config.downloads.seed_ratio_target.filter(|&r| r > 0.0),
+            auto_paused: HashSet::new(),
+            refresh_paused: false,
+            details_task_id: None,
+            details_loaded_at: None,
             tracker_scroll: 0,
             peer_scroll: 0,
             file_scroll: 0,
-            tracker_count: 0,
             peer_count: 0,
-            file_count: 0,
+            max_peers: config.display.max_peers,
             file_explorer: None,
+            picker_message: None,
+            picker_show_all: false,
             url_input: None,
             popup: None,
             url_input_cursor_pos: None,
             // Scrollable areas custom defaults
             popup_inner_height: 5,
-            tracker_inner_height: 5,
+            popup_line_count: 0,
+            task_inner_height: 5,
+            task_table_area: Rect::default(),
+            tabs_area: Rect::default(),
+            info_area: Rect::default(),
+            tracker_max_scroll: 0,
             peer_inner_height: 5,
-            file_inner_height: 5,
+            file_max_scroll: 0,
             pending_action: None,
+            pending_toggle: None,
+            delete_confirm: None,
+            typed_delete_size: config
+                .downloads
+                .typed_delete_size_mb
+                .saturating_mul(1_000_000),
+            confirm_add_above: config.downloads.confirm_add_above_bytes,
             spinner_frame: 0,
-            loading: false,
+            in_flight: None,
+            spinner_pos: None,
             config_path,
             sort_column,
             sort_order,
             connection_status: ConnectionStatus::Connected,
             notification: None,
+            rss: None,
+            bt_search: None,
+            server_settings: None,
+            folder_browser: None,
+            profile_picker: None,
+            settings_editor: None,
+            speed_limit: None,
+            profile_name: profile.name.clone(),
+            statistics: None,
+            speed_history: VecDeque::with_capacity(STATISTICS_HISTORY_LEN),
+            task_speed_history: HashMap::new(),
+            task_transferred: HashMap::new(),
+            session_transferred: (0, 0),
+            zero_speed_since: HashMap::new(),
+            stalled_after: stalled_after(&config),
+            error_count: 0,
             filter_input: None,
             filter_text: String::new(),
+            fuzzy_filter: config.display.fuzzy_filter,
+            hide_finished: config.display.hide_finished,
+            file_filter: String::new(),
+            filtering_files: false,
             filter_cursor_pos: None,
+            binary_units: config.display.binary_units,
+            use_local_time: config.display.use_local_time,
+            table_percent: config.display.table_percent.clamp(10, 100),
+            info_panel_hidden: false,
+            confirm_quit: config.display.confirm_quit,
+            enable_notifications: config.display.enable_notifications,
+            terminal_title: config.display.terminal_title,
+            keymap: keymap(&config.keybindings)?,
+            theme: Theme::from_name(&config.display.theme),
         };
 
+        let unknown = ColumnId::unknown(&config.display.columns);
+        if !unknown.is_empty() {
+            app.show_notification(format!(
+                "Unknown column {} in the config, showing the default columns",
+                unknown.join(", ")
+            ));
+        }
+
         app.refresh_tasks().await?;
+        app.refresh_statistics().await;
         Ok(app)
     }
 
     pub async fn run(mut self, mut terminal: DefaultTerminal) -> anyhow::Result<()> {
+        // Pasted text arrives as a single event instead of a burst of key presses
+        execute!(stdout(), EnableBracketedPaste, EnableMouseCapture)?;
+        self.update_terminal_title();
+
         while self.running {
             terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
 
-            // Show blinking cursor when URL input or filter is active, hide otherwise
-            if self.url_input.is_some() || self.filter_input.is_some() {
-                let pos = self.url_input_cursor_pos.or(self.filter_cursor_pos);
+            // Show blinking cursor when an input field is active, hide otherwise
+            let search_cursor_pos = self
+                .bt_search
+                .as_ref()
+                .filter(|search| search.input_focused)
+                .and_then(|search| search.cursor_pos);
+            let confirm_cursor_pos = self
+                .delete_confirm
+                .as_ref()
+                .and_then(|confirm| confirm.cursor_pos);
+            let settings_cursor_pos = self
+                .settings_editor
+                .as_ref()
+                .and_then(|editor| editor.cursor_pos);
+            let speed_limit_cursor_pos = self.speed_limit.as_ref().and_then(|form| form.cursor_pos);
+            if self.url_input.is_some()
+                || self.filter_input.is_some()
+                || search_cursor_pos.is_some()
+                || confirm_cursor_pos.is_some()
+                || settings_cursor_pos.is_some()
+                || speed_limit_cursor_pos.is_some()
+            {
+                let pos = self
+                    .url_input_cursor_pos
+                    .or(self.filter_cursor_pos)
+                    .or(search_cursor_pos)
+                    .or(confirm_cursor_pos)
+                    .or(settings_cursor_pos)
+                    .or(speed_limit_cursor_pos);
                 if let Some((x, y)) = pos {
                     execute!(
                         stdout(),
@@ -238,811 +769,3156 @@ impl App {
                 execute!(stdout(), cursor::Hide)?;
             }
 
-            match self.events.next().await? {
-                Event::Tick => self.tick().await?,
+            let event = self.events.next().await?;
+            // Both ticks and app events can wait on the NAS, the quit keys work meanwhile
+            let quit_keys = self.interrupt_keys();
+            let key_presses = self.events.key_presses();
+            match event {
+                Event::Tick => {
+                    if run_interruptible(self.tick(), None, &quit_keys, key_presses)
+                        .await?
+                        .is_none()
+                    {
+                        self.quit();
+                    }
+                }
                 Event::Crossterm(event) => match event {
                     crossterm::event::Event::Key(key_event)
                         if key_event.kind == crossterm::event::KeyEventKind::Press =>
                     {
                         self.handle_key_events(key_event)?
                     }
+                    crossterm::event::Event::Paste(text) => self.handle_paste(&text),
+                    crossterm::event::Event::Mouse(mouse_event) => {
+                        self.handle_mouse_events(mouse_event)
+                    }
                     _ => {}
                 },
-                Event::App(app_event) => match app_event {
-                    AppEvent::Quit => self.quit(),
-                    AppEvent::Refresh => {
-                        match self.refresh_tasks().await {
-                            Ok(_) => {}
-                            Err(e) => {
-                                self.show_popup(
-                                    vec!["Failed to refresh tasks:".into(), e.to_string()],
-                                    true,
-                                );
-                            }
-                        }
-                        self.loading = false;
-                    }
-                    AppEvent::Next => self.next_task_row(),
-                    AppEvent::Previous => self.previous_task_row(),
-                    AppEvent::OpenFilePicker => self.open_file_picker(),
-                    AppEvent::SubmitFile => {
-                        if let Err(e) = self.submit_selected_file().await {
-                            self.show_popup(
-                                vec!["Failed to submit file:".into(), e.to_string()],
-                                true,
-                            );
-                        }
-                    }
-                    AppEvent::OpenUrlInput => self.open_url_input(),
-                    AppEvent::SubmitUrl => {
-                        if let Err(e) = self.submit_url().await {
-                            self.show_popup(
-                                vec!["Failed to submit URL:".into(), e.to_string()],
-                                true,
-                            );
-                        }
-                    }
-                    AppEvent::ToggleTask => {
-                        if let Err(e) = self.toggle_task().await {
-                            self.show_popup(
-                                vec!["Failed to toggle task status:".into(), e.to_string()],
-                                true,
-                            );
-                        }
-                    }
-                    AppEvent::CompleteTask => {
-                        if let Err(e) = self.complete_task().await {
-                            self.show_popup(
-                                vec!["Failed to complete task:".into(), e.to_string()],
-                                true,
-                            );
-                        }
-                    }
-                    AppEvent::ClearCompleted => {
-                        if let Err(e) = self.clear_completed().await {
-                            self.show_popup(
-                                vec!["Failed to clear completed task(s):".into(), e.to_string()],
-                                true,
-                            );
-                        }
+                Event::App(AppEvent::EditConfigFile) => {
+                    self.edit_config_file(&mut terminal).await?
+                }
+                Event::App(app_event) => {
+                    // The title showed what send_busy announced, keep the spinner turning
+                    // while the request runs since nothing is drawn until it returns
+                    let spinner = self
+                        .in_flight
+                        .take()
+                        .and(self.spinner_pos)
+                        .map(|pos| (pos, self.spinner_frame));
+                    let handled = self.handle_app_event(app_event);
+                    match run_interruptible(handled, spinner, &quit_keys, key_presses).await? {
+                        Some(frame) => self.spinner_frame = frame,
+                        None => self.quit(),
                     }
-                    AppEvent::PopUp => self.show_popup(
-                        vec![
-                            String::new(),
-                            "Help:".into(),
-                            "j / k     — navigate tasks and info panel rows, scroll help text, navigate in the file picker"
-                                .into(),
-                            "h / l     — navigate tabs left and right when the info panel is active, navigate in the file picker".into(),
-                            "p         — pause / resume selected task".into(),
-                            "c         — complete selected task".into(),
-                            "C         — clear completed tasks".into(),
-                            "a         — add file (.torrent, .nzb and .txt is supported)".into(),
-                            "A         — add task by URL".into(),
-                            "d         — delete selected task".into(),
-                            "r         — manually refresh tasks".into(),
-                            "R         — reload config (only applies to destination, refresh and sort settings)".into(),
-                            "1-9       — sort by column (again to reverse)".into(),
-                            "Tab       — switch panels".into(),
-                            "/         — filter tasks by name".into(),
-                            "x         — clear active filter".into(),
-                            "?         — toggle this help popup".into(),
-                            "q / Esc   — quit".into(),
-                            String::new(),
-                            format!("Config:   {}", self.config_path),
-                        ],
-                        false,
-                    ),
-                    AppEvent::DeleteTask => self.request_delete_task(),
-                    AppEvent::ConfirmAction => self.confirm_action().await?,
-                    AppEvent::CancelAction => self.cancel_action(),
-                    AppEvent::ReloadConfig => self.reload_config().await?,
-                    AppEvent::OpenFilter => self.open_filter(),
-                    AppEvent::ApplyFilter => self.apply_filter(),
-                },
+                }
             }
         }
 
         // Make sure cursor is restored when app exits
-        execute!(stdout(), cursor::Show, cursor::EnableBlinking)?;
+        execute!(
+            stdout(),
+            cursor::Show,
+            cursor::EnableBlinking,
+            DisableBracketedPaste,
+            DisableMouseCapture
+        )?;
         Ok(())
     }
 
-    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> anyhow::Result<()> {
-        // Confirmation popup blocks all other input
-        if self.pending_action.is_some() {
-            match key_event.code {
-                KeyCode::Char('y') | KeyCode::Enter => self.events.send(AppEvent::ConfirmAction),
-                KeyCode::Char('n') | KeyCode::Esc => self.events.send(AppEvent::CancelAction),
-                _ => {}
+    async fn handle_app_event(&mut self, app_event: AppEvent) -> anyhow::Result<()> {
+        match app_event {
+            AppEvent::Quit => self.quit(),
+            AppEvent::Refresh => {
+                match self.refresh_tasks().await {
+                    Ok(_) => {}
+                    Err(e) => {
+                        self.show_popup(
+                            vec!["Failed to refresh tasks:".into(), e.to_string()],
+                            true,
+                        );
+                    }
+                }
+                self.refresh_statistics().await;
+                self.update_terminal_title();
             }
-            return Ok(());
-        }
-
-        // Popup is the next in the blocking chain
-        if let Some(popup) = &mut self.popup {
-            match key_event.code {
-                KeyCode::Esc => self.close_popup(),
-                KeyCode::Char('j') => {
-                    let max = popup.lines.len().saturating_sub(self.popup_inner_height);
-                    popup.scroll = (popup.scroll + 1).min(max);
+            AppEvent::Next => self.next_task_row(),
+            AppEvent::Previous => self.previous_task_row(),
+            AppEvent::OpenFilePicker => self.open_file_picker(),
+            AppEvent::SubmitFile => {
+                if let Err(e) = self.submit_selected_file().await {
+                    self.show_popup(vec!["Failed to submit file:".into(), e.to_string()], true);
                 }
-                KeyCode::Char('k') => {
-                    popup.scroll = popup.scroll.saturating_sub(1);
+            }
+            AppEvent::OpenUrlInput => self.open_url_input(),
+            AppEvent::SubmitUrl => {
+                if let Err(e) = self.submit_url().await {
+                    self.show_popup(vec!["Failed to submit URL:".into(), e.to_string()], true);
                 }
-                _ => {}
             }
-            return Ok(());
-        }
-
-        // Then we will handle the file picker
-        if let Some(explorer) = &mut self.file_explorer {
-            match key_event.code {
-                KeyCode::Enter => self.events.send(AppEvent::SubmitFile),
-                KeyCode::Esc => self.file_explorer = None,
-                _ => {
-                    explorer
-                        .handle(&crossterm::event::Event::Key(key_event))
-                        .unwrap();
+            AppEvent::ToggleTask => {
+                if let Err(e) = self.toggle_task().await {
+                    self.show_popup(
+                        vec!["Failed to toggle task status:".into(), e.to_string()],
+                        true,
+                    );
                 }
             }
-            return Ok(());
-        }
-
-        // Then we will handle the filtering input field
-        if self.filter_input.is_some() {
-            match key_event.code {
-                KeyCode::Enter => self.events.send(AppEvent::ApplyFilter),
-                KeyCode::Esc => self.filter_input = None,
-                _ => {
-                    if let Some(input) = &mut self.filter_input {
-                        input.handle_event(&crossterm::event::Event::Key(key_event));
-                    }
+            AppEvent::RetryTask => {
+                if let Err(e) = self.retry_task().await {
+                    self.show_popup(
+                        vec!["Failed to retry the task:".into(), e.to_string()],
+                        true,
+                    );
                 }
             }
-            return Ok(());
-        }
-
-        // Then the URL input gets priority when open
-        if self.url_input.is_some() {
-            match key_event.code {
-                KeyCode::Enter => self.events.send(AppEvent::SubmitUrl),
-                KeyCode::Esc => self.url_input = None,
-                _ => {
-                    if let Some(input) = &mut self.url_input {
-                        input.handle_event(&crossterm::event::Event::Key(key_event));
-                    }
+            AppEvent::RetryErrors => {
+                if let Err(e) = self.retry_errors().await {
+                    self.show_popup(vec!["Failed to retry tasks:".into(), e.to_string()], true);
                 }
             }
-            return Ok(());
-        }
-
-        // Finally, normal key handling
-        match key_event.code {
-            KeyCode::Esc | KeyCode::Char('q') => self.events.send(AppEvent::Quit),
-            KeyCode::Char('?') => self.events.send(AppEvent::PopUp),
-            KeyCode::Char('r') => self.events.send(AppEvent::Refresh),
-            KeyCode::Char('a') => self.events.send(AppEvent::OpenFilePicker),
-            KeyCode::Char('A') => self.events.send(AppEvent::OpenUrlInput),
-            KeyCode::Char('j') if self.active_panel == ActivePanel::Tasks => {
-                self.events.send(AppEvent::Next)
+            AppEvent::PauseAll => {
+                if let Err(e) = self.pause_all().await {
+                    self.show_popup(vec!["Failed to pause tasks:".into(), e.to_string()], true);
+                }
             }
-            KeyCode::Char('k') if self.active_panel == ActivePanel::Tasks => {
-                self.events.send(AppEvent::Previous)
+            AppEvent::ResumeAll => {
+                if let Err(e) = self.resume_all().await {
+                    self.show_popup(vec!["Failed to resume tasks:".into(), e.to_string()], true);
+                }
             }
-            KeyCode::Char('h') if self.active_panel == ActivePanel::Info => {
-                self.selected_tab = self.selected_tab.saturating_sub(1);
+            AppEvent::PauseSeeds => {
+                if let Err(e) = self.pause_seeds().await {
+                    self.show_popup(vec!["Failed to pause seeds:".into(), e.to_string()], true);
+                }
             }
-            KeyCode::Char('l') if self.active_panel == ActivePanel::Info => {
-                self.selected_tab = (self.selected_tab + 1).min(self.tabs.len() - 1);
+            AppEvent::ResumeSeeds => {
+                if let Err(e) = self.resume_seeds().await {
+                    self.show_popup(vec!["Failed to resume seeds:".into(), e.to_string()], true);
+                }
             }
-            KeyCode::Char('j') if self.active_panel == ActivePanel::Info => {
-                self.scroll_info_down();
+            AppEvent::CompleteTask => {
+                if let Err(e) = self.complete_task().await {
+                    self.show_popup(vec!["Failed to complete task:".into(), e.to_string()], true);
+                }
             }
-            KeyCode::Char('k') if self.active_panel == ActivePanel::Info => {
-                self.scroll_info_up();
+            AppEvent::ClearCompleted => {
+                if let Err(e) = self.clear_completed().await {
+                    self.show_popup(
+                        vec!["Failed to clear completed task(s):".into(), e.to_string()],
+                        true,
+                    );
+                }
             }
-            KeyCode::Char('p') => self.events.send(AppEvent::ToggleTask),
-            KeyCode::Char('c') => self.events.send(AppEvent::CompleteTask),
-            KeyCode::Char('C') => self.events.send(AppEvent::ClearCompleted),
-            KeyCode::Tab => {
-                self.active_panel = match self.active_panel {
-                    ActivePanel::Tasks => ActivePanel::Info,
-                    ActivePanel::Info => ActivePanel::Tasks,
+            AppEvent::PopUp => self.show_help(),
+            AppEvent::DeleteTask => self.request_delete_task(),
+            AppEvent::ConfirmAction => self.confirm_action(false).await?,
+            AppEvent::ConfirmForceAction => self.confirm_action(true).await?,
+            AppEvent::CancelAction => self.cancel_action(),
+            AppEvent::ReloadConfig => self.reload_config().await?,
+            AppEvent::OpenFilter => self.open_filter(),
+            AppEvent::ApplyFilter => self.apply_filter(),
+            AppEvent::CopyUri => self.copy_task_uri(),
+            AppEvent::CopyTitle => self.copy_task_title(),
+            AppEvent::ShowDetails => {
+                if let Err(e) = self.show_details().await {
+                    self.show_popup(
+                        vec!["Failed to load the task details:".into(), e.to_string()],
+                        true,
+                    );
                 }
             }
-            KeyCode::Char('d') => self.events.send(AppEvent::DeleteTask),
-            // Key for reloading config file manually
-            KeyCode::Char('R') => self.events.send(AppEvent::ReloadConfig),
-            // Filtering shortcuts
-            KeyCode::Char('/') => self.events.send(AppEvent::OpenFilter),
-            KeyCode::Char('x')
-                if !self.filter_text.is_empty() && self.active_panel == ActivePanel::Tasks =>
-            {
-                self.clear_filter();
+            AppEvent::ExportTask => {
+                if let Err(e) = self.export_task().await {
+                    self.show_popup(
+                        vec!["Failed to export the task:".into(), e.to_string()],
+                        true,
+                    );
+                }
             }
-            // Keys for sorting the columns
-            KeyCode::Char('1') => self.sort_by(SortColumn::Name),
-            KeyCode::Char('2') => self.sort_by(SortColumn::Size),
-            KeyCode::Char('3') => self.sort_by(SortColumn::Downloaded),
-            KeyCode::Char('4') => self.sort_by(SortColumn::Uploaded),
-            KeyCode::Char('5') => self.sort_by(SortColumn::Progress),
-            KeyCode::Char('6') => self.sort_by(SortColumn::UploadSpeed),
-            KeyCode::Char('7') => self.sort_by(SortColumn::DownloadSpeed),
-            KeyCode::Char('8') => self.sort_by(SortColumn::Ratio),
-            KeyCode::Char('9') => self.sort_by(SortColumn::Status),
-            _ => {}
-        }
-        Ok(())
-    }
-
-    pub async fn tick(&mut self) -> anyhow::Result<()> {
-        if self.loading {
-            self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
-        }
-
+            AppEvent::OpenDestination => self.open_destination(),
+            AppEvent::OpenRss => {
+                if let Err(e) = self.open_rss().await {
+                    self.show_popup(
+                        vec!["Failed to load RSS sites:".into(), e.to_string()],
+                        true,
+                    );
+                }
+            }
+            AppEvent::LoadRssItems => {
+                if let Err(e) = self.load_rss_items().await {
+                    self.show_popup(vec!["Failed to load RSS feed:".into(), e.to_string()], true);
+                }
+            }
+            AppEvent::DownloadRssItem => {
+                if let Err(e) = self.download_rss_item().await {
+                    self.show_popup(vec!["Failed to add RSS item:".into(), e.to_string()], true);
+                }
+            }
+            AppEvent::OpenBtSearch => self.open_bt_search(),
+            AppEvent::StartBtSearch => {
+                if let Err(e) = self.start_bt_search().await {
+                    self.show_popup(
+                        vec!["Failed to start BT search:".into(), e.to_string()],
+                        true,
+                    );
+                }
+            }
+            AppEvent::PollBtSearch => {
+                if let Err(e) = self.poll_bt_search().await {
+                    // Stop polling, otherwise the error would pop up every second
+                    if let Some(search) = &mut self.bt_search {
+                        search.finished = true;
+                    }
+                    self.show_popup(
+                        vec!["Failed to load BT search results:".into(), e.to_string()],
+                        true,
+                    );
+                }
+            }
+            AppEvent::DownloadBtSearchResult => {
+                if let Err(e) = self.download_bt_search_result().await {
+                    self.show_popup(
+                        vec!["Failed to add search result:".into(), e.to_string()],
+                        true,
+                    );
+                }
+            }
+            AppEvent::CloseBtSearch => self.close_bt_search().await,
+            // Handled by the event loop, the editor needs the terminal
+            AppEvent::EditConfigFile => {}
+            AppEvent::OpenSettingsEditor => {
+                if let Err(e) = self.open_settings_editor() {
+                    self.show_popup(vec!["Failed to load settings:".into(), e.to_string()], true);
+                }
+            }
+            AppEvent::SaveSettings => {
+                if let Err(e) = self.save_settings().await {
+                    self.show_popup(vec!["Failed to save settings:".into(), e.to_string()], true);
+                }
+            }
+            AppEvent::OpenSpeedLimit => self.open_speed_limit(),
+            AppEvent::SaveSpeedLimit => {
+                if let Err(e) = self.save_speed_limit().await {
+                    self.show_popup(
+                        vec!["Failed to set the speed limit:".into(), e.to_string()],
+                        true,
+                    );
+                }
+            }
+            AppEvent::OpenProfilePicker => {
+                if let Err(e) = self.open_profile_picker() {
+                    self.show_popup(vec!["Failed to load profiles:".into(), e.to_string()], true);
+                }
+            }
+            AppEvent::SwitchProfile => {
+                if let Err(e) = self.switch_profile().await {
+                    self.show_popup(vec!["Failed to switch server:".into(), e.to_string()], true);
+                }
+            }
+            AppEvent::Reconnect => {
+                if let Err(e) = self.reconnect().await {
+                    self.connection_status = ConnectionStatus::Disconnected;
+                    self.show_popup(vec!["Failed to reconnect:".into(), e.to_string()], true);
+                }
+            }
+            AppEvent::OpenFolderBrowser => {
+                self.folder_browser = Some(FolderBrowser {
+                    path: None,
+                    folders: vec![],
+                    selected: TableState::default(),
+                });
+                self.events.send(AppEvent::LoadFolder);
+            }
+            AppEvent::LoadFolder => {
+                if let Err(e) = self.load_folder().await {
+                    self.show_popup(vec!["Failed to list folders:".into(), e.to_string()], true);
+                }
+            }
+            AppEvent::ChooseDestination => {
+                if let Err(e) = self.choose_destination() {
+                    self.show_popup(
+                        vec!["Failed to save destination:".into(), e.to_string()],
+                        true,
+                    );
+                }
+            }
+            AppEvent::OpenServerSettings => {
+                if let Err(e) = self.open_server_settings().await {
+                    let mut lines = vec!["Failed to load server settings:".into(), e.to_string()];
+                    if e.downcast_ref::<ApiError>()
+                        .is_some_and(ApiError::is_permission_denied)
+                    {
+                        lines.push(NO_DOWNLOAD_STATION_ACCESS.into());
+                    }
+                    self.show_popup(lines, true);
+                }
+            }
+            AppEvent::ToggleServerSetting => {
+                if let Err(e) = self.toggle_server_setting().await {
+                    let mut lines = vec!["Failed to change setting:".into(), e.to_string()];
+                    if e.downcast_ref::<ApiError>()
+                        .is_some_and(ApiError::is_permission_denied)
+                    {
+                        lines.push(
+                            "Changing Download Station settings requires an administrator account."
+                                .into(),
+                        );
+                    }
+                    self.show_popup(lines, true);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Send an event for a request that can take a while, the title shows `label` with a
+    /// spinner until it is done
+    pub fn send_busy(&mut self, event: AppEvent, label: &'static str) {
+        self.in_flight = Some(label);
+        self.events.send(event);
+    }
+
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> anyhow::Result<()> {
+        // Typed confirmation takes every key, y / d would be part of the title otherwise
+        if let Some(confirm) = &mut self.delete_confirm {
+            match key_event.code {
+                KeyCode::Esc => self.events.send(AppEvent::CancelAction),
+                KeyCode::Char('d')
+                    if key_event.modifiers.contains(KeyModifiers::CONTROL) && confirm.matches() =>
+                {
+                    self.send_busy(AppEvent::ConfirmForceAction, "Deleting")
+                }
+                KeyCode::Enter if confirm.matches() => {
+                    self.send_busy(AppEvent::ConfirmAction, "Deleting")
+                }
+                KeyCode::Enter => {}
+                _ => {
+                    confirm
+                        .input
+                        .handle_event(&crossterm::event::Event::Key(key_event));
+                }
+            }
+            return Ok(());
+        }
+
+        // Confirmation popup blocks all other input
+        if let Some(action) = &self.pending_action {
+            let deleting = matches!(action, PendingAction::DeleteTask(_));
+            match key_event.code {
+                KeyCode::Char('y') | KeyCode::Enter
+                    if matches!(action, PendingAction::AddFile { .. }) =>
+                {
+                    self.send_busy(AppEvent::ConfirmAction, "Adding")
+                }
+                KeyCode::Char('y') | KeyCode::Enter if !deleting => {
+                    self.events.send(AppEvent::ConfirmAction)
+                }
+                KeyCode::Char('y') | KeyCode::Char('d') | KeyCode::Enter if deleting => {
+                    self.send_busy(AppEvent::ConfirmAction, "Deleting")
+                }
+                KeyCode::Char('D') if deleting => {
+                    self.send_busy(AppEvent::ConfirmForceAction, "Deleting")
+                }
+                KeyCode::Char('n') | KeyCode::Esc => self.events.send(AppEvent::CancelAction),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Popup is the next in the blocking chain
+        if let Some(popup) = &mut self.popup {
+            match key_event.code {
+                KeyCode::Esc => self.close_popup(),
+                KeyCode::Char('j') => {
+                    let max = self
+                        .popup_line_count
+                        .saturating_sub(self.popup_inner_height);
+                    popup.scroll = (popup.scroll + 1).min(max);
+                }
+                KeyCode::Char('k') => {
+                    popup.scroll = popup.scroll.saturating_sub(1);
+                }
+                KeyCode::Char('y') if popup.kind == PopupKind::Error => {
+                    let text = popup.lines.join("\n").trim().to_string();
+                    match set_clipboard(&text) {
+                        Ok(_) => self.show_notification("✓ Error copied to clipboard".to_string()),
+                        Err(e) => self.show_notification(format!("Failed to copy error: {}", e)),
+                    }
+                }
+                KeyCode::Char('y') => {
+                    let text = popup.lines.join("\n");
+                    match set_clipboard(&text) {
+                        Ok(_) => {
+                            self.show_notification("✓ Details copied to clipboard".to_string())
+                        }
+                        Err(e) => self.show_notification(format!("Failed to copy details: {}", e)),
+                    }
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Then we will handle the file picker
+        if let Some(explorer) = &mut self.file_explorer {
+            match key_event.code {
+                // Directories are opened here instead of by the explorer, which drops the
+                // entry from its list when the directory can't be read
+                KeyCode::Enter | KeyCode::Char('l') | KeyCode::Right
+                    if explorer.current().is_dir =>
+                {
+                    self.open_picker_dir()
+                }
+                KeyCode::Enter => self.send_busy(AppEvent::SubmitFile, "Adding"),
+                KeyCode::Char('t') => self.toggle_picker_filter(),
+                KeyCode::Esc => {
+                    self.file_explorer = None;
+                    self.picker_message = None;
+                }
+                _ => {
+                    self.picker_message = explorer
+                        .handle(&crossterm::event::Event::Key(key_event))
+                        .err()
+                        .map(|e| e.to_string());
+                }
+            }
+            return Ok(());
+        }
+
+        // Then we will handle the filtering input field
+        if self.filter_input.is_some() && self.filtering_files {
+            // Applied while typing, Esc drops it again
+            match key_event.code {
+                KeyCode::Enter => self.filter_input = None,
+                KeyCode::Esc => self.clear_file_filter(),
+                _ => {
+                    if let Some(input) = &mut self.filter_input {
+                        input.handle_event(&crossterm::event::Event::Key(key_event));
+                        self.file_filter = input.value().to_string();
+                        self.file_scroll = 0;
+                    }
+                }
+            }
+            return Ok(());
+        }
+        if self.filter_input.is_some() {
+            match key_event.code {
+                KeyCode::Enter => self.events.send(AppEvent::ApplyFilter),
+                KeyCode::Esc => self.filter_input = None,
+                _ => {
+                    if let Some(input) = &mut self.filter_input {
+                        input.handle_event(&crossterm::event::Event::Key(key_event));
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        // Then the URL input gets priority when open
+        if self.url_input.is_some() {
+            match key_event.code {
+                // Ignore Enter until the URL is valid, the popup shows what is wrong
+                KeyCode::Enter => {
+                    if self
+                        .url_input
+                        .as_ref()
+                        .and_then(|form| validate_url(form.value(UrlField::Url)))
+                        .is_some()
+                    {
+                        self.send_busy(AppEvent::SubmitUrl, "Adding")
+                    }
+                }
+                KeyCode::Esc => self.url_input = None,
+                KeyCode::Tab | KeyCode::Down => {
+                    if let Some(form) = &mut self.url_input {
+                        form.focused = (form.focused + 1) % UrlField::ALL.len();
+                    }
+                }
+                KeyCode::BackTab | KeyCode::Up => {
+                    if let Some(form) = &mut self.url_input {
+                        form.focused =
+                            (form.focused + UrlField::ALL.len() - 1) % UrlField::ALL.len();
+                    }
+                }
+                _ => {
+                    if let Some(form) = &mut self.url_input {
+                        form.focused_input()
+                            .handle_event(&crossterm::event::Event::Key(key_event));
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        // The RSS screen takes over the main screen while it is open
+        if let Some(rss) = &mut self.rss {
+            match key_event.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('F') => self.rss = None,
+                KeyCode::Tab => rss.items_focused = !rss.items_focused,
+                KeyCode::Char('r') => self.events.send(AppEvent::OpenRss),
+                KeyCode::Char('j') if rss.items_focused => {
+                    move_next(&mut rss.selected_item, rss.items.len())
+                }
+                KeyCode::Char('k') if rss.items_focused => move_previous(&mut rss.selected_item),
+                KeyCode::Char('j') => {
+                    move_next(&mut rss.selected_site, rss.sites.len());
+                    self.events.send(AppEvent::LoadRssItems);
+                }
+                KeyCode::Char('k') => {
+                    move_previous(&mut rss.selected_site);
+                    self.events.send(AppEvent::LoadRssItems);
+                }
+                KeyCode::Enter if rss.items_focused => self.events.send(AppEvent::DownloadRssItem),
+                KeyCode::Enter => rss.items_focused = true,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Settings editor
+        if let Some(editor) = &mut self.settings_editor {
+            match key_event.code {
+                KeyCode::Esc => self.settings_editor = None,
+                KeyCode::Tab | KeyCode::Down => {
+                    editor.focused = (editor.focused + 1) % editor.inputs.len();
+                }
+                KeyCode::BackTab | KeyCode::Up => {
+                    editor.focused =
+                        (editor.focused + editor.inputs.len() - 1) % editor.inputs.len();
+                }
+                KeyCode::Enter => self.events.send(AppEvent::SaveSettings),
+                _ => {
+                    editor
+                        .focused_input()
+                        .handle_event(&crossterm::event::Event::Key(key_event));
+                }
+            }
+            return Ok(());
+        }
+
+        // Speed limit of the selected task
+        if let Some(form) = &mut self.speed_limit {
+            match key_event.code {
+                KeyCode::Esc => self.speed_limit = None,
+                KeyCode::Tab | KeyCode::Down | KeyCode::BackTab | KeyCode::Up => {
+                    form.focused = (form.focused + 1) % SpeedLimitField::ALL.len();
+                }
+                KeyCode::Enter => self.send_busy(AppEvent::SaveSpeedLimit, "Saving"),
+                _ => {
+                    form.focused_input()
+                        .handle_event(&crossterm::event::Event::Key(key_event));
+                }
+            }
+            return Ok(());
+        }
+
+        // Server profile switcher
+        if let Some(picker) = &mut self.profile_picker {
+            match key_event.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('N') => {
+                    self.profile_picker = None
+                }
+                KeyCode::Char('j') => move_next(&mut picker.selected, picker.profiles.len()),
+                KeyCode::Char('k') => move_previous(&mut picker.selected),
+                KeyCode::Enter => self.events.send(AppEvent::SwitchProfile),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Destination folder browser
+        if let Some(browser) = &mut self.folder_browser {
+            match key_event.code {
+                KeyCode::Esc | KeyCode::Char('q') => self.folder_browser = None,
+                KeyCode::Char('j') => move_next(&mut browser.selected, browser.folders.len()),
+                KeyCode::Char('k') => move_previous(&mut browser.selected),
+                KeyCode::Enter | KeyCode::Char('l') => {
+                    if let Some(folder) = browser
+                        .selected
+                        .selected()
+                        .and_then(|i| browser.folders.get(i))
+                    {
+                        browser.path = Some(folder.path.clone());
+                        self.events.send(AppEvent::LoadFolder);
+                    }
+                }
+                KeyCode::Char('h') | KeyCode::Backspace => {
+                    // Shared folders are the top level, their parent is the share list
+                    browser.path = browser
+                        .path
+                        .as_deref()
+                        .and_then(|path| path.rsplit_once('/'))
+                        .map(|(parent, _)| parent.to_string())
+                        .filter(|parent| !parent.is_empty());
+                    self.events.send(AppEvent::LoadFolder);
+                }
+                KeyCode::Char(' ') => self.events.send(AppEvent::ChooseDestination),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Server settings popup
+        if let Some(settings) = &mut self.server_settings {
+            match key_event.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('S') => {
+                    self.server_settings = None
+                }
+                KeyCode::Char('j') => move_next(&mut settings.selected, ServerSetting::ALL.len()),
+                KeyCode::Char('k') => move_previous(&mut settings.selected),
+                KeyCode::Char(' ') | KeyCode::Enter => {
+                    self.events.send(AppEvent::ToggleServerSetting)
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // The BT search screen, typing goes to the keyword field until results are focused
+        if let Some(search) = &mut self.bt_search {
+            match key_event.code {
+                KeyCode::Esc => self.events.send(AppEvent::CloseBtSearch),
+                KeyCode::Tab => search.input_focused = !search.input_focused,
+                KeyCode::Enter
+                    if search.input_focused && !search.input.value().trim().is_empty() =>
+                {
+                    self.events.send(AppEvent::StartBtSearch)
+                }
+                _ if search.input_focused => {
+                    search
+                        .input
+                        .handle_event(&crossterm::event::Event::Key(key_event));
+                }
+                KeyCode::Char('q') => self.events.send(AppEvent::CloseBtSearch),
+                KeyCode::Char('j') => move_next(&mut search.selected, search.results.len()),
+                KeyCode::Char('k') => move_previous(&mut search.selected),
+                KeyCode::Char('/') | KeyCode::Char('s') => search.input_focused = true,
+                KeyCode::Enter => self.events.send(AppEvent::DownloadBtSearchResult),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Finally, normal key handling through the configurable keymap
+        if let Some(action) = self.keymap.get(&KeyBinding::from_event(&key_event)) {
+            self.run_action(*action);
+        }
+        Ok(())
+    }
+
+    fn run_action(&mut self, action: Action) {
+        let tasks_active = self.active_panel == ActivePanel::Tasks;
+        match action {
+            Action::Quit if self.confirm_quit => self.request_quit(),
+            Action::Quit => self.events.send(AppEvent::Quit),
+            Action::Help => self.events.send(AppEvent::PopUp),
+            Action::RetryTask => self.send_busy(AppEvent::RetryTask, "Retrying"),
+            Action::RetryErrors => self.send_busy(AppEvent::RetryErrors, "Retrying"),
+            Action::Refresh => self.send_busy(AppEvent::Refresh, "Refreshing"),
+            Action::AddFile => self.events.send(AppEvent::OpenFilePicker),
+            Action::AddUrl => self.events.send(AppEvent::OpenUrlInput),
+            Action::Next if tasks_active => self.events.send(AppEvent::Next),
+            Action::Previous if tasks_active => self.events.send(AppEvent::Previous),
+            Action::Next => self.scroll_info_down(),
+            Action::Previous => self.scroll_info_up(),
+            Action::ScrollInfoDown if self.info_panel_visible() => self.scroll_info_down(),
+            Action::ScrollInfoUp if self.info_panel_visible() => self.scroll_info_up(),
+            Action::First if tasks_active => self.first_task_row(),
+            Action::Last if tasks_active => self.last_task_row(),
+            Action::NextError => self.jump_to_errored_task(),
+            Action::HalfPageDown if tasks_active => self.half_page_down(),
+            Action::HalfPageUp if tasks_active => self.half_page_up(),
+            Action::PreviousTab if !tasks_active => {
+                self.select_tab(self.selected_tab.saturating_sub(1));
+            }
+            Action::NextTab if !tasks_active => {
+                self.select_tab((self.selected_tab + 1).min(self.tabs.len() - 1));
+            }
+            Action::ToggleTask => self.start_toggle_task(),
+            Action::PauseAll => self.send_busy(AppEvent::PauseAll, "Pausing"),
+            Action::ResumeAll => self.send_busy(AppEvent::ResumeAll, "Resuming"),
+            Action::PauseSeeds => self.send_busy(AppEvent::PauseSeeds, "Pausing"),
+            Action::ResumeSeeds => self.send_busy(AppEvent::ResumeSeeds, "Resuming"),
+            Action::CompleteTask => self.send_busy(AppEvent::CompleteTask, "Completing"),
+            Action::ClearCompleted => self.send_busy(AppEvent::ClearCompleted, "Clearing"),
+            Action::SwitchPanel if self.info_panel_visible() => {
+                self.active_panel = match self.active_panel {
+                    ActivePanel::Tasks => ActivePanel::Info,
+                    ActivePanel::Info => ActivePanel::Tasks,
+                }
+            }
+            Action::ToggleInfoPanel => self.toggle_info_panel(),
+            Action::HideFinished => self.toggle_hide_finished(),
+            Action::DeleteTask => self.events.send(AppEvent::DeleteTask),
+            Action::CopyUri => self.events.send(AppEvent::CopyUri),
+            Action::CopyTitle => self.events.send(AppEvent::CopyTitle),
+            Action::ExportTask => self.events.send(AppEvent::ExportTask),
+            Action::Details => self.send_busy(AppEvent::ShowDetails, "Loading"),
+            Action::OpenDestination => self.events.send(AppEvent::OpenDestination),
+            Action::Rss => self.events.send(AppEvent::OpenRss),
+            Action::BtSearch => self.events.send(AppEvent::OpenBtSearch),
+            Action::ServerSettings => self.events.send(AppEvent::OpenServerSettings),
+            Action::Destination => self.events.send(AppEvent::OpenFolderBrowser),
+            Action::Profiles => self.events.send(AppEvent::OpenProfilePicker),
+            Action::EditSettings => self.events.send(AppEvent::OpenSettingsEditor),
+            Action::EditConfigFile => self.events.send(AppEvent::EditConfigFile),
+            Action::Reconnect => self.send_busy(AppEvent::Reconnect, "Reconnecting"),
+            Action::SpeedLimit => self.events.send(AppEvent::OpenSpeedLimit),
+            Action::ReloadConfig => self.events.send(AppEvent::ReloadConfig),
+            // Runtime auto-refresh control
+            Action::PauseRefresh => self.toggle_refresh_paused(),
+            Action::RefreshFaster => self.adjust_refresh_interval(true),
+            Action::RefreshSlower => self.adjust_refresh_interval(false),
+            // Filtering shortcuts
+            Action::Filter if !tasks_active && self.selected_tab == 4 => self.open_file_filter(),
+            Action::Filter => self.events.send(AppEvent::OpenFilter),
+            Action::ClearFilter if !tasks_active && !self.file_filter.is_empty() => {
+                self.clear_file_filter();
+            }
+            Action::ClearFilter if !self.filter_text.is_empty() && tasks_active => {
+                self.clear_filter();
+            }
+            // Sorting the columns
+            Action::SortName => self.sort_by(SortColumn::Name),
+            Action::SortSize => self.sort_by(SortColumn::Size),
+            Action::SortDownloaded => self.sort_by(SortColumn::Downloaded),
+            Action::SortUploaded => self.sort_by(SortColumn::Uploaded),
+            Action::SortProgress => self.sort_by(SortColumn::Progress),
+            Action::SortUploadSpeed => self.sort_by(SortColumn::UploadSpeed),
+            Action::SortDownloadSpeed => self.sort_by(SortColumn::DownloadSpeed),
+            Action::SortRatio => self.sort_by(SortColumn::Ratio),
+            Action::SortStatus => self.sort_by(SortColumn::Status),
+            _ => {}
+        }
+    }
+
+    /// Whether a popup, input or screen is open on top of the task list
+    fn overlay_open(&self) -> bool {
+        self.pending_action.is_some()
+            || self.popup.is_some()
+            || self.file_explorer.is_some()
+            || self.filter_input.is_some()
+            || self.url_input.is_some()
+            || self.rss.is_some()
+            || self.bt_search.is_some()
+            || self.server_settings.is_some()
+            || self.folder_browser.is_some()
+            || self.profile_picker.is_some()
+            || self.settings_editor.is_some()
+            || self.speed_limit.is_some()
+            || self.delete_confirm.is_some()
+    }
+
+    /// Keys that quit right away, also in the middle of a request: the quit keys while the
+    /// task list has the keyboard, nothing when they would close an overlay or need confirming
+    fn interrupt_keys(&self) -> Vec<KeyBinding> {
+        if self.overlay_open() || self.confirm_quit {
+            return Vec::new();
+        }
+        self.keymap
+            .iter()
+            .filter(|(_, action)| **action == Action::Quit)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    pub fn handle_mouse_events(&mut self, mouse_event: MouseEvent) {
+        // Mouse only drives the main screen, overlays are keyboard only
+        if self.overlay_open() {
+            return;
+        }
+
+        let position = Position::new(mouse_event.column, mouse_event.row);
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if self.task_table_area.contains(position) {
+                    self.active_panel = ActivePanel::Tasks;
+                    // Skip the top border and the header row
+                    let first_row_y = self.task_table_area.y + 2;
+                    if position.y >= first_row_y {
+                        let row = self.selected_task.offset() + (position.y - first_row_y) as usize;
+                        if row < self.sorted_tasks().len() {
+                            self.jump_to_task_row(row);
+                        }
+                    }
+                } else if self.tabs_area.contains(position) {
+                    self.active_panel = ActivePanel::Info;
+                    if let Some(tab) = self.tab_at(position.x) {
+                        self.select_tab(tab);
+                    }
+                } else if self.info_area.contains(position) {
+                    self.active_panel = ActivePanel::Info;
+                }
+            }
+            MouseEventKind::ScrollDown => {
+                if self.info_area.contains(position) {
+                    self.scroll_info_down();
+                } else if self.task_table_area.contains(position) {
+                    self.next_task_row();
+                }
+            }
+            MouseEventKind::ScrollUp => {
+                if self.info_area.contains(position) {
+                    self.scroll_info_up();
+                } else if self.task_table_area.contains(position) {
+                    self.previous_task_row();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Find the tab under the given column, mirroring how the `Tabs` widget lays out titles:
+    /// one space of padding on each side of the title and a one column divider between tabs.
+    fn tab_at(&self, x: u16) -> Option<usize> {
+        let mut start = self.tabs_area.x;
+        for (i, title) in self.tabs.iter().enumerate() {
+            // Titles are rendered as " {title} " plus the padding on both sides
+            let width = title.chars().count() as u16 + 4;
+            if x >= start && x < start + width {
+                return Some(i);
+            }
+            start += width + 1;
+        }
+        None
+    }
+
+    /// Insert pasted text into whichever input field is open
+    pub fn handle_paste(&mut self, text: &str) {
+        let search_input = self
+            .bt_search
+            .as_mut()
+            .filter(|search| search.input_focused)
+            .map(|search| &mut search.input);
+        let settings_input = self
+            .settings_editor
+            .as_mut()
+            .map(|editor| editor.focused_input());
+        let speed_limit_input = self.speed_limit.as_mut().map(|form| form.focused_input());
+        let url_input = self.url_input.as_mut().map(|form| form.focused_input());
+        let input = match (
+            url_input,
+            &mut self.filter_input,
+            search_input,
+            settings_input,
+            speed_limit_input,
+        ) {
+            (Some(input), ..)
+            | (None, Some(input), ..)
+            | (None, None, Some(input), ..)
+            | (None, None, None, Some(input), _)
+            | (None, None, None, None, Some(input)) => input,
+            _ => return,
+        };
+        // All inputs are single-line, so drop any line breaks from the pasted text
+        for c in text.trim().chars().filter(|c| *c != '\n' && *c != '\r') {
+            input.handle(InputRequest::InsertChar(c));
+        }
+    }
+
+    pub async fn tick(&mut self) -> anyhow::Result<()> {
+        let searching = self
+            .bt_search
+            .as_ref()
+            .is_some_and(|search| search.task_id.is_some() && !search.finished);
+        if self.in_flight.is_some() || searching {
+            self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
+        }
+
         if let Some(notification) = &mut self.notification {
             if notification.ticks_remaining > 0 {
                 notification.ticks_remaining -= 1;
             } else {
-                self.notification = None;
+                self.notification = None;
+            }
+        }
+
+        // Poll a running BT search about once a second until the DiskStation reports it finished
+        if let Some(search) = &mut self.bt_search
+            && search.task_id.is_some()
+            && !search.finished
+        {
+            search.poll_ticks += 1;
+            if search.poll_ticks >= TICK_FPS as u64 {
+                search.poll_ticks = 0;
+                self.events.send(AppEvent::PollBtSearch);
+            }
+        }
+
+        // Selection changes come from many places (keys, mouse, sorting, filtering), so the
+        // details for a newly selected task are fetched here instead
+        let selected_id = self
+            .selected_task_in_sorted()
+            .and_then(|idx| self.tasks.get(idx))
+            .map(|task| task.id.as_str());
+        if selected_id != self.details_task_id.as_deref()
+            && let Err(e) = self.load_selected_details().await
+        {
+            self.show_popup(
+                vec!["Failed to load task details:".into(), e.to_string()],
+                true,
+            );
+        }
+
+        if let Some(interval) = self.effective_refresh_interval()
+            && !self.refresh_paused
+        {
+            self.tick_count += 1;
+            // A refresh under a popup can move the selection or replace the popup while
+            // someone is typing into it, so a due refresh waits for the popup to close
+            if self.tick_count >= interval && !self.overlay_open() {
+                self.tick_count = 0;
+                self.send_busy(AppEvent::Refresh, "Refreshing");
+            }
+        }
+        Ok(())
+    }
+
+    /// Seconds between automatic refreshes, None when auto-refresh is disabled
+    pub fn refresh_interval_secs(&self) -> Option<u64> {
+        self.refresh_interval
+            .map(|ticks| (ticks as f64 / TICK_FPS).round() as u64)
+    }
+
+    /// Whether any task is moving data or about to, which keeps the configured interval
+    fn has_active_tasks(&self) -> bool {
+        self.tasks.iter().any(|task| {
+            let transferring = task
+                .additional
+                .as_ref()
+                .and_then(|a| a.transfer.as_ref())
+                .is_some_and(|t| t.speed_download > 0 || t.speed_upload > 0);
+            transferring
+                || matches!(
+                    task.status,
+                    TaskStatus::Waiting
+                        | TaskStatus::Downloading
+                        | TaskStatus::Finishing
+                        | TaskStatus::HashChecking
+                        | TaskStatus::FilehostingWaiting
+                        | TaskStatus::Extracting
+                        | TaskStatus::Preprocessing
+                        | TaskStatus::PreprocessPass
+                        | TaskStatus::Postprocessing
+                )
+        })
+    }
+
+    /// Whether auto-refresh is currently backed off because nothing is transferring
+    pub fn refresh_backed_off(&self) -> bool {
+        self.adaptive_refresh
+            && self
+                .refresh_interval_secs()
+                .is_some_and(|secs| secs < IDLE_REFRESH_MAX_SECS)
+            && !self.has_active_tasks()
+    }
+
+    /// Ticks until the next automatic refresh: the configured interval, stretched by
+    /// `IDLE_REFRESH_FACTOR` while no task is active. The first refresh that shows an active
+    /// task (e.g. after resuming one) brings the configured interval back.
+    pub fn effective_refresh_interval(&self) -> Option<u64> {
+        let interval = self.refresh_interval?;
+        if !self.refresh_backed_off() {
+            return Some(interval);
+        }
+        let max = (IDLE_REFRESH_MAX_SECS as f64 * TICK_FPS) as u64;
+        Some((interval * IDLE_REFRESH_FACTOR).min(max))
+    }
+
+    pub fn effective_refresh_secs(&self) -> Option<u64> {
+        self.effective_refresh_interval()
+            .map(|ticks| (ticks as f64 / TICK_FPS).round() as u64)
+    }
+
+    pub fn toggle_refresh_paused(&mut self) {
+        if self.refresh_interval.is_none() {
+            self.show_notification("Auto-refresh is disabled, use + to enable it".to_string());
+            return;
+        }
+        self.refresh_paused = !self.refresh_paused;
+        self.tick_count = 0;
+        self.show_notification(if self.refresh_paused {
+            "Auto-refresh paused".to_string()
+        } else {
+            "Auto-refresh resumed".to_string()
+        });
+    }
+
+    /// Step the refresh interval by a second below 5 seconds and by 5 seconds above,
+    /// going below 1 second disables auto-refresh
+    pub fn adjust_refresh_interval(&mut self, increase: bool) {
+        let secs = match (self.refresh_interval_secs(), increase) {
+            (None, true) => Some(1),
+            (None, false) => None,
+            (Some(secs), true) if secs < 5 => Some(secs + 1),
+            (Some(secs), true) => Some(secs + 5),
+            (Some(secs), false) if secs <= 5 => Some(secs - 1).filter(|&s| s > 0),
+            (Some(secs), false) => Some(secs - 5),
+        };
+        self.refresh_interval = secs.map(|s| (s as f64 * TICK_FPS) as u64);
+        self.tick_count = 0;
+        self.show_notification(match secs {
+            Some(secs) => format!("Refreshing every {}s", secs),
+            None => "Auto-refresh disabled".to_string(),
+        });
+    }
+
+    pub fn quit(&mut self) {
+        self.running = false;
+    }
+
+    pub async fn refresh_tasks(&mut self) -> anyhow::Result<()> {
+        if let Some(api) = &self.api {
+            // Snapshot id -> status string before refreshing
+            let previous_statuses: HashMap<String, String> = self
+                .tasks
+                .iter()
+                .map(|t| (t.id.clone(), format!("{:?}", t.status)))
+                .collect();
+
+            let previously_selected_id = self
+                .selected_task_in_sorted()
+                .and_then(|idx| self.tasks.get(idx))
+                .map(|task| task.id.clone());
+            let previous_row = self.selected_task.selected().unwrap_or(0);
+
+            // The table only needs transfer and detail info, the heavy tracker/peer/file lists
+            // are loaded for the selected task alone below. None means the list is the same as
+            // last time, the tasks already there are still current.
+            match api.list_tasks_changed().await {
+                Ok(tasks) => {
+                    self.connection_status = ConnectionStatus::Connected;
+                    if let Some(tasks) = tasks {
+                        self.tasks = tasks;
+                    }
+                    self.record_task_speeds();
+                    self.record_session_transferred();
+                    self.pause_seeds_at_ratio_target().await;
+                    self.error_count = self
+                        .tasks
+                        .iter()
+                        .filter(|t| is_error_status(&t.status))
+                        .count();
+
+                    let newly_finished: Vec<String> = self
+                        .tasks
+                        .iter()
+                        .filter(|t| {
+                            match (&t.status, previous_statuses.get(&t.id).map(String::as_str)) {
+                                (TaskStatus::Finished, Some(prev)) => prev != "Finished",
+                                // Torrents that go on seeding never show up as Finished
+                                (TaskStatus::Seeding, Some(prev)) => {
+                                    matches!(prev, "Downloading" | "Finishing")
+                                }
+                                _ => false,
+                            }
+                        })
+                        .map(|t| t.title.clone())
+                        .collect();
+
+                    if self.tasks.is_empty() {
+                        self.selected_task.select(None);
+                    } else if let Some(prev_id) = &previously_selected_id {
+                        let sorted = self.sorted_tasks();
+                        let new_idx = sorted.iter().position(|t| t.id == *prev_id);
+                        match new_idx {
+                            Some(idx) => self.selected_task.select(Some(idx)),
+                            // The task is gone, stay on the row that took its place
+                            None if !sorted.is_empty() => self
+                                .selected_task
+                                .select(Some(previous_row.min(sorted.len() - 1))),
+                            None => self.selected_task.select(None),
+                        }
+                    } else {
+                        self.selected_task.select(Some(0));
+                    }
+                    // The filter can hide every task left
+                    self.clamp_task_selection();
+                    self.restore_task_tab();
+                    let tasks = &self.tasks;
+                    self.task_tabs
+                        .retain(|id, _| tasks.iter().any(|task| task.id == *id));
+
+                    if let Err(e) = self.load_selected_details().await {
+                        self.show_popup(
+                            vec!["Failed to load task details:".into(), e.to_string()],
+                            true,
+                        );
+                    }
+
+                    // Keep the info panel scroll across refreshes unless the selection moved to
+                    // another task, e.g. because the selected one was deleted
+                    let selected_id = self
+                        .selected_task_in_sorted()
+                        .and_then(|idx| self.tasks.get(idx))
+                        .map(|task| task.id.clone());
+                    if selected_id != previously_selected_id {
+                        self.reset_info_scroll();
+                    }
+                    self.update_info_counts();
+                    self.clamp_info_scroll();
+
+                    if !newly_finished.is_empty() {
+                        let message = if newly_finished.len() == 1 {
+                            format!("✓ {}", newly_finished[0])
+                        } else {
+                            format!("✓ {} tasks completed", newly_finished.len())
+                        };
+                        self.show_notification(message);
+                        if self.enable_notifications {
+                            let summary = if newly_finished.len() == 1 {
+                                "Download complete".to_string()
+                            } else {
+                                format!("{} downloads complete", newly_finished.len())
+                            };
+                            desktop_notification(&summary, &newly_finished.join("\n"));
+                        }
+                    }
+                }
+                Err(e) => {
+                    if e.is_unreachable() {
+                        self.connection_status = ConnectionStatus::Disconnected;
+                    }
+                    let mut lines = vec!["Failed to get tasks:".into(), e.to_string()];
+                    if e.is_permission_denied() {
+                        lines.push(NO_DOWNLOAD_STATION_ACCESS.into());
+                    }
+                    self.show_popup(lines, true);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Pause the seeding tasks that reached `seed_ratio_target`. A task is only paused once, so
+    /// resuming it by hand lets it seed on.
+    async fn pause_seeds_at_ratio_target(&mut self) {
+        let Some(target) = self.seed_ratio_target else {
+            return;
+        };
+        self.auto_paused
+            .retain(|id| self.tasks.iter().any(|t| t.id == *id));
+        let ids: Vec<String> = self
+            .tasks
+            .iter()
+            .filter(|t| {
+                matches!(t.status, TaskStatus::Seeding)
+                    && t.calculate_ratio() >= target
+                    && !self.auto_paused.contains(&t.id)
+            })
+            .map(|t| t.id.clone())
+            .collect();
+        if ids.is_empty() {
+            return;
+        }
+        let Some(api) = &self.api else {
+            return;
+        };
+
+        match api.pause(&ids.join(",")).await {
+            Ok(operation) => {
+                // Tasks DSM refused stay seeding and are tried again on the next refresh
+                let ids: Vec<String> = ids
+                    .into_iter()
+                    .filter(|id| !operation.failed_task.iter().any(|f| f.id == *id))
+                    .collect();
+                if ids.is_empty() {
+                    return;
+                }
+                for task in self.tasks.iter_mut().filter(|t| ids.contains(&t.id)) {
+                    log::info!(
+                        "Paused \"{}\" ({}) at ratio {:.2}, target {:.2}",
+                        task.title,
+                        task.id,
+                        task.calculate_ratio(),
+                        target
+                    );
+                    // Shown as paused right away instead of after the next refresh
+                    task.status = TaskStatus::Paused;
+                }
+                self.show_notification(format!(
+                    "✓ Paused {} task(s) at ratio {:.2}",
+                    ids.len(),
+                    target
+                ));
+                self.auto_paused.extend(ids);
+            }
+            // Tried again on the next refresh
+            Err(e) => log::warn!("Failed to pause tasks at the ratio target: {}", e),
+        }
+    }
+
+    /// Append the current download speed of every task to its history and forget tasks that
+    /// are gone
+    fn record_task_speeds(&mut self) {
+        let history = &mut self.task_speed_history;
+        history.retain(|id, _| self.tasks.iter().any(|t| t.id == *id));
+        let now = Instant::now();
+        for task in &self.tasks {
+            let speed = task
+                .additional
+                .as_ref()
+                .and_then(|a| a.transfer.as_ref())
+                .map_or(0, |t| t.speed_download);
+            let samples = history
+                .entry(task.id.clone())
+                .or_insert_with(|| VecDeque::with_capacity(TASK_SPEED_HISTORY_LEN));
+            if samples.len() == TASK_SPEED_HISTORY_LEN {
+                samples.pop_front();
+            }
+            samples.push_back(speed);
+
+            if speed == 0 && matches!(task.status, TaskStatus::Downloading) {
+                self.zero_speed_since.entry(task.id.clone()).or_insert(now);
+            } else {
+                self.zero_speed_since.remove(&task.id);
+            }
+        }
+        let tasks = &self.tasks;
+        self.zero_speed_since
+            .retain(|id, _| tasks.iter().any(|t| t.id == *id));
+    }
+
+    /// Add what each task transferred since the last refresh to `session_transferred`
+    fn record_session_transferred(&mut self) {
+        self.task_transferred = count_transferred(
+            &self.tasks,
+            &self.task_transferred,
+            &mut self.session_transferred,
+        );
+    }
+
+    /// Whether a download has been stuck at 0 B/s for `stalled_after`, e.g. without seeds or
+    /// with dead trackers
+    pub fn is_stalled(&self, task: &Task) -> bool {
+        self.stalled_after.is_some_and(|after| {
+            self.zero_speed_since
+                .get(&task.id)
+                .is_some_and(|since| since.elapsed() >= after)
+        })
+    }
+
+    /// Something like "dstui — 3↓ 12↑ 4.2 MB/s", to see what's going on from the taskbar
+    pub fn terminal_title_text(&self) -> String {
+        let downloading = self
+            .tasks
+            .iter()
+            .filter(|t| matches!(t.status, TaskStatus::Downloading))
+            .count();
+        let seeding = self
+            .tasks
+            .iter()
+            .filter(|t| matches!(t.status, TaskStatus::Seeding))
+            .count();
+        let speed: u64 = self
+            .tasks
+            .iter()
+            .filter_map(|t| t.additional.as_ref().and_then(|a| a.transfer.as_ref()))
+            .map(|t| t.speed_download)
+            .sum();
+        let mut title = format!("dstui — {}↓ {}↑", downloading, seeding);
+        // format_speed leaves 0 B/s empty
+        let speed = format_speed(speed, self.binary_units);
+        if !speed.is_empty() {
+            title.push(' ');
+            title.push_str(&speed);
+        }
+        title
+    }
+
+    /// Put the summary in the terminal's title, for `display.terminal_title`
+    fn update_terminal_title(&self) {
+        if !self.terminal_title {
+            return;
+        }
+        // The terminal keeps its own title on a stack, so it can be put back on exit
+        if !TITLE_SET.swap(true, Ordering::Relaxed) {
+            let _ = execute!(stdout(), Print("\x1b[22;0t"));
+        }
+        let _ = execute!(stdout(), SetTitle(self.terminal_title_text()));
+    }
+
+    pub async fn refresh_statistics(&mut self) {
+        let Some(api) = &self.api else {
+            return;
+        };
+        match api.get_statistics().await {
+            Ok(statistics) => {
+                if self.speed_history.len() == STATISTICS_HISTORY_LEN {
+                    self.speed_history.pop_front();
+                }
+                self.speed_history.push_back((
+                    statistics.speed_download + statistics.emule_speed_download,
+                    statistics.speed_upload + statistics.emule_speed_upload,
+                ));
+                self.statistics = Some(statistics);
+            }
+            // Not worth a popup on every refresh, the tab shows that statistics are unavailable
+            Err(_) => self.statistics = None,
+        }
+    }
+
+    /// Load trackers, peers and files for the selected task into its entry in `tasks`
+    pub async fn load_selected_details(&mut self) -> anyhow::Result<()> {
+        let selected_id = self
+            .selected_task_in_sorted()
+            .and_then(|idx| self.tasks.get(idx))
+            .map(|task| task.id.clone());
+        // Set before fetching so a failing request isn't retried on every tick
+        self.details_task_id = selected_id.clone();
+
+        let (Some(api), Some(id)) = (&self.api, selected_id) else {
+            return Ok(());
+        };
+        if let Some(detailed) = api.get_task(&id).await?
+            && let Some(task) = self.tasks.iter_mut().find(|t| t.id == id)
+        {
+            *task = detailed;
+            self.details_loaded_at = Some(Instant::now());
+        }
+
+        self.update_info_counts();
+        self.clamp_info_scroll();
+        Ok(())
+    }
+
+    /// Show an info panel tab, remembering it for the selected task with `tab_per_task`
+    fn select_tab(&mut self, tab: usize) {
+        self.selected_tab = tab;
+        if self.tab_per_task
+            && let Some(id) = self
+                .selected_task_in_sorted()
+                .and_then(|idx| self.tasks.get(idx))
+                .map(|task| task.id.clone())
+        {
+            self.task_tabs.insert(id, tab);
+        }
+    }
+
+    /// Go back to the tab last shown for the selected task, tasks without one keep the
+    /// current tab
+    fn restore_task_tab(&mut self) {
+        if !self.tab_per_task {
+            return;
+        }
+        if let Some(&tab) = self
+            .selected_task_in_sorted()
+            .and_then(|idx| self.tasks.get(idx))
+            .and_then(|task| self.task_tabs.get(&task.id))
+        {
+            self.selected_tab = tab;
+        }
+    }
+
+    // Task panel scroll and row selection
+    pub fn next_task_row(&mut self) {
+        let row_count = self.sorted_tasks().len();
+        move_next(&mut self.selected_task, row_count);
+        self.restore_task_tab();
+        self.reset_info_scroll();
+        self.update_info_counts();
+    }
+    pub fn previous_task_row(&mut self) {
+        move_previous(&mut self.selected_task);
+        self.restore_task_tab();
+        self.reset_info_scroll();
+        self.update_info_counts();
+    }
+
+    pub fn first_task_row(&mut self) {
+        self.jump_to_task_row(0);
+    }
+    pub fn last_task_row(&mut self) {
+        self.jump_to_task_row(usize::MAX);
+    }
+    pub fn half_page_down(&mut self) {
+        let current = self.selected_task.selected().unwrap_or(0);
+        self.jump_to_task_row(current + (self.task_inner_height / 2).max(1));
+    }
+    pub fn half_page_up(&mut self) {
+        let current = self.selected_task.selected().unwrap_or(0);
+        self.jump_to_task_row(current.saturating_sub((self.task_inner_height / 2).max(1)));
+    }
+
+    /// Select the first task in an error state, searching from below the current selection so
+    /// pressing again cycles through them
+    pub fn jump_to_errored_task(&mut self) {
+        let current = self.selected_task.selected();
+        let errored: Vec<usize> = self
+            .sorted_tasks()
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| is_error_status(&t.status))
+            .map(|(row, _)| row)
+            .collect();
+        let Some(&first) = errored.first() else {
+            let message = if self.error_count > 0 {
+                "No errored tasks match the filter"
+            } else {
+                "No errored tasks"
+            };
+            self.show_notification(message.to_string());
+            return;
+        };
+        let row = errored
+            .iter()
+            .copied()
+            .find(|&row| current.is_none_or(|current| row > current))
+            .unwrap_or(first);
+        self.active_panel = ActivePanel::Tasks;
+        self.jump_to_task_row(row);
+    }
+
+    pub fn info_panel_visible(&self) -> bool {
+        !self.info_panel_hidden && self.table_percent < 100
+    }
+
+    /// Give the task table the whole height, or bring the info panel back
+    pub fn toggle_info_panel(&mut self) {
+        if self.table_percent >= 100 {
+            self.show_notification("The info panel is turned off in the config".to_string());
+            return;
+        }
+        self.info_panel_hidden = !self.info_panel_hidden;
+        if self.info_panel_hidden {
+            self.active_panel = ActivePanel::Tasks;
+        }
+    }
+
+    /// Show or hide the finished and seeding tasks, the status bar keeps counting them. The
+    /// selected task stays selected if it is still listed.
+    pub fn toggle_hide_finished(&mut self) {
+        let selected_id = self
+            .selected_task_in_sorted()
+            .map(|idx| self.tasks[idx].id.clone());
+        self.hide_finished = !self.hide_finished;
+        if let Some(row) =
+            selected_id.and_then(|id| self.sorted_tasks().iter().position(|t| t.id == id))
+        {
+            self.selected_task.select(Some(row));
+        }
+        self.clamp_task_selection();
+        self.reset_info_scroll();
+        self.show_notification(if self.hide_finished {
+            "Hiding finished and seeding tasks".to_string()
+        } else {
+            "Showing all tasks".to_string()
+        });
+    }
+
+    /// Select the given row, clamped to the visible task list
+    fn jump_to_task_row(&mut self, row: usize) {
+        let row_count = self.sorted_tasks().len();
+        if row_count == 0 {
+            return;
+        }
+        self.selected_task.select(Some(row.min(row_count - 1)));
+        self.restore_task_tab();
+        self.reset_info_scroll();
+        self.update_info_counts();
+    }
+
+    // Info Panel scroll and row selection
+    fn reset_info_scroll(&mut self) {
+        self.tracker_scroll = 0;
+        self.peer_scroll = 0;
+        self.file_scroll = 0;
+        // Another task's files are unlikely to match
+        self.file_filter.clear();
+    }
+
+    /// Lists can shrink between refreshes, keep the scroll positions inside them
+    fn clamp_info_scroll(&mut self) {
+        self.tracker_scroll = self.tracker_scroll.min(self.tracker_max_scroll);
+        self.peer_scroll = self
+            .peer_scroll
+            .min(self.peer_count.saturating_sub(self.peer_inner_height));
+        self.file_scroll = self.file_scroll.min(self.file_max_scroll);
+    }
+
+    pub fn scroll_info_down(&mut self) {
+        match self.selected_tab {
+            2 => self.tracker_scroll = (self.tracker_scroll + 1).min(self.tracker_max_scroll),
+            3 => {
+                let max = self.peer_count.saturating_sub(self.peer_inner_height);
+                self.peer_scroll = (self.peer_scroll + 1).min(max);
+            }
+            4 => self.file_scroll = (self.file_scroll + 1).min(self.file_max_scroll),
+            _ => {}
+        }
+    }
+
+    pub fn scroll_info_up(&mut self) {
+        match self.selected_tab {
+            2 => self.tracker_scroll = self.tracker_scroll.saturating_sub(1),
+            3 => self.peer_scroll = self.peer_scroll.saturating_sub(1),
+            4 => self.file_scroll = self.file_scroll.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    pub fn selected_task_index(&self) -> Option<usize> {
+        self.selected_task.selected()
+    }
+
+    pub fn show_popup(&mut self, lines: Vec<String>, error: bool) {
+        if error {
+            // Kept in dstui.log so it can still be reported after the popup is closed
+            let message: Vec<&str> = lines
+                .iter()
+                .map(|l| l.trim())
+                .filter(|l| !l.is_empty())
+                .collect();
+            log::error!("{}", message.join(" "));
+        }
+        let kind = if error {
+            PopupKind::Error
+        } else {
+            PopupKind::Help
+        };
+        self.popup = Some(PopupState {
+            lines,
+            kind,
+            scroll: 0,
+        });
+    }
+
+    /// Everything known about the selected task in a popup nearly as big as the terminal, so
+    /// long destinations, URIs and file names fit
+    pub async fn show_details(&mut self) -> anyhow::Result<()> {
+        // The table only has the summary, make sure the trackers, peers and files are loaded
+        self.load_selected_details().await?;
+        let Some(task) = self
+            .selected_task_in_sorted()
+            .and_then(|idx| self.tasks.get(idx))
+        else {
+            return Ok(());
+        };
+
+        let text = task_details_text(task, self.binary_units, self.use_local_time);
+        self.popup = Some(PopupState {
+            lines: text.lines().map(String::from).collect(),
+            kind: PopupKind::Details(task.title.clone()),
+            scroll: 0,
+        });
+        Ok(())
+    }
+
+    pub fn close_popup(&mut self) {
+        self.popup = None;
+    }
+
+    pub fn update_info_counts(&mut self) {
+        if let Some(real_idx) = self.selected_task_in_sorted()
+            && let Some(task) = self.tasks.get(real_idx)
+        {
+            let peers = task
+                .additional
+                .as_ref()
+                .and_then(|a| a.peer.as_ref())
+                .map(|p| p.len())
+                .unwrap_or(0);
+            self.peer_count = match self.max_peers {
+                max if max > 0 && peers > max => max + 1,
+                _ => peers,
+            };
+        }
+    }
+
+    // File picker methods
+    pub fn open_file_picker(&mut self) {
+        let theme = ExplorerTheme::default()
+            .add_default_title()
+            .with_block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(self.theme.accent))
+                    .title(" File Picker "),
+            )
+            .with_title_bottom(|_| {
+                " Enter to open or select · h to go up · t to toggle other files · Esc to cancel "
+                    .into()
+            })
+            .with_title_top({
+                let destination = format!(" → {} ", self.destination);
+                move |_| destination.clone().into()
+            });
+        let mut explorer = FileExplorerBuilder::build_with_theme(theme).unwrap();
+        if !self.picker_show_all {
+            // The unfiltered listing already worked, so this can't fail in practice
+            let _ = explorer.set_filter_map(supported_files_only);
+        }
+        self.file_explorer = Some(explorer);
+    }
+
+    pub fn toggle_picker_filter(&mut self) {
+        let Some(explorer) = &mut self.file_explorer else {
+            return;
+        };
+        self.picker_show_all = !self.picker_show_all;
+        let result = if self.picker_show_all {
+            explorer.remove_filter_map().map(|_| ())
+        } else {
+            explorer.set_filter_map(supported_files_only)
+        };
+        self.picker_message = result.err().map(|e| e.to_string());
+    }
+
+    pub fn open_picker_dir(&mut self) {
+        let Some(explorer) = &mut self.file_explorer else {
+            return;
+        };
+        let dir = explorer.current().clone();
+        // set_cwd leaves the explorer untouched when listing fails
+        self.picker_message = match explorer.set_cwd(&dir.path) {
+            Ok(()) => None,
+            Err(e) => Some(format!("Can't open {}: {}", dir.name, e)),
+        };
+    }
+
+    pub async fn submit_selected_file(&mut self) -> anyhow::Result<()> {
+        // Extract everything we need from the explorer before any API calls
+        let file_data = if let Some(explorer) = &self.file_explorer {
+            let path = explorer.current();
+            if path.is_file() {
+                let ext = path.path.extension().and_then(|e| e.to_str());
+                if task_file_mime(&path.name).is_some() {
+                    let filename = path
+                        .path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("upload.torrent")
+                        .to_string();
+                    match std::fs::read(&path.path) {
+                        Ok(bytes) => Some((bytes, filename)),
+                        Err(e) => {
+                            self.show_popup(
+                                vec!["Failed to read file:".into(), e.to_string()],
+                                true,
+                            );
+                            None
+                        }
+                    }
+                } else {
+                    self.show_popup(
+                        vec![
+                            "Unsupported file type.".into(),
+                            format!("Got: .{}", ext.unwrap_or("unknown")),
+                            "Only .torrent, .nzb and .txt are supported.".into(),
+                        ],
+                        true,
+                    );
+                    None
+                }
+            } else {
+                // selected a directory, do nothing silently
+                None
+            }
+        } else {
+            None
+        };
+
+        self.file_explorer = None;
+        self.picker_message = None;
+
+        let Some((file_bytes, filename)) = file_data else {
+            return Ok(());
+        };
+        // A look at the torrent first, a wrong or huge one is easier to skip than to delete
+        if filename.to_lowercase().ends_with(".torrent") {
+            let summary = parse_torrent(&file_bytes);
+            // With a threshold only the big ones ask, a size that can't be read doesn't
+            let below_threshold = self.confirm_add_above > 0
+                && summary
+                    .as_ref()
+                    .map_or(true, |summary| summary.size <= self.confirm_add_above);
+            if below_threshold {
+                return self.add_file_task(&file_bytes, &filename).await;
+            }
+            let mut lines = match summary {
+                Ok(summary) if self.confirm_add_above > 0 => vec![
+                    format!("Add torrent: {}?", summary.name),
+                    String::new(),
+                    format!(
+                        "Size:  {}, more than the {} set as confirm_add_above_bytes",
+                        format_bytes(summary.size, self.binary_units),
+                        format_bytes(self.confirm_add_above, self.binary_units)
+                    ),
+                    format!("Files: {}", summary.files),
+                ],
+                Ok(summary) => vec![
+                    format!("Add torrent: {}?", summary.name),
+                    String::new(),
+                    format!("Size:  {}", format_bytes(summary.size, self.binary_units)),
+                    format!("Files: {}", summary.files),
+                ],
+                Err(e) => vec![
+                    format!("Add torrent: {}?", filename),
+                    String::new(),
+                    format!("Can't show what's in it ({}), add it anyway?", e),
+                ],
+            };
+            lines.extend([
+                String::new(),
+                "  y / Enter — add it".into(),
+                "  n / Esc   — cancel".into(),
+            ]);
+            self.pending_action = Some(PendingAction::AddFile {
+                bytes: file_bytes,
+                filename,
+            });
+            self.show_popup(lines, false);
+            return Ok(());
+        }
+        self.add_file_task(&file_bytes, &filename).await
+    }
+
+    async fn add_file_task(&mut self, file_bytes: &[u8], filename: &str) -> anyhow::Result<()> {
+        let Some(api) = &self.api else {
+            return Ok(());
+        };
+        api.create_task_from_file(file_bytes, filename, &self.destination)
+            .await?;
+        if let Err(e) = self.refresh_tasks().await {
+            self.show_popup(
+                vec!["Task added but refresh failed:".into(), e.to_string()],
+                true,
+            );
+        }
+        Ok(())
+    }
+
+    // Add by URL methods
+    pub fn open_url_input(&mut self) {
+        self.url_input = Some(UrlForm::default());
+    }
+
+    pub async fn submit_url(&mut self) -> anyhow::Result<()> {
+        if let Some(form) = self.url_input.take() {
+            let url = form.value(UrlField::Url).trim().to_string();
+            if validate_url(&url).is_none() {
+                self.show_popup(
+                    vec![
+                        "Invalid URL format.".into(),
+                        "Must start with http://, https://, ftp://, sftp://, magnet: or ed2k://"
+                            .into(),
+                    ],
+                    true,
+                );
+            } else if let Some(api) = &self.api {
+                api.create_task(&url, &self.destination, &form.options())
+                    .await?;
+                self.send_busy(AppEvent::Refresh, "Refreshing");
+            }
+        }
+        Ok(())
+    }
+
+    // Toggle task status (pause/resume)
+    /// Show the selected task as paused / resumed right away and send the request, the next
+    /// draw comes before the answer so the table doesn't lag behind the key press
+    pub fn start_toggle_task(&mut self) {
+        // The status shown is already the toggled one until the request is done
+        if self.pending_toggle.is_some() {
+            return;
+        }
+        let Some(task) = self
+            .selected_task_in_sorted()
+            .and_then(|idx| self.tasks.get_mut(idx))
+        else {
+            return;
+        };
+        let Some((status, previous)) = toggled_status(task) else {
+            return;
+        };
+        task.status = status;
+        self.pending_toggle = Some(PendingToggle {
+            task_id: task.id.clone(),
+            previous,
+        });
+        self.send_busy(AppEvent::ToggleTask, "Updating");
+    }
+
+    /// Send the pause or resume of `start_toggle_task`. The next refresh replaces the status
+    /// shown with the real one, a failed request puts the old one back.
+    pub async fn toggle_task(&mut self) -> anyhow::Result<()> {
+        let Some(toggle) = self.pending_toggle.take() else {
+            return Ok(());
+        };
+        let Some(api) = &self.api else {
+            return Ok(());
+        };
+
+        let result = match toggle.previous {
+            TaskStatus::Paused | TaskStatus::Finished => {
+                api.resume(&toggle.task_id).await.map(|_| ())
+            }
+            _ => api.pause(&toggle.task_id).await.map(|_| ()),
+        };
+        match result {
+            // The list from before the toggle must not count as unchanged
+            Ok(()) => api.forget_task_list().await,
+            Err(e) => {
+                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == toggle.task_id) {
+                    task.status = toggle.previous;
+                }
+                self.show_popup(vec!["Failed to toggle task:".into(), e.to_string()], true);
+            }
+        }
+        Ok(())
+    }
+
+    /// Resume a task that stopped with an error, DSM then queues it again. Errors that won't
+    /// go away by retrying, like an invalid torrent, are refused with a notification.
+    pub async fn retry_task(&mut self) -> anyhow::Result<()> {
+        let Some(task) = self
+            .selected_task_in_sorted()
+            .and_then(|idx| self.tasks.get(idx))
+        else {
+            return Ok(());
+        };
+        if !is_error_status(&task.status) {
+            self.show_notification("Only tasks with an error can be retried".to_string());
+            return Ok(());
+        }
+        if !is_retriable_status(&task.status) {
+            self.show_notification(format!("{} won't go away by retrying", status_label(task)));
+            return Ok(());
+        }
+        let (id, title) = (task.id.clone(), task.title.clone());
+
+        if let Some(api) = &self.api {
+            let operation = api.resume(&id).await?;
+            if let Some(failed) = operation.failed_task.first() {
+                self.show_popup(
+                    vec![
+                        format!("Failed to retry {}:", title),
+                        format!(
+                            "{} (code {})",
+                            task_error_description(failed.error),
+                            failed.error
+                        ),
+                    ],
+                    true,
+                );
+                return Ok(());
+            }
+            self.show_notification(format!("✓ Retrying {}", title));
+        }
+        self.refresh_tasks().await
+    }
+
+    /// Queue every task with an error again in a single request, leaving out the errors that
+    /// retrying can't fix, like a premium account being required
+    pub async fn retry_errors(&mut self) -> anyhow::Result<()> {
+        let (ids, skipped) = self
+            .tasks
+            .iter()
+            .filter(|t| is_error_status(&t.status))
+            .fold((Vec::new(), 0), |(mut ids, skipped), t| {
+                if is_retriable_status(&t.status) {
+                    ids.push(t.id.clone());
+                    (ids, skipped)
+                } else {
+                    (ids, skipped + 1)
+                }
+            });
+
+        if ids.is_empty() {
+            let message = match skipped {
+                0 => "No tasks with an error".to_string(),
+                n => format!("{} task(s) with an error, none that retrying can fix", n),
+            };
+            self.show_notification(message);
+            return Ok(());
+        }
+        self.resume_tasks(&ids, "task(s) with an error").await?;
+        if skipped > 0 && self.popup.is_none() {
+            self.show_notification(format!(
+                "✓ Retried {} task(s), skipped {} that retrying can't fix",
+                ids.len(),
+                skipped
+            ));
+        }
+        Ok(())
+    }
+
+    /// Pause every task that is currently active, using a single request
+    pub async fn pause_all(&mut self) -> anyhow::Result<()> {
+        let ids: Vec<String> = self
+            .tasks
+            .iter()
+            .filter(|t| {
+                matches!(
+                    t.status,
+                    TaskStatus::Downloading | TaskStatus::Waiting | TaskStatus::Seeding
+                )
+            })
+            .map(|t| t.id.clone())
+            .collect();
+
+        if ids.is_empty() {
+            self.show_notification("No active tasks to pause".to_string());
+            return Ok(());
+        }
+        self.pause_tasks(&ids, "task(s)").await
+    }
+
+    /// Pause the seeding tasks only, downloads keep running
+    pub async fn pause_seeds(&mut self) -> anyhow::Result<()> {
+        let ids: Vec<String> = self
+            .tasks
+            .iter()
+            .filter(|t| matches!(t.status, TaskStatus::Seeding | TaskStatus::PreSeeding))
+            .map(|t| t.id.clone())
+            .collect();
+
+        if ids.is_empty() {
+            self.show_notification("No seeding tasks to pause".to_string());
+            return Ok(());
+        }
+        self.pause_tasks(&ids, "seed(s)").await
+    }
+
+    async fn pause_tasks(&mut self, ids: &[String], what: &str) -> anyhow::Result<()> {
+        if let Some(api) = &self.api {
+            // The API accepts a comma separated list of ids
+            match api.pause(&ids.join(",")).await {
+                Ok(operation) => self.show_operation_result("Paused", ids.len(), what, &operation),
+                Err(e) => {
+                    self.show_popup(
+                        vec![
+                            format!("Failed to pause {} {}:", ids.len(), what),
+                            e.to_string(),
+                        ],
+                        true,
+                    );
+                }
+            }
+        }
+
+        self.refresh_tasks().await
+    }
+
+    /// Notify about a pause or resume of `count` tasks, or list the ones DSM refused with the
+    /// reason
+    fn show_operation_result(
+        &mut self,
+        verb: &str,
+        count: usize,
+        what: &str,
+        operation: &TaskOperation,
+    ) {
+        if operation.failed_task.is_empty() {
+            self.show_notification(format!("✓ {} {} {}", verb, count, what));
+            return;
+        }
+        let failed = operation.failed_task.len();
+        let mut lines = vec![
+            format!(
+                "{} {} {}, {} failed:",
+                verb,
+                count.saturating_sub(failed),
+                what,
+                failed
+            ),
+            String::new(),
+        ];
+        lines.extend(operation.failed_task.iter().map(|f| {
+            let title = self
+                .tasks
+                .iter()
+                .find(|t| t.id == f.id)
+                .map(|t| t.title.as_str())
+                .unwrap_or(f.id.as_str());
+            format!(
+                "{}: {} (code {})",
+                title,
+                task_error_description(f.error),
+                f.error
+            )
+        }));
+        self.show_popup(lines, true);
+    }
+
+    /// Resume every paused task, using a single request
+    pub async fn resume_all(&mut self) -> anyhow::Result<()> {
+        let ids: Vec<String> = self
+            .tasks
+            .iter()
+            .filter(|t| matches!(t.status, TaskStatus::Paused))
+            .map(|t| t.id.clone())
+            .collect();
+
+        if ids.is_empty() {
+            self.show_notification("No paused tasks to resume".to_string());
+            return Ok(());
+        }
+        self.resume_tasks(&ids, "task(s)").await
+    }
+
+    /// Resume the paused tasks that were seeding. DSM reports those as plain paused, so they
+    /// are told apart from paused downloads by having everything downloaded already.
+    pub async fn resume_seeds(&mut self) -> anyhow::Result<()> {
+        let ids: Vec<String> = self
+            .tasks
+            .iter()
+            .filter(|t| {
+                let downloaded = t
+                    .additional
+                    .as_ref()
+                    .and_then(|a| a.transfer.as_ref())
+                    .is_some_and(|tr| t.size > 0 && tr.size_downloaded >= t.size);
+                matches!(t.status, TaskStatus::Paused) && t.task_type == "bt" && downloaded
+            })
+            .map(|t| t.id.clone())
+            .collect();
+
+        if ids.is_empty() {
+            self.show_notification("No paused seeds to resume".to_string());
+            return Ok(());
+        }
+        self.resume_tasks(&ids, "seed(s)").await
+    }
+
+    async fn resume_tasks(&mut self, ids: &[String], what: &str) -> anyhow::Result<()> {
+        if let Some(api) = &self.api {
+            match api.resume(&ids.join(",")).await {
+                Ok(operation) => self.show_operation_result("Resumed", ids.len(), what, &operation),
+                Err(e) => {
+                    self.show_popup(
+                        vec![
+                            format!("Failed to resume {} {}:", ids.len(), what),
+                            e.to_string(),
+                        ],
+                        true,
+                    );
+                }
+            }
+        }
+
+        self.refresh_tasks().await
+    }
+
+    // Complete task
+    pub async fn complete_task(&mut self) -> anyhow::Result<()> {
+        if let Some(real_idx) = self.selected_task_in_sorted()
+            && let Some(task) = self.tasks.get(real_idx)
+            && let Some(api) = &self.api
+        {
+            if let Err(e) = api.complete(&task.id).await {
+                self.show_popup(vec!["Failed to complete task:".into(), e.to_string()], true);
             }
-        }
 
-        if let Some(interval) = self.refresh_interval {
-            self.tick_count += 1;
-            if self.tick_count >= interval {
-                self.tick_count = 0;
-                self.refresh_tasks().await?;
+            if let Err(e) = self.refresh_tasks().await {
+                self.show_popup(vec!["Failed to refresh tasks:".into(), e.to_string()], true);
             }
         }
         Ok(())
     }
 
-    pub fn quit(&mut self) {
-        self.running = false;
+    /// Clear completed tasks
+    pub async fn clear_completed(&mut self) -> anyhow::Result<()> {
+        if let Some(api) = &self.api {
+            if let Err(e) = api.clear_completed().await {
+                self.show_popup(
+                    vec!["Failed to clear completed task(s):".into(), e.to_string()],
+                    true,
+                );
+            }
+            if let Err(e) = self.refresh_tasks().await {
+                self.show_popup(vec!["Failed to refresh tasks:".into(), e.to_string()], true);
+            }
+        }
+        Ok(())
     }
 
-    pub async fn refresh_tasks(&mut self) -> anyhow::Result<()> {
-        if let Some(client) = &self.client {
-            // Snapshot id -> status string before refreshing
-            let previous_statuses: std::collections::HashMap<String, String> = self
-                .tasks
-                .iter()
-                .map(|t| (t.id.clone(), format!("{:?}", t.status)))
-                .collect();
+    /// Delete selected task
+    pub fn request_delete_task(&mut self) {
+        if let Some(real_idx) = self.selected_task_in_sorted()
+            && let Some(task) = self.tasks.get(real_idx)
+        {
+            self.pending_action = Some(PendingAction::DeleteTask(task.id.clone()));
 
-            let previously_selected_id = self
-                .selected_task_in_sorted()
-                .and_then(|idx| self.tasks.get(idx))
-                .map(|task| task.id.clone());
+            // Big and seeding tasks are costly to get back, so a quick keypress on a row that
+            // just moved because of a refresh shouldn't be enough to delete them
+            let needs_typing = self.typed_delete_size > 0
+                && (task.size >= self.typed_delete_size
+                    || matches!(task.status, TaskStatus::Seeding));
+            let expected: String = task.title.chars().take(TYPED_DELETE_CHARS).collect();
+            if needs_typing && !expected.trim().is_empty() {
+                self.delete_confirm = Some(TypedConfirm {
+                    expected: expected.trim().to_string(),
+                    input: Input::default(),
+                    cursor_pos: None,
+                });
+                self.show_popup(
+                    vec![
+                        format!("Delete task: {}?", task.title),
+                        String::new(),
+                        format!(
+                            "This task is large or seeding. Type \"{}\" below to confirm.",
+                            expected.trim()
+                        ),
+                        String::new(),
+                        "  Enter  — delete the task, unfinished data is discarded".into(),
+                        "  Ctrl-d — delete the task and move the downloaded data".into(),
+                        "           (even if incomplete) to the destination folder".into(),
+                        "  Esc    — cancel".into(),
+                    ],
+                    false,
+                );
+                return;
+            }
 
-            let result = client.get_tasks().await;
-            match result {
-                Ok(result) => {
-                    self.tasks = result.task;
+            self.show_popup(
+                vec![
+                    format!("Delete task: {}?", task.title),
+                    String::new(),
+                    "  d / y / Enter — delete the task, unfinished data is discarded".into(),
+                    "  D             — delete the task and move the downloaded data".into(),
+                    "                  (even if incomplete) to the destination folder".into(),
+                    "  n / Esc       — cancel".into(),
+                ],
+                false,
+            );
+        }
+    }
 
-                    let newly_finished: Vec<String> = self
-                        .tasks
-                        .iter()
-                        .filter(|t| {
-                            let current_status = format!("{:?}", t.status);
-                            current_status == "Finished"
-                                && previous_statuses
-                                    .get(&t.id)
-                                    .map(|prev| prev != "Finished")
-                                    .unwrap_or(false)
-                        })
-                        .map(|t| t.title.clone())
-                        .collect();
+    /// Ask before quitting, for `display.confirm_quit`
+    pub fn request_quit(&mut self) {
+        self.pending_action = Some(PendingAction::Quit);
+        self.show_popup(
+            vec![
+                "Quit dstui?".into(),
+                String::new(),
+                "  y / Enter — quit".into(),
+                "  n / Esc   — stay".into(),
+            ],
+            false,
+        );
+    }
 
-                    if self.tasks.is_empty() {
-                        self.selected_task.select(None);
-                    } else if let Some(prev_id) = previously_selected_id {
-                        let sorted = self.sorted_tasks();
-                        let new_idx = sorted.iter().position(|t| t.id == prev_id);
-                        match new_idx {
-                            Some(idx) => self.selected_task.select(Some(idx)),
-                            None => self.selected_task.select(Some(0)),
+    /// Confirm action popup for deleting a task, adding a torrent or quitting
+    ///
+    /// `force` maps to the API's `force_complete` flag when deleting.
+    pub async fn confirm_action(&mut self, force: bool) -> anyhow::Result<()> {
+        self.delete_confirm = None;
+        if let Some(action) = self.pending_action.take() {
+            match action {
+                PendingAction::Quit => {
+                    self.close_popup();
+                    self.quit();
+                }
+                PendingAction::AddFile { bytes, filename } => {
+                    self.close_popup();
+                    if let Err(e) = self.add_file_task(&bytes, &filename).await {
+                        self.show_popup(vec!["Failed to submit file:".into(), e.to_string()], true);
+                    }
+                }
+                PendingAction::DeleteTask(task_id) => {
+                    self.close_popup();
+                    if let Some(api) = &self.api {
+                        match api.delete_task(&task_id, force).await {
+                            Ok(operation) => {
+                                if let Some(failed) = operation.failed_task.first() {
+                                    self.show_popup(
+                                        vec![
+                                            "Failed to delete task:".into(),
+                                            format!(
+                                                "{} (code {})",
+                                                task_error_description(failed.error),
+                                                failed.error
+                                            ),
+                                        ],
+                                        true,
+                                    );
+                                }
+                                if let Err(e) = self.refresh_tasks().await {
+                                    self.show_popup(
+                                        vec![
+                                            "Task deleted but refresh failed:".into(),
+                                            e.to_string(),
+                                        ],
+                                        true,
+                                    );
+                                }
+                            }
+                            Err(e) => {
+                                self.show_popup(
+                                    vec!["Failed to delete task:".into(), e.to_string()],
+                                    true,
+                                );
+                            }
                         }
-                    } else {
-                        self.selected_task.select(Some(0));
                     }
+                }
+            }
+        }
+        Ok(())
+    }
 
-                    self.update_info_counts();
+    /// Cancel action
+    pub fn cancel_action(&mut self) {
+        self.pending_action = None;
+        self.delete_confirm = None;
+        self.close_popup();
+    }
+
+    pub fn sort_by(&mut self, column: SortColumn) {
+        if self.sort_column == column {
+            // Same column — toggle order
+            self.sort_order = match self.sort_order {
+                SortOrder::Ascending => SortOrder::Descending,
+                SortOrder::Descending => SortOrder::Ascending,
+            };
+        } else {
+            self.sort_column = column;
+            self.sort_order = SortOrder::Ascending;
+        }
+        self.selected_task.select(Some(0));
+        self.clamp_task_selection();
+        self.reset_info_scroll();
+    }
+
+    /// Keep the selected row inside the visible task list, nothing is selected when the list
+    /// is empty, e.g. after a refresh removed tasks or a filter matches nothing
+    pub fn clamp_task_selection(&mut self) {
+        let row_count = self.sorted_tasks().len();
+        let selected = match self.selected_task.selected() {
+            _ if row_count == 0 => None,
+            Some(row) => Some(row.min(row_count - 1)),
+            None => Some(0),
+        };
+        self.selected_task.select(selected);
+    }
+
+    pub fn sorted_tasks(&self) -> Vec<&Task> {
+        let fuzzy = self.fuzzy_filter && !self.filter_text.is_empty();
+        let filter = self.filter_text.to_lowercase();
+        let mut tasks: Vec<(i64, &Task)> = self
+            .tasks
+            .iter()
+            .filter(|t| {
+                !self.hide_finished
+                    || !matches!(t.status, TaskStatus::Finished | TaskStatus::Seeding)
+            })
+            .filter_map(|t| {
+                if fuzzy {
+                    fuzzy_match(&filter, &t.title).map(|(score, _)| (score, t))
+                } else {
+                    t.title.to_lowercase().contains(&filter).then_some((0, t))
+                }
+            })
+            .collect();
+
+        tasks.sort_by(|(_, a), (_, b)| {
+            let ord = match self.sort_column {
+                SortColumn::Name => a.title.cmp(&b.title),
+                SortColumn::Size => a.size.cmp(&b.size),
+                SortColumn::Progress => a
+                    .calculate_progress()
+                    .partial_cmp(&b.calculate_progress())
+                    .unwrap_or(std::cmp::Ordering::Equal),
+                SortColumn::Ratio => a
+                    .calculate_ratio()
+                    .partial_cmp(&b.calculate_ratio())
+                    .unwrap_or(std::cmp::Ordering::Equal),
+                SortColumn::Status => format!("{:?}", a.status).cmp(&format!("{:?}", b.status)),
+                SortColumn::Downloaded => {
+                    let a_dl = a
+                        .additional
+                        .as_ref()
+                        .and_then(|x| x.transfer.as_ref())
+                        .map(|t| t.size_downloaded)
+                        .unwrap_or(0);
+                    let b_dl = b
+                        .additional
+                        .as_ref()
+                        .and_then(|x| x.transfer.as_ref())
+                        .map(|t| t.size_downloaded)
+                        .unwrap_or(0);
+                    a_dl.cmp(&b_dl)
+                }
+                SortColumn::Uploaded => {
+                    let a_ul = a
+                        .additional
+                        .as_ref()
+                        .and_then(|x| x.transfer.as_ref())
+                        .map(|t| t.size_uploaded)
+                        .unwrap_or(0);
+                    let b_ul = b
+                        .additional
+                        .as_ref()
+                        .and_then(|x| x.transfer.as_ref())
+                        .map(|t| t.size_uploaded)
+                        .unwrap_or(0);
+                    a_ul.cmp(&b_ul)
+                }
+                SortColumn::UploadSpeed => {
+                    let a_us = a
+                        .additional
+                        .as_ref()
+                        .and_then(|x| x.transfer.as_ref())
+                        .map(|t| t.speed_upload)
+                        .unwrap_or(0);
+                    let b_us = b
+                        .additional
+                        .as_ref()
+                        .and_then(|x| x.transfer.as_ref())
+                        .map(|t| t.speed_upload)
+                        .unwrap_or(0);
+                    a_us.cmp(&b_us)
+                }
+                SortColumn::DownloadSpeed => {
+                    let a_ds = a
+                        .additional
+                        .as_ref()
+                        .and_then(|x| x.transfer.as_ref())
+                        .map(|t| t.speed_download)
+                        .unwrap_or(0);
+                    let b_ds = b
+                        .additional
+                        .as_ref()
+                        .and_then(|x| x.transfer.as_ref())
+                        .map(|t| t.speed_download)
+                        .unwrap_or(0);
+                    a_ds.cmp(&b_ds)
+                }
+            };
+
+            match self.sort_order {
+                SortOrder::Ascending => ord,
+                SortOrder::Descending => ord.reverse(),
+            }
+        });
+        // Best matches first, the sort column only orders equally good ones
+        if fuzzy {
+            tasks.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        }
+
+        tasks.into_iter().map(|(_, t)| t).collect()
+    }
+
+    /// Positions of the characters in `title` the fuzzy filter matched, to highlight them
+    pub fn filter_matches(&self, title: &str) -> Vec<usize> {
+        if !self.fuzzy_filter || self.filter_text.is_empty() {
+            return Vec::new();
+        }
+        fuzzy_match(&self.filter_text, title)
+            .map(|(_, positions)| positions)
+            .unwrap_or_default()
+    }
+
+    pub fn selected_task_in_sorted(&self) -> Option<usize> {
+        // Returns the index into self.tasks of the currently selected sorted row
+        let idx = self.selected_task.selected()?;
+        let sorted = self.sorted_tasks();
+        sorted
+            .get(idx)
+            .and_then(|task| self.tasks.iter().position(|t| t.id == task.id))
+    }
+
+    pub fn show_notification(&mut self, message: String) {
+        self.notification = Some(Notification {
+            message,
+            ticks_remaining: (4.0 * TICK_FPS) as u64, // visible for 4 seconds
+        });
+    }
+
+    // RSS methods
+    pub async fn open_rss(&mut self) -> anyhow::Result<()> {
+        let Some(api) = &self.api else {
+            return Ok(());
+        };
+        let sites = api.list_rss_sites().await?;
+
+        let mut selected_site = TableState::default();
+        if !sites.is_empty() {
+            selected_site.select(Some(0));
+        }
+        self.rss = Some(RssView {
+            sites,
+            items: vec![],
+            selected_site,
+            selected_item: TableState::default(),
+            items_focused: false,
+        });
+        self.load_rss_items().await
+    }
+
+    pub async fn load_rss_items(&mut self) -> anyhow::Result<()> {
+        let (Some(api), Some(rss)) = (&self.api, &mut self.rss) else {
+            return Ok(());
+        };
+        let Some(site) = rss.selected_site.selected().and_then(|i| rss.sites.get(i)) else {
+            return Ok(());
+        };
+
+        rss.items = api.list_rss_feed_items(site.id).await?;
+        rss.selected_item
+            .select(if rss.items.is_empty() { None } else { Some(0) });
+        Ok(())
+    }
+
+    pub async fn download_rss_item(&mut self) -> anyhow::Result<()> {
+        let uri = self
+            .rss
+            .as_ref()
+            .and_then(|rss| rss.selected_item.selected().and_then(|i| rss.items.get(i)))
+            .map(|item| item.download_uri.clone())
+            .unwrap_or_default();
+
+        if uri.is_empty() {
+            self.show_notification("This item has no download link".to_string());
+            return Ok(());
+        }
 
-                    if !newly_finished.is_empty() {
-                        let message = if newly_finished.len() == 1 {
-                            format!("✓ {}", newly_finished[0])
-                        } else {
-                            format!("✓ {} tasks completed", newly_finished.len())
-                        };
-                        self.show_notification(message);
-                    }
-                }
-                Err(e) => {
-                    self.show_popup(vec!["Failed to get tasks:".into(), e.to_string()], true);
-                }
-            }
+        if let Some(api) = &self.api {
+            api.create_task(&uri, &self.destination, &CreateTaskOptions::default())
+                .await?;
+            self.show_notification("✓ Task added from RSS".to_string());
+            self.refresh_tasks().await?;
         }
         Ok(())
     }
 
-    // Task panel scroll and row selection
-    pub fn next_task_row(&mut self) {
-        move_next(&mut self.selected_task, self.tasks.len());
-        self.reset_info_scroll();
-        self.update_info_counts();
-    }
-    pub fn previous_task_row(&mut self) {
-        move_previous(&mut self.selected_task);
-        self.reset_info_scroll();
-        self.update_info_counts();
+    // BT search methods
+    pub fn open_bt_search(&mut self) {
+        if self.api.is_none() {
+            return;
+        }
+        self.bt_search = Some(BtSearchView {
+            input: Input::default(),
+            cursor_pos: None,
+            input_focused: true,
+            task_id: None,
+            finished: false,
+            results: vec![],
+            selected: TableState::default(),
+            poll_ticks: 0,
+        });
     }
 
-    // Info Panel scroll and row selection
-    fn reset_info_scroll(&mut self) {
-        self.tracker_scroll = 0;
-        self.peer_scroll = 0;
-        self.file_scroll = 0;
+    pub async fn start_bt_search(&mut self) -> anyhow::Result<()> {
+        let (Some(api), Some(search)) = (&self.api, &mut self.bt_search) else {
+            return Ok(());
+        };
+
+        // Only one search at a time, drop the previous one on the DiskStation
+        if let Some(task_id) = search.task_id.take() {
+            let _ = api.bt_search_clean(&task_id).await;
+        }
+
+        let keyword = search.input.value().trim().to_string();
+        search.results.clear();
+        search.selected.select(None);
+        search.finished = false;
+        search.poll_ticks = 0;
+        search.task_id = Some(api.bt_search_start(&keyword, "enabled").await?);
+        search.input_focused = false;
+        Ok(())
     }
 
-    pub fn scroll_info_down(&mut self) {
-        match self.selected_tab {
-            2 => {
-                let max = self.tracker_count.saturating_sub(self.tracker_inner_height);
-                self.tracker_scroll = (self.tracker_scroll + 1).min(max);
-            }
-            3 => {
-                let max = self.peer_count.saturating_sub(self.peer_inner_height);
-                self.peer_scroll = (self.peer_scroll + 1).min(max);
-            }
-            4 => {
-                let max = self.file_count.saturating_sub(self.file_inner_height);
-                self.file_scroll = (self.file_scroll + 1).min(max);
-            }
-            _ => {}
+    pub async fn poll_bt_search(&mut self) -> anyhow::Result<()> {
+        let (Some(api), Some(search)) = (&self.api, &mut self.bt_search) else {
+            return Ok(());
+        };
+        let Some(task_id) = &search.task_id else {
+            return Ok(());
+        };
+
+        let list = api.bt_search_list(task_id).await?;
+        search.results = list.items;
+        search.finished = list.finished;
+        if search.results.is_empty() {
+            search.selected.select(None);
+        } else if search.selected.selected().is_none() {
+            search.selected.select(Some(0));
         }
+
+        if search.finished && search.results.is_empty() {
+            self.show_notification("No search results".to_string());
+        }
+        Ok(())
     }
 
-    pub fn scroll_info_up(&mut self) {
-        match self.selected_tab {
-            2 => self.tracker_scroll = self.tracker_scroll.saturating_sub(1),
-            3 => self.peer_scroll = self.peer_scroll.saturating_sub(1),
-            4 => self.file_scroll = self.file_scroll.saturating_sub(1),
-            _ => {}
+    pub async fn download_bt_search_result(&mut self) -> anyhow::Result<()> {
+        let uri = self
+            .bt_search
+            .as_ref()
+            .and_then(|search| {
+                search
+                    .selected
+                    .selected()
+                    .and_then(|i| search.results.get(i))
+            })
+            .map(|result| result.download_uri.clone())
+            .unwrap_or_default();
+
+        if uri.is_empty() {
+            self.show_notification("This result has no download link".to_string());
+            return Ok(());
         }
+
+        if let Some(api) = &self.api {
+            api.create_task(&uri, &self.destination, &CreateTaskOptions::default())
+                .await?;
+            self.show_notification("✓ Task added from search".to_string());
+            self.refresh_tasks().await?;
+        }
+        Ok(())
     }
 
-    pub fn selected_task_index(&self) -> Option<usize> {
-        self.selected_task.selected()
+    pub async fn close_bt_search(&mut self) {
+        let Some(search) = self.bt_search.take() else {
+            return;
+        };
+        // Cleaning up is best effort, the DiskStation drops old searches on its own as well
+        if let (Some(api), Some(task_id)) = (&self.api, search.task_id) {
+            let _ = api.bt_search_clean(&task_id).await;
+        }
     }
 
-    pub fn show_popup(&mut self, lines: Vec<String>, error: bool) {
-        self.popup = Some(PopupState {
-            lines,
-            error,
-            scroll: 0,
+    // Destination folder browser methods
+    pub async fn load_folder(&mut self) -> anyhow::Result<()> {
+        let (Some(api), Some(browser)) = (&self.api, &mut self.folder_browser) else {
+            return Ok(());
+        };
+        browser.folders = match &browser.path {
+            Some(path) => api.list_folders(path).await?,
+            None => api.list_shared_folders().await?,
+        };
+        browser.selected.select(if browser.folders.is_empty() {
+            None
+        } else {
+            Some(0)
         });
+        Ok(())
     }
 
-    pub fn close_popup(&mut self) {
-        self.popup = None;
-    }
+    /// Use the highlighted folder as the destination for new tasks and remember it in the
+    /// config file
+    pub fn choose_destination(&mut self) -> anyhow::Result<()> {
+        let Some(folder) = self.folder_browser.as_ref().and_then(|browser| {
+            browser
+                .selected
+                .selected()
+                .and_then(|i| browser.folders.get(i))
+        }) else {
+            return Ok(());
+        };
 
-    pub fn update_info_counts(&mut self) {
-        if let Some(real_idx) = self.selected_task_in_sorted()
-            && let Some(task) = self.tasks.get(real_idx)
-        {
-            self.tracker_count = task
-                .additional
-                .as_ref()
-                .and_then(|a| a.tracker.as_ref())
-                .map(|t| t.len())
-                .unwrap_or(0);
-            self.peer_count = task
-                .additional
-                .as_ref()
-                .and_then(|a| a.peer.as_ref())
-                .map(|p| p.len())
-                .unwrap_or(0);
-            self.file_count = task
-                .additional
-                .as_ref()
-                .and_then(|a| a.file.as_ref())
-                .map(|f| f.len())
-                .unwrap_or(0);
-        }
-    }
+        // Download Station wants the path without the leading slash
+        let destination = folder.path.trim_start_matches('/').to_string();
+        self.destination = destination.clone();
+        self.folder_browser = None;
+        self.show_notification(format!("✓ New tasks go to {}", destination));
 
-    // File picker methods
-    pub fn open_file_picker(&mut self) {
-        let theme = Theme::default()
-            .add_default_title()
-            .with_block(
-                Block::bordered()
-                    .border_type(BorderType::Rounded)
-                    .border_style(Style::default().fg(Color::Yellow))
-                    .title(" File Picker "),
-            )
-            .with_title_bottom(|_| {
-                " .torrent / .nzb / .txt · Enter to select · Esc to cancel ".into()
-            });
-        self.file_explorer = Some(FileExplorerBuilder::build_with_theme(theme).unwrap());
+        let mut config = crate::config::load_config()?;
+        config.downloads.destination = destination;
+        crate::config::save_config(&config)
     }
-    pub async fn submit_selected_file(&mut self) -> anyhow::Result<()> {
-        let allowed_extensions = ["torrent", "nzb", "txt"];
 
-        // Extract everything we need from the explorer before any API calls
-        let file_data = if let Some(explorer) = &self.file_explorer {
-            let path = explorer.current();
-            if path.is_file() {
-                let ext = path.path.extension().and_then(|e| e.to_str());
-                if ext
-                    .map(|e| allowed_extensions.contains(&e))
-                    .unwrap_or(false)
-                {
-                    let filename = path
-                        .path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("upload.torrent")
-                        .to_string();
-                    match std::fs::read(&path.path) {
-                        Ok(bytes) => Some((bytes, filename)),
-                        Err(e) => {
-                            self.show_popup(
-                                vec!["Failed to read file:".into(), e.to_string()],
-                                true,
-                            );
-                            None
-                        }
-                    }
-                } else {
-                    self.show_popup(
-                        vec![
-                            "Unsupported file type.".into(),
-                            format!("Got: .{}", ext.unwrap_or("unknown")),
-                            "Only .torrent, .nzb and .txt are supported.".into(),
-                        ],
-                        true,
-                    );
-                    None
-                }
-            } else {
-                // selected a directory, do nothing silently
-                None
-            }
-        } else {
-            None
+    // Server settings methods
+    pub async fn open_server_settings(&mut self) -> anyhow::Result<()> {
+        let Some(api) = &self.api else {
+            return Ok(());
         };
+        let config = api.get_server_config().await?;
+        let schedule = api.get_schedule_config().await?;
 
-        self.file_explorer = None;
+        let mut selected = TableState::default();
+        selected.select(Some(0));
+        self.server_settings = Some(ServerSettingsView {
+            config,
+            schedule,
+            selected,
+        });
+        Ok(())
+    }
 
-        if let Some((file_bytes, filename)) = file_data
-            && let Some(client) = &self.client
-        {
-            let _ = client
-                .create_task_from_file(&file_bytes, &filename, &self.destination)
-                .await;
-            if let Err(e) = self.refresh_tasks().await {
-                self.show_popup(
-                    vec!["Task added but refresh failed:".into(), e.to_string()],
-                    true,
-                );
+    pub async fn toggle_server_setting(&mut self) -> anyhow::Result<()> {
+        let (Some(api), Some(settings)) = (&self.api, &mut self.server_settings) else {
+            return Ok(());
+        };
+        let Some(setting) = settings
+            .selected
+            .selected()
+            .and_then(|i| ServerSetting::ALL.get(i).copied())
+        else {
+            return Ok(());
+        };
+
+        let enable = !settings.is_enabled(setting);
+        let value = enable.to_string();
+        match setting {
+            ServerSetting::Emule => api.set_server_config(&[("emule_enabled", &value)]).await?,
+            ServerSetting::UnzipService => {
+                api.set_server_config(&[("unzip_service_enabled", &value)])
+                    .await?
             }
+            ServerSetting::Schedule => api.set_schedule_config(&[("enabled", &value)]).await?,
         }
 
+        // Read the settings back so the popup shows what the DiskStation actually stored
+        settings.config = api.get_server_config().await?;
+        settings.schedule = api.get_schedule_config().await?;
+
+        self.show_notification(format!(
+            "✓ {} {}",
+            setting.label(),
+            if enable { "enabled" } else { "disabled" }
+        ));
         Ok(())
     }
 
-    // Add by URL methods
-    pub fn open_url_input(&mut self) {
-        self.url_input = Some(Input::default());
+    // Server profile methods
+    pub fn open_profile_picker(&mut self) -> anyhow::Result<()> {
+        let config = crate::config::load_config()?;
+        let profiles: Vec<(String, String)> = config
+            .profiles
+            .into_iter()
+            .map(|p| (p.name, p.connection.url))
+            .collect();
+
+        let mut selected = TableState::default();
+        selected.select(Some(
+            profiles
+                .iter()
+                .position(|(name, _)| *name == self.profile_name)
+                .unwrap_or(0),
+        ));
+        self.profile_picker = Some(ProfilePicker { profiles, selected });
+        Ok(())
     }
 
-    pub async fn submit_url(&mut self) -> anyhow::Result<()> {
-        if let Some(input) = &self.url_input {
-            let url = input.value().trim().to_string();
-            if url.is_empty() {
-                self.show_popup(vec!["Please enter a URL.".into()], true);
-            } else if !url.starts_with("http://")
-                && !url.starts_with("https://")
-                && !url.starts_with("magnet:")
-            {
-                self.show_popup(
-                    vec![
-                        "Invalid URL format.".into(),
-                        "Must start with http://, https://, or magnet:".into(),
-                    ],
-                    true,
-                );
-            } else if let Some(client) = &self.client {
-                self.loading = true;
-                let _ = client.create_task(&url, &self.destination).await;
-                self.events.send(AppEvent::Refresh);
-            }
+    /// Log in to the selected profile's DiskStation and, once that worked, log out of the
+    /// current one and start over with the new task list
+    pub async fn switch_profile(&mut self) -> anyhow::Result<()> {
+        let Some(name) = self.profile_picker.take().and_then(|picker| {
+            picker
+                .selected
+                .selected()
+                .and_then(|i| picker.profiles.into_iter().nth(i))
+                .map(|(name, _)| name)
+        }) else {
+            return Ok(());
+        };
+        if name == self.profile_name {
+            return Ok(());
+        }
+
+        let config = crate::config::load_config()?;
+        let profile = config
+            .profiles
+            .iter()
+            .find(|p| p.name == name)
+            .with_context(|| format!("Profile {} is no longer in the config file", name))?;
+        let api = DsApi::new(&profile.connection)?;
+        api.authorize().await?;
+        self.use_profile(api, &config, profile).await
+    }
+
+    /// Start over with a freshly logged in `api` for `profile`, logging out of the current
+    /// DiskStation
+    async fn use_profile(
+        &mut self,
+        api: DsApi,
+        config: &Config,
+        profile: &ServerProfile,
+    ) -> anyhow::Result<()> {
+        if let Some(previous) = self.api.replace(api) {
+            // The session times out on its own, no need to bother the user if this fails
+            let _ = previous.logout().await;
         }
-        self.url_input = None;
+
+        self.profile_name = profile.name.clone();
+        self.server_url = profile.connection.url.clone();
+        self.destination = config.downloads.destination.clone();
+        self.refresh_interval = config
+            .refresh_interval(profile)
+            .map(|s| (s as f64 * TICK_FPS) as u64);
+        self.refresh_paused = false;
+        self.tick_count = 0;
+        self.tasks.clear();
+        self.selected_task.select(None);
+        self.details_task_id = None;
+        self.statistics = None;
+        self.speed_history.clear();
+        self.task_speed_history.clear();
+        self.task_transferred.clear();
+        self.session_transferred = (0, 0);
+        self.zero_speed_since.clear();
+        self.auto_paused.clear();
+        self.task_tabs.clear();
+        self.error_count = 0;
+        self.reset_info_scroll();
+        self.connection_status = ConnectionStatus::Connected;
+
+        self.refresh_tasks().await?;
+        self.refresh_statistics().await;
+        self.show_notification(format!("✓ Connected to {}", self.profile_name));
         Ok(())
     }
 
-    // Toggle task status (pause/resume)
-    pub async fn toggle_task(&mut self) -> anyhow::Result<()> {
-        // Extract task id and status before borrowing client
-        let task_info = if let Some(real_idx) = self.selected_task_in_sorted() {
-            self.tasks.get(real_idx).map(|task| {
-                let should_pause = matches!(
-                    task.status,
-                    TaskStatus::Downloading | TaskStatus::Waiting | TaskStatus::Seeding
-                );
-                let is_paused = matches!(task.status, TaskStatus::Paused | TaskStatus::Finished);
-                (task.id.clone(), should_pause, is_paused)
+    // Settings editor methods
+    pub fn open_settings_editor(&mut self) -> anyhow::Result<()> {
+        let config = crate::config::load_config()?;
+        let profile = config
+            .profiles
+            .iter()
+            .find(|p| p.name == self.profile_name)
+            .with_context(|| {
+                format!(
+                    "Profile {} is no longer in the config file",
+                    self.profile_name
+                )
+            })?;
+
+        let refresh = config
+            .refresh_interval(profile)
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "off".to_string());
+        let inputs = SettingsField::ALL
+            .iter()
+            .map(|field| {
+                Input::new(match field {
+                    SettingsField::Url => profile.connection.url.clone(),
+                    SettingsField::Username => profile.connection.username.clone(),
+                    SettingsField::Password => profile.connection.password.clone(),
+                    SettingsField::RefreshInterval => refresh.clone(),
+                    SettingsField::Destination => config.downloads.destination.clone(),
+                })
             })
-        } else {
-            None
+            .collect();
+        self.settings_editor = Some(SettingsEditor {
+            inputs,
+            focused: 0,
+            error: None,
+            cursor_pos: None,
+        });
+        Ok(())
+    }
+
+    /// Check the edited settings, log in with them and only then write them to the config
+    /// file, so a typo can't leave a config that doesn't connect
+    pub async fn save_settings(&mut self) -> anyhow::Result<()> {
+        let Some(editor) = &mut self.settings_editor else {
+            return Ok(());
         };
+        let value = |field: SettingsField| editor.inputs[field as usize].value().trim().to_string();
 
-        if let Some((task_id, should_pause, is_paused)) = task_info {
-            if let Some(client) = &self.client {
-                let result = if should_pause {
-                    client.pause(&task_id).await.map(|_| ())
-                } else if is_paused {
-                    client.resume(&task_id).await.map(|_| ())
-                } else {
-                    return Ok(());
-                };
+        let url = match normalize_server_url(&value(SettingsField::Url)) {
+            Ok(url) => url,
+            Err(e) => {
+                editor.error = Some(e);
+                return Ok(());
+            }
+        };
+        let username = value(SettingsField::Username);
+        // Leading or trailing spaces can be part of a password
+        let password = editor.inputs[SettingsField::Password as usize]
+            .value()
+            .to_string();
+        let destination = value(SettingsField::Destination);
+        let refresh = value(SettingsField::RefreshInterval);
 
-                if let Err(e) = result {
-                    self.show_popup(vec!["Failed to toggle task:".into(), e.to_string()], true);
+        let refresh_interval = match refresh.to_lowercase().as_str() {
+            "" | "off" | "0" => Some(0),
+            secs => match secs.parse::<u64>() {
+                Ok(secs) => Some(secs),
+                Err(_) => {
+                    editor.error =
+                        Some("Refresh interval must be a number of seconds or off".to_string());
                     return Ok(());
                 }
-            }
+            },
+        };
+        if username.is_empty() {
+            editor.error = Some("Username can't be empty".to_string());
+            return Ok(());
+        }
+        if destination.is_empty() {
+            editor.error = Some("Destination can't be empty".to_string());
+            return Ok(());
+        }
 
-            if let Err(e) = self.refresh_tasks().await {
-                self.show_popup(vec!["Failed to refresh tasks:".into(), e.to_string()], true);
-            }
+        let mut config = crate::config::load_config()?;
+        let Some(index) = config
+            .profiles
+            .iter()
+            .position(|p| p.name == self.profile_name)
+        else {
+            editor.error = Some(format!(
+                "Profile {} is no longer in the config file",
+                self.profile_name
+            ));
+            return Ok(());
+        };
+        let profile = &mut config.profiles[index];
+        profile.connection.url = url;
+        profile.connection.username = username;
+        profile.connection.password = password;
+        profile.refresh_interval = refresh_interval;
+        config.downloads.destination = destination;
+
+        let api = DsApi::new(&config.profiles[index].connection)?;
+        if let Err(e) = api.authorize().await {
+            editor.error = Some(format!("Login failed, nothing saved: {}", e));
+            return Ok(());
         }
+        crate::config::save_config(&config)?;
 
+        self.settings_editor = None;
+        self.use_profile(api, &config, &config.profiles[index])
+            .await?;
+        self.show_notification("✓ Settings saved".to_string());
         Ok(())
     }
 
-    // Complete task
-    pub async fn complete_task(&mut self) -> anyhow::Result<()> {
-        if let Some(real_idx) = self.selected_task_in_sorted()
-            && let Some(task) = self.tasks.get(real_idx)
-            && let Some(client) = &self.client
-        {
-            if let Err(e) = client.complete(&task.id).await {
-                self.show_popup(vec!["Failed to complete task:".into(), e.to_string()], true);
-            }
+    // Speed limit methods
+    pub fn open_speed_limit(&mut self) {
+        let Some(task) = self
+            .selected_task_in_sorted()
+            .and_then(|idx| self.tasks.get(idx))
+        else {
+            return;
+        };
+        // The task entities don't carry the current limits, start from unlimited
+        self.speed_limit = Some(SpeedLimitForm {
+            task_id: task.id.clone(),
+            title: task.title.clone(),
+            inputs: [Input::new("0".to_string()), Input::new("0".to_string())],
+            focused: 0,
+            error: None,
+            cursor_pos: None,
+        });
+    }
 
-            if let Err(e) = self.refresh_tasks().await {
-                self.show_popup(vec!["Failed to refresh tasks:".into(), e.to_string()], true);
+    /// Send the limits of the speed limit popup, keeping it open with the reason when a value
+    /// isn't a number or the DiskStation can't limit single tasks
+    pub async fn save_speed_limit(&mut self) -> anyhow::Result<()> {
+        let Some(form) = &mut self.speed_limit else {
+            return Ok(());
+        };
+        let limit = |field: SpeedLimitField| parse_speed_limit(form.inputs[field as usize].value());
+        let (down, up) = match (
+            limit(SpeedLimitField::Download),
+            limit(SpeedLimitField::Upload),
+        ) {
+            (Ok(down), Ok(up)) => (down, up),
+            (Err(e), _) | (_, Err(e)) => {
+                form.error = Some(e);
+                return Ok(());
             }
-        }
-        Ok(())
-    }
+        };
 
-    /// Clear completed tasks
-    pub async fn clear_completed(&mut self) -> anyhow::Result<()> {
-        if let Some(client) = &self.client {
-            if let Err(e) = client.clear_completed().await {
-                self.show_popup(
-                    vec!["Failed to clear completed task(s):".into(), e.to_string()],
-                    true,
+        let Some(api) = &self.api else {
+            return Ok(());
+        };
+        match api.set_task_speed_limit(&form.task_id, down, up).await {
+            Ok(()) => {}
+            Err(e) if e.is_unsupported() => {
+                form.error = Some(
+                    "This Download Station version can't limit single tasks, only the global limits in DSM"
+                        .to_string(),
                 );
+                return Ok(());
             }
-            if let Err(e) = self.refresh_tasks().await {
-                self.show_popup(vec!["Failed to refresh tasks:".into(), e.to_string()], true);
-            }
+            Err(e) => return Err(e.into()),
         }
+
+        let title = form.title.clone();
+        self.speed_limit = None;
+        self.show_notification(format!("✓ Speed limit set for {}", title));
         Ok(())
     }
 
-    /// Delete selected task
-    pub fn request_delete_task(&mut self) {
-        if let Some(real_idx) = self.selected_task_in_sorted()
-            && let Some(task) = self.tasks.get(real_idx)
-        {
-            self.pending_action = Some(PendingAction::DeleteTask(task.id.clone()));
+    /// Hand the terminal to $VISUAL or $EDITOR with the config file, then reload the config and
+    /// reconnect with the profile in use
+    async fn edit_config_file(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+        let Some(editor) = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .ok()
+            .filter(|editor| !editor.trim().is_empty())
+        else {
             self.show_popup(
                 vec![
-                    format!("Delete task: {}?", task.title),
-                    String::new(),
-                    "  y / Enter — confirm".into(),
-                    "  n / Esc   — cancel".into(),
+                    "Can't edit the config file, $EDITOR is not set.".into(),
+                    format!("The config file is {}", self.config_path),
                 ],
-                false,
+                true,
             );
+            return Ok(());
+        };
+        let path = config_path()?;
+        // Like "code --wait", the editor can come with arguments
+        let mut words = editor.split_whitespace();
+        let mut command = tokio::process::Command::new(words.next().unwrap_or_default());
+        command.args(words).arg(&path);
+
+        self.events.suspend(true);
+        execute!(
+            stdout(),
+            DisableBracketedPaste,
+            DisableMouseCapture,
+            LeaveAlternateScreen,
+            cursor::Show
+        )?;
+        disable_raw_mode()?;
+        let status = command.status().await;
+        enable_raw_mode()?;
+        execute!(
+            stdout(),
+            EnterAlternateScreen,
+            EnableBracketedPaste,
+            EnableMouseCapture
+        )?;
+        terminal.clear()?;
+        self.events.suspend(false);
+
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                self.show_popup(
+                    vec![format!(
+                        "{} exited with {}, config not reloaded",
+                        editor, status
+                    )],
+                    true,
+                );
+                return Ok(());
+            }
+            Err(e) => {
+                self.show_popup(
+                    vec![format!("Failed to start {}:", editor), e.to_string()],
+                    true,
+                );
+                return Ok(());
+            }
         }
-    }
 
-    /// Confirm action popup (delete uses this only at the moment)
-    pub async fn confirm_action(&mut self) -> anyhow::Result<()> {
-        if let Some(action) = self.pending_action.take() {
-            match action {
-                PendingAction::DeleteTask(task_id) => {
-                    self.close_popup();
-                    if let Some(client) = &self.client {
-                        match client.delete_task(&task_id, false).await {
-                            Ok(_) => {
-                                if let Err(e) = self.refresh_tasks().await {
-                                    self.show_popup(
-                                        vec![
-                                            "Task deleted but refresh failed:".into(),
-                                            e.to_string(),
-                                        ],
-                                        true,
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                self.show_popup(
-                                    vec!["Failed to delete task:".into(), e.to_string()],
-                                    true,
-                                );
-                            }
-                        }
-                    }
-                }
+        self.reload_config().await?;
+        if self.popup.is_some() {
+            // reload_config explained what is wrong with the file
+            return Ok(());
+        }
+        match self.reconnect().await {
+            Ok(()) => self.show_notification("✓ Config reloaded, reconnected".to_string()),
+            Err(e) => {
+                self.show_popup(vec!["Failed to reconnect:".into(), e.to_string()], true);
             }
         }
         Ok(())
     }
 
-    /// Cancel action
-    pub fn cancel_action(&mut self) {
-        self.pending_action = None;
-        self.close_popup();
+    /// Log in again with the connection settings of the current profile from the config file
+    async fn reconnect(&mut self) -> anyhow::Result<()> {
+        let config = crate::config::load_config()?;
+        let profile = config
+            .profiles
+            .iter()
+            .find(|p| p.name == self.profile_name)
+            .with_context(|| {
+                format!(
+                    "Profile {} is no longer in the config file",
+                    self.profile_name
+                )
+            })?;
+        let api = DsApi::new(&profile.connection)?;
+        api.authorize().await?;
+        self.use_profile(api, &config, profile).await?;
+        self.show_notification(format!("✓ Reconnected to {}", self.profile_name));
+        Ok(())
     }
 
-    pub fn sort_by(&mut self, column: SortColumn) {
-        if self.sort_column == column {
-            // Same column — toggle order
-            self.sort_order = match self.sort_order {
-                SortOrder::Ascending => SortOrder::Descending,
-                SortOrder::Descending => SortOrder::Ascending,
-            };
-        } else {
-            self.sort_column = column;
-            self.sort_order = SortOrder::Ascending;
+    /// Help popup listing the keys from the keymap, so rebound keys show up as configured
+    pub fn show_help(&mut self) {
+        let mut lines = vec![String::new(), "Help:".into()];
+        for action in Action::ALL {
+            let keys = keys_for(&self.keymap, action);
+            if keys.is_empty() {
+                continue;
+            }
+            lines.push(format!(
+                "{:<9} — {}",
+                keys.join(" / "),
+                action.description()
+            ));
         }
-        self.selected_task.select(Some(0));
-        self.reset_info_scroll();
+        lines.push("Mouse     — click to select tasks and tabs, wheel to scroll".into());
+        lines.push(
+            "j / k     — scroll help text and move in the file picker and other lists".into(),
+        );
+        lines.push(String::new());
+        lines.push(format!("Config:   {}", self.config_path));
+        self.show_popup(lines, false);
     }
 
-    pub fn sorted_tasks(&self) -> Vec<&Task> {
-        let mut tasks: Vec<&Task> = self
-            .tasks
-            .iter()
-            .filter(|t| {
-                self.filter_text.is_empty()
-                    || t.title
-                        .to_lowercase()
-                        .contains(&self.filter_text.to_lowercase())
-            })
-            .collect();
-
-        tasks.sort_by(|a, b| {
-            let ord = match self.sort_column {
-                SortColumn::Name => a.title.cmp(&b.title),
-                SortColumn::Size => a.size.cmp(&b.size),
-                SortColumn::Progress => a
-                    .calculate_progress()
-                    .partial_cmp(&b.calculate_progress())
-                    .unwrap_or(std::cmp::Ordering::Equal),
-                SortColumn::Ratio => a
-                    .calculate_ratio()
-                    .partial_cmp(&b.calculate_ratio())
-                    .unwrap_or(std::cmp::Ordering::Equal),
-                SortColumn::Status => format!("{:?}", a.status).cmp(&format!("{:?}", b.status)),
-                SortColumn::Downloaded => {
-                    let a_dl = a
-                        .additional
-                        .as_ref()
-                        .and_then(|x| x.transfer.as_ref())
-                        .map(|t| t.size_downloaded)
-                        .unwrap_or(0);
-                    let b_dl = b
-                        .additional
-                        .as_ref()
-                        .and_then(|x| x.transfer.as_ref())
-                        .map(|t| t.size_downloaded)
-                        .unwrap_or(0);
-                    a_dl.cmp(&b_dl)
-                }
-                SortColumn::Uploaded => {
-                    let a_ul = a
-                        .additional
-                        .as_ref()
-                        .and_then(|x| x.transfer.as_ref())
-                        .map(|t| t.size_uploaded)
-                        .unwrap_or(0);
-                    let b_ul = b
-                        .additional
-                        .as_ref()
-                        .and_then(|x| x.transfer.as_ref())
-                        .map(|t| t.size_uploaded)
-                        .unwrap_or(0);
-                    a_ul.cmp(&b_ul)
+    pub async fn reload_config(&mut self) -> anyhow::Result<()> {
+        match crate::config::load_config() {
+            Ok(config) => {
+                self.destination = config.downloads.destination.clone();
+                let refresh_secs =
+                    match config.profiles.iter().find(|p| p.name == self.profile_name) {
+                        Some(profile) => config.refresh_interval(profile),
+                        None => config.downloads.refresh_interval.filter(|&s| s > 0),
+                    };
+                self.refresh_interval = refresh_secs.map(|s| (s as f64 * TICK_FPS) as u64);
+                self.sort_column = SortColumn::from_str(&config.sorting.column);
+                self.sort_order = SortOrder::from_str(&config.sorting.order);
+                self.binary_units = config.display.binary_units;
+                self.use_local_time = config.display.use_local_time;
+                self.table_percent = config.display.table_percent.clamp(10, 100);
+                self.confirm_quit = config.display.confirm_quit;
+                self.enable_notifications = config.display.enable_notifications;
+                self.terminal_title = config.display.terminal_title;
+                self.fuzzy_filter = config.display.fuzzy_filter;
+                if self.hide_finished != config.display.hide_finished {
+                    self.toggle_hide_finished();
                 }
-                SortColumn::UploadSpeed => {
-                    let a_us = a
-                        .additional
-                        .as_ref()
-                        .and_then(|x| x.transfer.as_ref())
-                        .map(|t| t.speed_upload)
-                        .unwrap_or(0);
-                    let b_us = b
-                        .additional
-                        .as_ref()
-                        .and_then(|x| x.transfer.as_ref())
-                        .map(|t| t.speed_upload)
-                        .unwrap_or(0);
-                    a_us.cmp(&b_us)
+                if self.terminal_title {
+                    self.update_terminal_title();
+                } else {
+                    reset_terminal_title();
                 }
-                SortColumn::DownloadSpeed => {
-                    let a_ds = a
-                        .additional
-                        .as_ref()
-                        .and_then(|x| x.transfer.as_ref())
-                        .map(|t| t.speed_download)
-                        .unwrap_or(0);
-                    let b_ds = b
-                        .additional
-                        .as_ref()
-                        .and_then(|x| x.transfer.as_ref())
-                        .map(|t| t.speed_download)
-                        .unwrap_or(0);
-                    a_ds.cmp(&b_ds)
+                self.max_peers = config.display.max_peers;
+                self.tab_per_task = config.display.tab_per_task;
+                self.theme = Theme::from_name(&config.display.theme);
+                self.columns = ColumnId::from_config(&config.display.columns);
+                self.typed_delete_size = config
+                    .downloads
+                    .typed_delete_size_mb
+                    .saturating_mul(1_000_000);
+                self.confirm_add_above = config.downloads.confirm_add_above_bytes;
+                self.adaptive_refresh = config.downloads.adaptive_refresh;
+                self.seed_ratio_target = config.downloads.seed_ratio_target.filter(|&r| r > 0.0);
+                self.stalled_after = stalled_after(&config);
+                // load_config already rejected invalid [keybindings]
+                if let Ok(keymap) = keymap(&config.keybindings) {
+                    self.keymap = keymap;
                 }
-            };
+                self.refresh_paused = false;
+                self.tick_count = 0; // reset so the new interval starts fresh
 
-            match self.sort_order {
-                SortOrder::Ascending => ord,
-                SortOrder::Descending => ord.reverse(),
+                let unknown = ColumnId::unknown(&config.display.columns);
+                self.show_notification(if unknown.is_empty() {
+                    "✓ Config reloaded".to_string()
+                } else {
+                    format!(
+                        "✓ Config reloaded, unknown column {} so showing the default columns",
+                        unknown.join(", ")
+                    )
+                });
             }
-        });
-
-        tasks
+            Err(e) => {
+                self.show_popup(vec!["Failed to reload config:".into(), e.to_string()], true);
+            }
+        }
+        Ok(())
     }
 
-    pub fn selected_task_in_sorted(&self) -> Option<usize> {
-        // Returns the index into self.tasks of the currently selected sorted row
-        let idx = self.selected_task.selected()?;
-        let sorted = self.sorted_tasks();
-        sorted
-            .get(idx)
-            .and_then(|task| self.tasks.iter().position(|t| t.id == task.id))
+    /// Copy everything known about the selected task (trackers, peers, files, ...) to the
+    /// clipboard as text and save it as JSON, for sharing when asking for help
+    pub async fn export_task(&mut self) -> anyhow::Result<()> {
+        // The table only has the summary, make sure the trackers, peers and files are loaded
+        self.load_selected_details().await?;
+        let Some(task) = self
+            .selected_task_in_sorted()
+            .and_then(|idx| self.tasks.get(idx))
+        else {
+            return Ok(());
+        };
+
+        let path = save_task_details(task)?;
+        let text = task_details_text(task, self.binary_units, self.use_local_time);
+        match set_clipboard(&text) {
+            Ok(_) => self.show_notification(format!(
+                "✓ Task details copied, JSON saved to {}",
+                path.display()
+            )),
+            Err(_) => self.show_notification(format!("✓ Task details saved to {}", path.display())),
+        }
+        Ok(())
     }
 
-    pub fn show_notification(&mut self, message: String) {
-        self.notification = Some(Notification {
-            message,
-            ticks_remaining: (4.0 * TICK_FPS) as u64, // visible for 4 seconds
-        });
+    /// Copy the URI of the selected task to the clipboard
+    pub fn copy_task_uri(&mut self) {
+        let uri = self
+            .selected_task_in_sorted()
+            .and_then(|idx| self.tasks.get(idx))
+            .and_then(|task| task.additional.as_ref())
+            .and_then(|a| a.detail.as_ref())
+            .map(|d| d.uri.trim().to_string())
+            .unwrap_or_default();
+
+        if uri.is_empty() {
+            self.show_notification("No URI available for this task".to_string());
+            return;
+        }
+
+        match set_clipboard(&uri) {
+            Ok(_) => self.show_notification("✓ URI copied to clipboard".to_string()),
+            Err(e) => {
+                self.show_popup(vec!["Failed to copy URI:".into(), e.to_string()], true);
+            }
+        }
     }
 
-    pub async fn reload_config(&mut self) -> anyhow::Result<()> {
-        match crate::config::load_config() {
-            Ok(config) => {
-                self.destination = config.downloads.destination;
-                self.refresh_interval = config
-                    .downloads
-                    .refresh_interval
-                    .filter(|&s| s > 0)
-                    .map(|s| (s as f64 * TICK_FPS) as u64);
-                self.sort_column = SortColumn::from_str(&config.sorting.column);
-                self.sort_order = SortOrder::from_str(&config.sorting.order);
-                self.tick_count = 0; // reset so the new interval starts fresh
+    pub fn copy_task_title(&mut self) {
+        let Some(title) = self
+            .selected_task_in_sorted()
+            .and_then(|idx| self.tasks.get(idx))
+            .map(|task| task.title.clone())
+        else {
+            return;
+        };
 
-                self.show_notification("✓ Config reloaded".to_string());
+        match set_clipboard(&title) {
+            Ok(_) => self.show_notification("✓ Name copied to clipboard".to_string()),
+            Err(e) => {
+                self.show_popup(vec!["Failed to copy name:".into(), e.to_string()], true);
             }
+        }
+    }
+
+    pub fn open_destination(&mut self) {
+        let destination = self
+            .selected_task_in_sorted()
+            .and_then(|idx| self.tasks.get(idx))
+            .and_then(|task| task.additional.as_ref())
+            .and_then(|a| a.detail.as_ref())
+            .map(|d| d.destination.trim().to_string())
+            .unwrap_or_default();
+
+        if destination.is_empty() {
+            self.show_notification("No destination for this task".to_string());
+            return;
+        }
+
+        let url = match file_station_url(&self.server_url, &destination) {
+            Ok(url) => url,
             Err(e) => {
-                self.show_popup(vec!["Failed to reload config:".into(), e.to_string()], true);
+                self.show_popup(
+                    vec!["Failed to build File Station link:".into(), e.to_string()],
+                    true,
+                );
+                return;
+            }
+        };
+
+        // Without a local browser the link goes to the clipboard instead
+        if open_in_browser(&url) {
+            self.show_notification(format!("Opening {} in File Station", destination));
+        } else {
+            match set_clipboard(&url) {
+                Ok(_) => self.show_notification("✓ File Station link copied to clipboard".into()),
+                Err(e) => {
+                    self.show_popup(vec!["Failed to copy link:".into(), e.to_string()], true);
+                }
             }
         }
-        Ok(())
     }
 
     // Filtering methods
@@ -1052,6 +3928,19 @@ impl App {
             input = input.with_value(self.filter_text.clone());
         }
         self.filter_input = Some(input);
+        self.filtering_files = false;
+    }
+
+    /// Filter the Files tab of the selected task, narrowing the list while typing
+    pub fn open_file_filter(&mut self) {
+        self.filter_input = Some(Input::default().with_value(self.file_filter.clone()));
+        self.filtering_files = true;
+    }
+
+    pub fn clear_file_filter(&mut self) {
+        self.file_filter.clear();
+        self.file_scroll = 0;
+        self.filter_input = None;
     }
 
     pub fn apply_filter(&mut self) {
@@ -1060,6 +3949,7 @@ impl App {
         }
         self.filter_input = None;
         self.selected_task.select(Some(0));
+        self.clamp_task_selection();
         self.reset_info_scroll();
     }
 
@@ -1067,6 +3957,7 @@ impl App {
         self.filter_text.clear();
         self.filter_input = None;
         self.selected_task.select(Some(0));
+        self.clamp_task_selection();
     }
 }
 
@@ -1086,6 +3977,108 @@ impl SortColumn {
     }
 }
 
+impl ColumnId {
+    pub const DEFAULT: [ColumnId; 10] = [
+        ColumnId::Name,
+        ColumnId::Size,
+        ColumnId::Downloaded,
+        ColumnId::Uploaded,
+        ColumnId::Progress,
+        ColumnId::UploadSpeed,
+        ColumnId::DownloadSpeed,
+        ColumnId::Ratio,
+        ColumnId::Status,
+        ColumnId::Eta,
+    ];
+
+    fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "name" => Some(ColumnId::Name),
+            "size" => Some(ColumnId::Size),
+            "downloaded" => Some(ColumnId::Downloaded),
+            "uploaded" => Some(ColumnId::Uploaded),
+            "progress" => Some(ColumnId::Progress),
+            "up_speed" | "uploadspeed" => Some(ColumnId::UploadSpeed),
+            "dl_speed" | "downloadspeed" => Some(ColumnId::DownloadSpeed),
+            "ratio" => Some(ColumnId::Ratio),
+            "status" => Some(ColumnId::Status),
+            "eta" => Some(ColumnId::Eta),
+            "added" => Some(ColumnId::Added),
+            _ => None,
+        }
+    }
+
+    /// Parse the configured column list, falling back to all columns if it is empty or
+    /// contains an unknown id
+    pub fn from_config(columns: &[String]) -> Vec<Self> {
+        let unknown = Self::unknown(columns);
+        if !unknown.is_empty() {
+            log::warn!(
+                "Unknown column(s) {} in [display] columns, showing the default columns",
+                unknown.join(", ")
+            );
+        }
+        let parsed: Option<Vec<Self>> = columns.iter().map(|c| Self::from_str(c)).collect();
+        match parsed {
+            Some(parsed) if !parsed.is_empty() => parsed,
+            _ => Self::DEFAULT.to_vec(),
+        }
+    }
+
+    /// The configured ids that aren't a column, any of them makes `from_config` fall back
+    pub fn unknown(columns: &[String]) -> Vec<&str> {
+        columns
+            .iter()
+            .filter(|c| Self::from_str(c).is_none())
+            .map(String::as_str)
+            .collect()
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ColumnId::Name => "Name",
+            ColumnId::Size => "Size",
+            ColumnId::Downloaded => "Downloaded",
+            ColumnId::Uploaded => "Uploaded",
+            ColumnId::Progress => "Progress",
+            ColumnId::UploadSpeed => "Up Speed",
+            ColumnId::DownloadSpeed => "Down Speed",
+            ColumnId::Ratio => "Ratio",
+            ColumnId::Status => "Status",
+            ColumnId::Eta => "ETA",
+            ColumnId::Added => "Added",
+        }
+    }
+
+    /// Relative width of the column, the space left by hidden columns is shared out
+    pub fn width(&self) -> u16 {
+        match self {
+            ColumnId::Name => 21,
+            ColumnId::Progress => 12, // wider for the bar
+            ColumnId::UploadSpeed | ColumnId::DownloadSpeed | ColumnId::Status => 10,
+            ColumnId::Ratio => 5,
+            ColumnId::Added => 11,
+            _ => 8,
+        }
+    }
+
+    /// The sort column matching this column, if it is sortable
+    pub fn sort_column(&self) -> Option<SortColumn> {
+        match self {
+            ColumnId::Name => Some(SortColumn::Name),
+            ColumnId::Size => Some(SortColumn::Size),
+            ColumnId::Downloaded => Some(SortColumn::Downloaded),
+            ColumnId::Uploaded => Some(SortColumn::Uploaded),
+            ColumnId::Progress => Some(SortColumn::Progress),
+            ColumnId::UploadSpeed => Some(SortColumn::UploadSpeed),
+            ColumnId::DownloadSpeed => Some(SortColumn::DownloadSpeed),
+            ColumnId::Ratio => Some(SortColumn::Ratio),
+            ColumnId::Status => Some(SortColumn::Status),
+            ColumnId::Eta | ColumnId::Added => None,
+        }
+    }
+}
+
 impl SortOrder {
     fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
@@ -1094,3 +4087,70 @@ impl SortOrder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, downloaded: u64, uploaded: u64) -> Task {
+        serde_json::from_str(&format!(
+            r#"{{"id":"{}","username":"admin","type":"bt","title":"debian.iso","size":100,"status":2,
+                "additional":{{"transfer":{{"downloaded_pieces":1,"size_downloaded":{},"size_uploaded":{},"speed_download":0,"speed_upload":0}}}}}}"#,
+            id, downloaded, uploaded
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn unknown_column_falls_back_and_is_named() {
+        let columns = vec!["name".to_string(), "staus".to_string()];
+        assert_eq!(ColumnId::unknown(&columns), vec!["staus"]);
+        assert_eq!(ColumnId::from_config(&columns), ColumnId::DEFAULT.to_vec());
+        let columns = vec!["Name".to_string(), "eta".to_string()];
+        assert!(ColumnId::unknown(&columns).is_empty());
+        assert_eq!(
+            ColumnId::from_config(&columns),
+            vec![ColumnId::Name, ColumnId::Eta]
+        );
+    }
+
+    #[test]
+    fn first_sighting_is_only_a_baseline() {
+        let mut session = (0, 0);
+        let seen = count_transferred(&[task("dbid_1", 500, 20)], &HashMap::new(), &mut session);
+        assert_eq!(session, (0, 0));
+        assert_eq!(seen["dbid_1"], (500, 20));
+
+        count_transferred(&[task("dbid_1", 700, 50)], &seen, &mut session);
+        assert_eq!(session, (200, 30));
+    }
+
+    #[test]
+    fn counter_going_down_counts_in_full() {
+        let mut session = (0, 0);
+        let seen = count_transferred(&[task("dbid_1", 500, 20)], &HashMap::new(), &mut session);
+        // Restarted task, DSM counts from zero again
+        count_transferred(&[task("dbid_1", 100, 5)], &seen, &mut session);
+        assert_eq!(session, (100, 5));
+    }
+
+    #[test]
+    fn deleted_task_stays_counted() {
+        let mut session = (0, 0);
+        let seen = count_transferred(
+            &[task("dbid_1", 100, 0), task("dbid_2", 100, 0)],
+            &HashMap::new(),
+            &mut session,
+        );
+        let seen = count_transferred(
+            &[task("dbid_1", 300, 0), task("dbid_2", 150, 0)],
+            &seen,
+            &mut session,
+        );
+        assert_eq!(session, (250, 0));
+
+        let seen = count_transferred(&[task("dbid_1", 400, 0)], &seen, &mut session);
+        assert_eq!(session, (350, 0));
+        assert!(!seen.contains_key("dbid_2"));
+    }
+}
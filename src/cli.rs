@@ -0,0 +1,133 @@
+//! Headless commands for scripts, cron jobs and "send to NAS" handlers. Each one logs in,
+//! does a single thing and logs out again, nothing here touches the terminal beyond plain
+//! printing.
+
+use crate::api::{
+    CreateTaskOptions, DsApi, NO_DOWNLOAD_STATION_ACCESS, status_label, task_file_mime,
+};
+use crate::config::{Config, ServerProfile};
+use crate::export::task_json;
+use crate::util::{UNKNOWN, fit_width, format_bytes, format_speed, validate_url};
+use anyhow::{Context, Result, bail};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::Path;
+use syno_download_station::entities::Task;
+
+async fn connect(profile: &ServerProfile) -> Result<DsApi> {
+    let api = DsApi::new(&profile.connection)?;
+    api.authorize().await?;
+    Ok(api)
+}
+
+/// Log out before looking at the result, sessions left behind by cron jobs pile up on DSM
+async fn disconnect(api: &DsApi, profile: &ServerProfile) {
+    if let Err(e) = api.logout().await {
+        log::warn!("Logout from {} failed: {:#}", profile.connection.url, e);
+    }
+}
+
+/// Print the tasks of a profile to stdout as a table or JSON
+pub async fn print_tasks(config: &Config, profile: usize, as_json: bool) -> Result<()> {
+    let profile = &config.profiles[profile];
+    let api = connect(profile).await?;
+    let tasks = api.list_tasks_summary().await;
+    disconnect(&api, profile).await;
+    let tasks = tasks.map_err(|e| {
+        let denied = e.is_permission_denied();
+        let e = anyhow::Error::from(e);
+        if denied {
+            e.context(NO_DOWNLOAD_STATION_ACCESS)
+        } else {
+            e
+        }
+    })?;
+
+    let output = if as_json {
+        let tasks: Vec<_> = tasks.iter().map(task_json).collect();
+        serde_json::to_string_pretty(&tasks)? + "\n"
+    } else {
+        format_table(&tasks, config.display.binary_units)
+    };
+    // `dstui --list | head` closing the pipe early is fine
+    match io::stdout().write_all(output.as_bytes()) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn format_table(tasks: &[Task], binary_units: bool) -> String {
+    let mut table = String::new();
+    let _ = writeln!(
+        table,
+        "{:<40}  {:<16}  {:>5}  {:>10}  {:>12}  {:>12}",
+        "Name", "Status", "Done", "Size", "Down", "Up"
+    );
+    for task in tasks {
+        let transfer = task.additional.as_ref().and_then(|a| a.transfer.as_ref());
+        let _ = writeln!(
+            table,
+            "{}  {:<16}  {:>5}  {:>10}  {:>12}  {:>12}",
+            // `{:<40}` pads by chars, CJK and emoji titles would push the other columns over
+            fit_width(&task.title, 40),
+            status_label(task),
+            transfer
+                .map(|_| format!("{}%", task.calculate_progress()))
+                .unwrap_or_else(|| UNKNOWN.to_string()),
+            format_bytes(task.size, binary_units),
+            transfer
+                .map(|t| format_speed(t.speed_download, binary_units))
+                .unwrap_or_else(|| UNKNOWN.to_string()),
+            transfer
+                .map(|t| format_speed(t.speed_upload, binary_units))
+                .unwrap_or_else(|| UNKNOWN.to_string()),
+        );
+    }
+    table
+}
+
+/// Create a task from a URL or link [`validate_url`] accepts in the default destination
+pub async fn add_url(config: &Config, profile: usize, url: &str) -> Result<()> {
+    let url = url.trim();
+    if validate_url(url).is_none() {
+        bail!(
+            "Invalid URL, it must start with http://, https://, ftp://, sftp://, magnet:, ed2k:// or another scheme Download Station takes"
+        );
+    }
+    let profile = &config.profiles[profile];
+    let api = connect(profile).await?;
+    let result = api
+        .create_task(
+            url,
+            &config.downloads.destination,
+            &CreateTaskOptions::default(),
+        )
+        .await;
+    disconnect(&api, profile).await;
+    result?;
+    println!("Task added to {}", config.downloads.destination);
+    Ok(())
+}
+
+/// Upload a .torrent, .nzb or .txt file as a new task in the default destination
+pub async fn add_file(config: &Config, profile: usize, path: &Path) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("upload.torrent");
+    if task_file_mime(file_name).is_none() {
+        bail!("Unsupported file type, only .torrent, .nzb and .txt are supported");
+    }
+    let file_data =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let profile = &config.profiles[profile];
+    let api = connect(profile).await?;
+    let result = api
+        .create_task_from_file(&file_data, file_name, &config.downloads.destination)
+        .await;
+    disconnect(&api, profile).await;
+    result?;
+    println!("Task added to {}", config.downloads.destination);
+    Ok(())
+}
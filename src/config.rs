@@ -1,19 +1,35 @@
+use crate::keys::{Keys, keymap};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionConfig {
     pub url: String,
     pub username: String,
     pub password: String,
     #[serde(default)]
     pub accept_invalid_certs: bool,
+    #[serde(default)]
+    pub ca_cert: Option<String>, // PEM file trusted for a self-signed certificate
+    #[serde(default)]
+    pub proxy: Option<String>, // http://, https:// or socks5:// proxy for every request
     #[serde(default = "default_timeout")]
     pub timeout_ms: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+/// One DiskStation to connect to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerProfile {
+    pub name: String,
+    #[serde(flatten)]
+    pub connection: ConnectionConfig,
+    #[serde(default)]
+    pub refresh_interval: Option<u64>, // in seconds, overrides [downloads] for this server, 0 = disabled
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SortConfig {
     #[serde(default)]
     pub column: String, // "name", "size", "progress", etc.
@@ -21,25 +37,162 @@ pub struct SortConfig {
     pub order: String, // "ascending" or "descending"
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    #[serde(default)]
+    pub binary_units: bool, // true = KiB/MiB (1024), false = KB/MB (1000)
+    #[serde(default)]
+    pub theme: String, // "default", "dark", "light" or "solarized"
+    #[serde(default)]
+    pub columns: Vec<String>, // ordered task table columns, empty = all
+    #[serde(default = "default_use_local_time")]
+    pub use_local_time: bool, // false = show task times in UTC
+    #[serde(default = "default_table_percent")]
+    pub table_percent: u16, // share of the height for the task table, 100 = no info panel
+    #[serde(default)]
+    pub confirm_quit: bool, // true = q / Esc ask before quitting
+    #[serde(default = "default_max_peers")]
+    pub max_peers: usize, // fastest peers listed in the Peers tab, 0 = all
+    #[serde(default)]
+    pub tab_per_task: bool, // true = the info panel remembers the last tab of each task
+    #[serde(default)]
+    pub enable_notifications: bool, // desktop notification when a download completes
+    #[serde(default)]
+    pub terminal_title: bool, // task counts and speed in the terminal's title
+    #[serde(default)]
+    pub fuzzy_filter: bool, // true = "/" matches the characters in order instead of a substring
+    #[serde(default)]
+    pub hide_finished: bool, // start with finished and seeding tasks left out of the table
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            binary_units: false,
+            theme: String::new(),
+            columns: Vec::new(),
+            use_local_time: default_use_local_time(),
+            table_percent: default_table_percent(),
+            confirm_quit: false,
+            max_peers: default_max_peers(),
+            tab_per_task: false,
+            enable_notifications: false,
+            terminal_title: false,
+            fuzzy_filter: false,
+            hide_finished: false,
+        }
+    }
+}
+
+fn default_use_local_time() -> bool {
+    true
+}
+
+fn default_table_percent() -> u16 {
+    40
+}
+
+fn default_max_peers() -> usize {
+    100
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogConfig {
+    #[serde(default = "default_log_level")]
+    pub level: String, // "off", "error", "warn", "info", "debug" or "trace"
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: default_log_level(),
+        }
+    }
+}
+
+fn default_log_level() -> String {
+    String::from("warn")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    pub connection: ConnectionConfig,
+    #[serde(default)]
+    pub default_profile: String, // name of the profile to preselect at startup
+    #[serde(default = "default_use_keyring")]
+    pub use_keyring: bool, // keep passwords in the system keyring instead of this file
+    // Single server config from before profiles, moved into `profiles` on load
+    #[serde(default, skip_serializing)]
+    pub connection: Option<ConnectionConfig>,
     pub downloads: DownloadConfig,
     #[serde(default)]
     pub sorting: SortConfig,
+    #[serde(default)]
+    pub display: DisplayConfig,
+    #[serde(default)]
+    pub logging: LogConfig,
+    #[serde(default)]
+    pub profiles: Vec<ServerProfile>,
+    // Action name to one key or a list of keys, see keys.rs for the names
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keybindings: BTreeMap<String, Keys>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Config {
+    /// Index of the profile named by `default_profile`, the first one if there is no such profile
+    pub fn default_profile_index(&self) -> usize {
+        self.profiles
+            .iter()
+            .position(|p| p.name == self.default_profile)
+            .unwrap_or(0)
+    }
+
+    /// Auto-refresh interval in seconds for a profile: its own if set, otherwise the one
+    /// from [downloads]. None = disabled.
+    pub fn refresh_interval(&self, profile: &ServerProfile) -> Option<u64> {
+        profile
+            .refresh_interval
+            .or(self.downloads.refresh_interval)
+            .filter(|&s| s > 0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadConfig {
     pub destination: String,
     #[serde(default = "default_refresh_interval")]
     pub refresh_interval: Option<u64>, // in seconds, None = disabled
+    #[serde(default = "default_typed_delete_size_mb")]
+    pub typed_delete_size_mb: u64, // tasks this big or seeding need the title typed to delete, 0 = never
+    #[serde(default = "default_adaptive_refresh")]
+    pub adaptive_refresh: bool, // refresh less often while no task is transferring
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed_ratio_target: Option<f64>, // pause seeding tasks at this upload ratio, None = never
+    #[serde(default = "default_stalled_after_secs")]
+    pub stalled_after_secs: u64, // a download at 0 B/s this long is highlighted, 0 = never
+    #[serde(default)]
+    pub confirm_add_above_bytes: u64, // only bigger .torrent files ask before adding, 0 = all
+}
+
+pub fn default_adaptive_refresh() -> bool {
+    true
+}
+
+pub fn default_stalled_after_secs() -> u64 {
+    120
 }
 
 fn default_refresh_interval() -> Option<u64> {
     Some(30)
 }
 
+pub fn default_typed_delete_size_mb() -> u64 {
+    1024
+}
+
+fn default_use_keyring() -> bool {
+    true
+}
+
 fn default_timeout() -> u64 {
     3000
 }
@@ -47,21 +200,38 @@ fn default_timeout() -> u64 {
 impl Default for Config {
     fn default() -> Self {
         Self {
-            connection: ConnectionConfig {
-                url: String::from("http://your-diskstation:5000"),
-                username: String::from("admin"),
-                password: String::new(),
-                accept_invalid_certs: false,
-                timeout_ms: default_timeout(),
-            },
+            default_profile: String::from("default"),
+            use_keyring: default_use_keyring(),
+            connection: None,
             downloads: DownloadConfig {
                 destination: String::from("downloads"),
                 refresh_interval: Some(30),
+                typed_delete_size_mb: default_typed_delete_size_mb(),
+                adaptive_refresh: default_adaptive_refresh(),
+                seed_ratio_target: None,
+                stalled_after_secs: default_stalled_after_secs(),
+                confirm_add_above_bytes: 0,
             },
             sorting: SortConfig {
                 column: String::from("name"),
                 order: String::from("ascending"),
             },
+            display: DisplayConfig::default(),
+            logging: LogConfig::default(),
+            profiles: vec![ServerProfile {
+                name: String::from("default"),
+                connection: ConnectionConfig {
+                    url: String::from("http://your-diskstation:5000"),
+                    username: String::from("admin"),
+                    password: String::new(),
+                    accept_invalid_certs: false,
+                    ca_cert: None,
+                    proxy: None,
+                    timeout_ms: default_timeout(),
+                },
+                refresh_interval: None,
+            }],
+            keybindings: BTreeMap::new(),
         }
     }
 }
@@ -73,6 +243,63 @@ pub fn config_path() -> Result<PathBuf> {
     Ok(config_dir.join("config.toml"))
 }
 
+/// Written to the config file in place of a password kept in the system keyring
+const KEYRING_PLACEHOLDER: &str = "<keyring>";
+
+/// Keyring entry of a profile's password. Keyed by user and server rather than the profile
+/// name, so renaming a profile doesn't lose it.
+fn keyring_entry(connection: &ConnectionConfig) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(
+        "dstui",
+        &format!("{}@{}", connection.username, connection.url),
+    )
+}
+
+/// Write the config file, readable by the owner only since it can hold passwords.
+///
+/// With `use_keyring` the passwords go to the system keyring and the file only gets a
+/// placeholder. Without a usable keyring (e.g. a headless Linux box without a Secret
+/// Service) they stay in the file.
+pub fn save_config(config: &Config) -> Result<()> {
+    let path = config_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut config = config.clone();
+    if config.use_keyring {
+        for profile in &mut config.profiles {
+            let connection = &profile.connection;
+            if connection.password.is_empty() || connection.password == KEYRING_PLACEHOLDER {
+                continue;
+            }
+            match keyring_entry(connection).and_then(|e| e.set_password(&connection.password)) {
+                Ok(()) => profile.connection.password = KEYRING_PLACEHOLDER.to_string(),
+                Err(e) => log::warn!(
+                    "Keeping the password of profile {} in the config file: {}",
+                    profile.name,
+                    e
+                ),
+            }
+        }
+    }
+
+    let toml = toml::to_string_pretty(&config)?;
+    std::fs::write(&path, &toml)
+        .context(format!("Failed to write config file at {}", path.display()))?;
+
+    // Change file permissions to owner read/write only
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = std::fs::metadata(&path)?.permissions();
+        perms.set_mode(0o600);
+        std::fs::set_permissions(&path, perms)?;
+    }
+
+    Ok(())
+}
+
 pub fn load_config() -> Result<Config> {
     let path = config_path()?;
 
@@ -82,8 +309,59 @@ pub fn load_config() -> Result<Config> {
 
     let contents = std::fs::read_to_string(&path)
         .context(format!("Failed to read config file at {}", path.display()))?;
-    let config: Config = toml::from_str(&contents)
+    let mut config: Config = toml::from_str(&contents)
         .context("Failed to parse config file — check your TOML syntax")?;
 
+    // Move a [connection] section from before profiles existed into a profile
+    let mut needs_save = false;
+    if let Some(connection) = config.connection.take()
+        && config.profiles.is_empty()
+    {
+        config.profiles.push(ServerProfile {
+            name: String::from("default"),
+            connection,
+            refresh_interval: None,
+        });
+        config.default_profile = String::from("default");
+        needs_save = true;
+    }
+
+    if config.profiles.is_empty() {
+        anyhow::bail!("No server configured, add a [[profiles]] section");
+    }
+
+    for profile in &mut config.profiles {
+        if profile.connection.password == KEYRING_PLACEHOLDER {
+            // Missing e.g. after the URL or username was changed by hand. Logging in without
+            // a password fails and asks for it, saving then stores it under the new key.
+            profile.connection.password = keyring_entry(&profile.connection)
+                .and_then(|entry| entry.get_password())
+                .unwrap_or_else(|e| {
+                    log::warn!(
+                        "Failed to read the password of profile \"{}\" from the keyring: {}",
+                        profile.name,
+                        e
+                    );
+                    String::new()
+                });
+        } else if config.use_keyring
+            && !profile.connection.password.is_empty()
+            && keyring_entry(&profile.connection)
+                .and_then(|entry| entry.set_password(&profile.connection.password))
+                .is_ok()
+        {
+            // A password written before the keyring was used is in the keyring now, saving
+            // replaces it with the placeholder. Without a usable keyring the file is left
+            // alone instead of being rewritten on every load.
+            needs_save = true;
+        }
+    }
+
+    keymap(&config.keybindings).context("Invalid [keybindings] in the config file")?;
+
+    if needs_save {
+        save_config(&config)?;
+    }
+
     Ok(config)
 }
@@ -1,8 +1,8 @@
 use anyhow::{Context, Result};
 use futures::{FutureExt, StreamExt};
-use ratatui::crossterm::event::Event as CrosstermEvent;
+use ratatui::crossterm::event::{Event as CrosstermEvent, KeyEvent, KeyEventKind};
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc, watch};
 
 /// The frequency at which tick events are emitted.
 pub const TICK_FPS: f64 = 30.0;
@@ -49,6 +49,16 @@ pub enum AppEvent {
     SubmitUrl,
     /// Toggle task status (pause, resume)
     ToggleTask,
+    /// Queue a task in an error state again
+    RetryTask,
+    /// Queue every task in a retriable error state again
+    RetryErrors,
+    /// Pause or resume every task at once
+    PauseAll,
+    ResumeAll,
+    /// Pause or resume only the seeding tasks
+    PauseSeeds,
+    ResumeSeeds,
     /// Complete task
     CompleteTask,
     /// Clear completed tasks
@@ -56,12 +66,53 @@ pub enum AppEvent {
     /// Task deletion and confirmation events
     DeleteTask,
     ConfirmAction,
+    ConfirmForceAction,
     CancelAction,
     /// Manual config reload
     ReloadConfig,
     /// Filtering events
     OpenFilter,
     ApplyFilter,
+    /// Copy the selected task's URI to the clipboard
+    CopyUri,
+    /// Copy the selected task's title to the clipboard
+    CopyTitle,
+    /// Copy all details of the selected task to the clipboard and save them as JSON
+    ExportTask,
+    /// Show everything about the selected task in a full screen popup
+    ShowDetails,
+    /// Speed limit popup of the selected task
+    OpenSpeedLimit,
+    SaveSpeedLimit,
+    /// Open the selected task's destination in File Station
+    OpenDestination,
+    /// RSS feed screen events
+    OpenRss,
+    LoadRssItems,
+    DownloadRssItem,
+    /// BT search events
+    OpenBtSearch,
+    StartBtSearch,
+    PollBtSearch,
+    DownloadBtSearchResult,
+    CloseBtSearch,
+    /// Server settings popup events
+    OpenServerSettings,
+    ToggleServerSetting,
+    /// Settings editor events
+    OpenSettingsEditor,
+    SaveSettings,
+    /// Edit the config file in $EDITOR, handled by the event loop as it needs the terminal
+    EditConfigFile,
+    /// Server profile switcher events
+    OpenProfilePicker,
+    SwitchProfile,
+    /// Log in to the current profile again
+    Reconnect,
+    /// Destination folder browser events
+    OpenFolderBrowser,
+    LoadFolder,
+    ChooseDestination,
 }
 
 /// Terminal event handler.
@@ -71,15 +122,38 @@ pub struct EventHandler {
     sender: mpsc::UnboundedSender<Event>,
     /// Event receiver channel.
     receiver: mpsc::UnboundedReceiver<Event>,
+    /// Key presses as they happen, also while the event loop is busy with a request
+    key_presses: broadcast::Sender<KeyEvent>,
+    /// True while another program has the terminal, see [`EventHandler::suspend`]
+    suspended: watch::Sender<bool>,
 }
 
 impl EventHandler {
     /// Constructs a new instance of [`EventHandler`] and spawns a new thread to handle events.
     pub fn new() -> Self {
         let (sender, receiver) = mpsc::unbounded_channel();
-        let actor = EventTask::new(sender.clone());
+        let (key_presses, _) = broadcast::channel(16);
+        let (suspended, suspended_receiver) = watch::channel(false);
+        let actor = EventTask::new(sender.clone(), key_presses.clone(), suspended_receiver);
         tokio::spawn(async { actor.run().await });
-        Self { sender, receiver }
+        Self {
+            sender,
+            receiver,
+            key_presses,
+            suspended,
+        }
+    }
+
+    /// Stop reading the terminal and sending ticks while `suspended`, so a program run in the
+    /// foreground, like the editor, gets every key press
+    pub fn suspend(&self, suspended: bool) {
+        self.suspended.send_replace(suspended);
+    }
+
+    /// Key presses from now on, independent of the event queue. Lets a long request be
+    /// interrupted before the event loop gets to the queued key events.
+    pub fn key_presses(&self) -> broadcast::Receiver<KeyEvent> {
+        self.key_presses.subscribe()
     }
 
     /// Receives an event from the sender.
@@ -119,35 +193,72 @@ impl Default for EventHandler {
 struct EventTask {
     /// Event sender channel.
     sender: mpsc::UnboundedSender<Event>,
+    /// Copy of every key press for [`EventHandler::key_presses`]
+    key_presses: broadcast::Sender<KeyEvent>,
+    /// See [`EventHandler::suspend`]
+    suspended: watch::Receiver<bool>,
 }
 
 impl EventTask {
     /// Constructs a new instance of [`EventThread`].
-    fn new(sender: mpsc::UnboundedSender<Event>) -> Self {
-        Self { sender }
+    fn new(
+        sender: mpsc::UnboundedSender<Event>,
+        key_presses: broadcast::Sender<KeyEvent>,
+        suspended: watch::Receiver<bool>,
+    ) -> Self {
+        Self {
+            sender,
+            key_presses,
+            suspended,
+        }
     }
 
     /// Runs the event thread.
     ///
     /// This function emits tick events at a fixed rate and polls for crossterm events in between.
-    async fn run(self) -> Result<()> {
+    async fn run(mut self) -> Result<()> {
         let tick_rate = Duration::from_secs_f64(1.0 / TICK_FPS);
-        let mut reader = crossterm::event::EventStream::new();
+        let mut reader = Some(crossterm::event::EventStream::new());
         let mut tick = tokio::time::interval(tick_rate);
         loop {
+            let suspended = reader.is_none();
+            let mut suspend = None;
             let tick_delay = tick.tick();
-            let crossterm_event = reader.next().fuse();
+            let crossterm_event = async {
+                match reader.as_mut() {
+                    Some(reader) => reader.next().await,
+                    None => std::future::pending().await,
+                }
+            }
+            .fuse();
             tokio::select! {
               _ = self.sender.closed() => {
                 break;
               }
-              _ = tick_delay => {
+              Ok(()) = self.suspended.changed() => {
+                suspend = Some(*self.suspended.borrow_and_update());
+              }
+              _ = tick_delay, if !suspended => {
                 self.send(Event::Tick);
               }
               Some(Ok(evt)) = crossterm_event => {
+                if let CrosstermEvent::Key(key) = evt
+                    && key.kind == KeyEventKind::Press
+                {
+                    // No one listening is the normal case
+                    let _ = self.key_presses.send(key);
+                }
                 self.send(Event::Crossterm(evt));
               }
             };
+            // Dropping the stream stops crossterm from reading the terminal
+            match suspend {
+                Some(true) => reader = None,
+                Some(false) if reader.is_none() => {
+                    reader = Some(crossterm::event::EventStream::new())
+                }
+                _ => {}
+            }
         }
         Ok(())
     }
@@ -0,0 +1,220 @@
+use crate::api::{is_error_status, status_label, task_error_detail};
+use crate::config::config_path;
+use crate::util::{UNKNOWN, format_bytes, format_ratio, format_speed, format_timestamp};
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use syno_download_station::entities::Task;
+
+/// The fields of the task table as JSON, raw numbers rather than formatted ones so scripts
+/// don't have to parse units
+pub fn task_json(task: &Task) -> serde_json::Value {
+    let transfer = task.additional.as_ref().and_then(|a| a.transfer.as_ref());
+    let detail = task.additional.as_ref().and_then(|a| a.detail.as_ref());
+    json!({
+        "id": task.id,
+        "title": task.title,
+        "type": task.task_type,
+        "status": status_label(task),
+        "error": is_error_status(&task.status),
+        "size": task.size,
+        "size_downloaded": transfer.map_or(0, |t| t.size_downloaded),
+        "size_uploaded": transfer.map_or(0, |t| t.size_uploaded),
+        "speed_download": transfer.map_or(0, |t| t.speed_download),
+        "speed_upload": transfer.map_or(0, |t| t.speed_upload),
+        "progress": task.calculate_progress(),
+        "ratio": task.calculate_ratio(),
+        "destination": detail.map(|d| d.destination.clone()),
+    })
+}
+
+/// Everything known about a task, including trackers, peers and files. The entity types
+/// come from syno-download-station and don't implement `Serialize`, hence built by hand.
+pub fn task_details_json(task: &Task) -> serde_json::Value {
+    let additional = task.additional.as_ref();
+    let detail = additional.and_then(|a| a.detail.as_ref());
+    let mut value = task_json(task);
+    value["user"] = json!(task.username);
+    value["error_detail"] = json!(task_error_detail(task));
+    value["uri"] = json!(detail.map(|d| d.uri.clone()));
+    value["created_time"] = json!(detail.map(|d| d.created_time.to_rfc3339()));
+    value["completed_time"] = json!(
+        detail
+            .filter(|d| d.completed_time.timestamp() != 0)
+            .map(|d| d.completed_time.to_rfc3339())
+    );
+    value["trackers"] = additional
+        .and_then(|a| a.tracker.as_ref())
+        .map(|trackers| {
+            trackers
+                .iter()
+                .map(|t| {
+                    json!({
+                        "url": t.url,
+                        "status": t.status,
+                        "seeds": t.seeds,
+                        "peers": t.peers,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    value["peers"] = additional
+        .and_then(|a| a.peer.as_ref())
+        .map(|peers| {
+            peers
+                .iter()
+                .map(|p| {
+                    json!({
+                        "address": p.address,
+                        "agent": p.agent,
+                        "progress": p.progress,
+                        "speed_download": p.speed_download,
+                        "speed_upload": p.speed_upload,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    value["files"] = additional
+        .and_then(|a| a.file.as_ref())
+        .map(|files| {
+            files
+                .iter()
+                .map(|f| {
+                    json!({
+                        "filename": f.filename,
+                        "size": f.size,
+                        "size_downloaded": f.size_downloaded,
+                        "priority": f.priority,
+                        "wanted": f.wanted,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    value
+}
+
+/// The same details as a readable text block, laid out like the info panel tabs
+pub fn task_details_text(task: &Task, binary_units: bool, use_local_time: bool) -> String {
+    let additional = task.additional.as_ref();
+    let detail = additional.and_then(|a| a.detail.as_ref());
+    let transfer = additional.and_then(|a| a.transfer.as_ref());
+    let mut text = String::new();
+
+    let _ = writeln!(text, "Title:       {}", task.title);
+    let _ = writeln!(text, "ID:          {}", task.id);
+    let _ = writeln!(text, "Type:        {}", task.task_type);
+    let _ = writeln!(text, "Status:      {}", status_label(task));
+    if let Some(error) = task_error_detail(task) {
+        let _ = writeln!(text, "Error:       {}", error);
+    }
+    let _ = writeln!(
+        text,
+        "Size:        {}",
+        format_bytes(task.size, binary_units)
+    );
+    if let Some(detail) = detail {
+        let _ = writeln!(text, "Destination: {}", detail.destination);
+        let _ = writeln!(
+            text,
+            "Created:     {}",
+            format_timestamp(detail.created_time, use_local_time)
+        );
+        let _ = writeln!(
+            text,
+            "Completed:   {}",
+            format_timestamp(detail.completed_time, use_local_time)
+        );
+        let _ = writeln!(text, "URI:         {}", detail.uri.trim());
+    }
+    if let Some(t) = transfer {
+        let _ = writeln!(
+            text,
+            "Downloaded:  {}",
+            format_bytes(t.size_downloaded, binary_units)
+        );
+        let _ = writeln!(
+            text,
+            "Uploaded:    {}",
+            format_bytes(t.size_uploaded, binary_units)
+        );
+        let _ = writeln!(
+            text,
+            "Speed:       ↓ {} ↑ {}",
+            format_speed(t.speed_download, binary_units),
+            format_speed(t.speed_upload, binary_units)
+        );
+    }
+    let progress = match transfer {
+        Some(_) => format!("{}%", task.calculate_progress()),
+        None => UNKNOWN.to_string(),
+    };
+    let _ = writeln!(text, "Progress:    {}", progress);
+    let _ = writeln!(text, "Ratio:       {}", format_ratio(task));
+
+    if let Some(trackers) = additional.and_then(|a| a.tracker.as_ref()) {
+        let _ = writeln!(text, "\nTrackers:");
+        for t in trackers {
+            let _ = writeln!(
+                text,
+                "  {} ({}, {} seeds, {} peers)",
+                t.url, t.status, t.seeds, t.peers
+            );
+        }
+    }
+    if let Some(peers) = additional.and_then(|a| a.peer.as_ref()) {
+        let _ = writeln!(text, "\nPeers:");
+        for p in peers {
+            let _ = writeln!(
+                text,
+                "  {} {} {:.1}% ↓ {} ↑ {}",
+                p.address,
+                p.agent,
+                p.progress * 100.0,
+                format_speed(p.speed_download, binary_units),
+                format_speed(p.speed_upload, binary_units)
+            );
+        }
+    }
+    if let Some(files) = additional.and_then(|a| a.file.as_ref()) {
+        let _ = writeln!(text, "\nFiles:");
+        for f in files {
+            let _ = writeln!(
+                text,
+                "  {} ({} of {}, priority {}{})",
+                f.filename,
+                format_bytes(f.size_downloaded, binary_units),
+                format_bytes(f.size, binary_units),
+                f.priority,
+                if f.wanted { "" } else { ", skipped" }
+            );
+        }
+    }
+    text
+}
+
+/// Write the task's details as JSON to `exports/task-<id>.json` next to the config file
+pub fn save_task_details(task: &Task) -> Result<PathBuf> {
+    let dir = config_path()?
+        .parent()
+        .map(|p| p.join("exports"))
+        .context("Config path has no parent directory")?;
+    std::fs::create_dir_all(&dir)?;
+
+    // Task ids look like "dbid_123", keep anything else out of the file name
+    let id: String = task
+        .id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let path = dir.join(format!("task-{}.json", id));
+    std::fs::write(
+        &path,
+        serde_json::to_string_pretty(&task_details_json(task))?,
+    )
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
@@ -0,0 +1,568 @@
+use anyhow::{Result, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Commands of the main screen that can be bound to other keys in [keybindings]. Overlays
+/// like the file picker or the RSS screen keep their own fixed keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Next,
+    Previous,
+    First,
+    Last,
+    NextError,
+    HalfPageDown,
+    HalfPageUp,
+    ScrollInfoDown,
+    ScrollInfoUp,
+    PreviousTab,
+    NextTab,
+    ToggleTask,
+    RetryTask,
+    RetryErrors,
+    PauseAll,
+    ResumeAll,
+    PauseSeeds,
+    ResumeSeeds,
+    CompleteTask,
+    ClearCompleted,
+    AddFile,
+    AddUrl,
+    DeleteTask,
+    Refresh,
+    PauseRefresh,
+    RefreshFaster,
+    RefreshSlower,
+    ReloadConfig,
+    SortName,
+    SortSize,
+    SortDownloaded,
+    SortUploaded,
+    SortProgress,
+    SortUploadSpeed,
+    SortDownloadSpeed,
+    SortRatio,
+    SortStatus,
+    SwitchPanel,
+    ToggleInfoPanel,
+    HideFinished,
+    Details,
+    SpeedLimit,
+    Filter,
+    ClearFilter,
+    CopyUri,
+    CopyTitle,
+    ExportTask,
+    OpenDestination,
+    Rss,
+    Destination,
+    ServerSettings,
+    Profiles,
+    Reconnect,
+    EditSettings,
+    EditConfigFile,
+    BtSearch,
+    Help,
+    Quit,
+}
+
+impl Action {
+    /// Every action in the order the help popup lists them
+    pub const ALL: [Action; 58] = [
+        Action::Next,
+        Action::Previous,
+        Action::First,
+        Action::Last,
+        Action::NextError,
+        Action::HalfPageDown,
+        Action::HalfPageUp,
+        Action::ScrollInfoDown,
+        Action::ScrollInfoUp,
+        Action::PreviousTab,
+        Action::NextTab,
+        Action::ToggleTask,
+        Action::RetryTask,
+        Action::RetryErrors,
+        Action::PauseAll,
+        Action::ResumeAll,
+        Action::PauseSeeds,
+        Action::ResumeSeeds,
+        Action::CompleteTask,
+        Action::ClearCompleted,
+        Action::AddFile,
+        Action::AddUrl,
+        Action::DeleteTask,
+        Action::Refresh,
+        Action::PauseRefresh,
+        Action::RefreshFaster,
+        Action::RefreshSlower,
+        Action::ReloadConfig,
+        Action::SortName,
+        Action::SortSize,
+        Action::SortDownloaded,
+        Action::SortUploaded,
+        Action::SortProgress,
+        Action::SortUploadSpeed,
+        Action::SortDownloadSpeed,
+        Action::SortRatio,
+        Action::SortStatus,
+        Action::SwitchPanel,
+        Action::ToggleInfoPanel,
+        Action::HideFinished,
+        Action::Details,
+        Action::SpeedLimit,
+        Action::Filter,
+        Action::ClearFilter,
+        Action::CopyUri,
+        Action::CopyTitle,
+        Action::ExportTask,
+        Action::OpenDestination,
+        Action::Rss,
+        Action::Destination,
+        Action::ServerSettings,
+        Action::Profiles,
+        Action::Reconnect,
+        Action::EditSettings,
+        Action::EditConfigFile,
+        Action::BtSearch,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    /// Name used in the [keybindings] section of the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Next => "next",
+            Action::Previous => "previous",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::NextError => "next_error",
+            Action::HalfPageDown => "half_page_down",
+            Action::HalfPageUp => "half_page_up",
+            Action::ScrollInfoDown => "scroll_info_down",
+            Action::ScrollInfoUp => "scroll_info_up",
+            Action::PreviousTab => "previous_tab",
+            Action::NextTab => "next_tab",
+            Action::ToggleTask => "toggle_task",
+            Action::RetryTask => "retry_task",
+            Action::RetryErrors => "retry_errors",
+            Action::PauseAll => "pause_all",
+            Action::ResumeAll => "resume_all",
+            Action::PauseSeeds => "pause_seeds",
+            Action::ResumeSeeds => "resume_seeds",
+            Action::CompleteTask => "complete_task",
+            Action::ClearCompleted => "clear_completed",
+            Action::AddFile => "add_file",
+            Action::AddUrl => "add_url",
+            Action::DeleteTask => "delete_task",
+            Action::Refresh => "refresh",
+            Action::PauseRefresh => "pause_refresh",
+            Action::RefreshFaster => "refresh_faster",
+            Action::RefreshSlower => "refresh_slower",
+            Action::ReloadConfig => "reload_config",
+            Action::SortName => "sort_name",
+            Action::SortSize => "sort_size",
+            Action::SortDownloaded => "sort_downloaded",
+            Action::SortUploaded => "sort_uploaded",
+            Action::SortProgress => "sort_progress",
+            Action::SortUploadSpeed => "sort_upload_speed",
+            Action::SortDownloadSpeed => "sort_download_speed",
+            Action::SortRatio => "sort_ratio",
+            Action::SortStatus => "sort_status",
+            Action::SwitchPanel => "switch_panel",
+            Action::ToggleInfoPanel => "toggle_info_panel",
+            Action::HideFinished => "hide_finished",
+            Action::Details => "details",
+            Action::SpeedLimit => "speed_limit",
+            Action::Filter => "filter",
+            Action::ClearFilter => "clear_filter",
+            Action::CopyUri => "copy_uri",
+            Action::CopyTitle => "copy_title",
+            Action::ExportTask => "export_task",
+            Action::OpenDestination => "open_destination",
+            Action::Rss => "rss",
+            Action::Destination => "destination",
+            Action::ServerSettings => "server_settings",
+            Action::Profiles => "profiles",
+            Action::Reconnect => "reconnect",
+            Action::EditSettings => "edit_settings",
+            Action::EditConfigFile => "edit_config_file",
+            Action::BtSearch => "bt_search",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    /// Line in the help popup
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Next => "next task, or scroll down in the info panel",
+            Action::Previous => "previous task, or scroll up in the info panel",
+            Action::First => "jump to the first task",
+            Action::Last => "jump to the last task",
+            Action::NextError => "jump to the next task in an error state",
+            Action::HalfPageDown => "move half a page down in the task list",
+            Action::HalfPageUp => "move half a page up in the task list",
+            Action::ScrollInfoDown => "scroll down in the info panel from either panel",
+            Action::ScrollInfoUp => "scroll up in the info panel from either panel",
+            Action::PreviousTab => "previous tab when the info panel is active",
+            Action::NextTab => "next tab when the info panel is active",
+            Action::ToggleTask => "pause / resume selected task",
+            Action::RetryTask => {
+                "retry the selected task after an error, like a timeout or a full disk"
+            }
+            Action::RetryErrors => "retry every task with an error that retrying can fix",
+            Action::PauseAll => "pause all tasks",
+            Action::ResumeAll => "resume all paused tasks",
+            Action::PauseSeeds => "pause all seeding tasks, downloads are left alone",
+            Action::ResumeSeeds => "resume the paused seeding tasks",
+            Action::CompleteTask => "complete selected task",
+            Action::ClearCompleted => "clear completed tasks",
+            Action::AddFile => "add file (.torrent, .nzb and .txt is supported)",
+            Action::AddUrl => "add task by URL",
+            Action::DeleteTask => "delete selected task",
+            Action::Refresh => "manually refresh tasks",
+            Action::PauseRefresh => "pause / resume auto-refresh",
+            Action::RefreshFaster => "refresh more often",
+            Action::RefreshSlower => "refresh less often",
+            Action::ReloadConfig => {
+                "reload config (only applies to destination, refresh, sort, display and key settings)"
+            }
+            Action::SortName => "sort by name (again to reverse)",
+            Action::SortSize => "sort by size",
+            Action::SortDownloaded => "sort by downloaded",
+            Action::SortUploaded => "sort by uploaded",
+            Action::SortProgress => "sort by progress",
+            Action::SortUploadSpeed => "sort by upload speed",
+            Action::SortDownloadSpeed => "sort by download speed",
+            Action::SortRatio => "sort by ratio",
+            Action::SortStatus => "sort by status",
+            Action::SwitchPanel => "switch panels",
+            Action::ToggleInfoPanel => {
+                "hide / show the info panel to give the task list the full height"
+            }
+            Action::HideFinished => "hide / show finished and seeding tasks",
+            Action::Details => "all details of the selected task, full width (y to copy)",
+            Action::SpeedLimit => "limit the speed of the selected task (0 is unlimited)",
+            Action::Filter => "filter tasks by name (files in the Files tab)",
+            Action::ClearFilter => "clear active filter",
+            Action::CopyUri => "copy the selected task's URI / magnet link",
+            Action::CopyTitle => "copy the selected task's name",
+            Action::ExportTask => "copy all details of the selected task and save them as JSON",
+            Action::OpenDestination => {
+                "open the task's destination in File Station (copies the link over SSH)"
+            }
+            Action::Rss => "RSS feeds (Tab to switch lists, Enter to download)",
+            Action::Destination => {
+                "choose the download destination (Enter to open, Space to choose)"
+            }
+            Action::ServerSettings => "server settings (Space / Enter to toggle)",
+            Action::Profiles => "switch to another server profile",
+            Action::Reconnect => "log in again, e.g. after the NAS or the network was down",
+            Action::EditSettings => {
+                "edit the connection settings (Tab to move between fields, Enter to save)"
+            }
+            Action::EditConfigFile => {
+                "edit the config file in $EDITOR, then reload it and reconnect"
+            }
+            Action::BtSearch => {
+                "BT search (Enter to search, Tab to switch to results, Enter to download)"
+            }
+            Action::Help => "toggle this help popup",
+            Action::Quit => "quit",
+        }
+    }
+
+    /// The keys used when [keybindings] doesn't mention the action
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Next => &["j", "down"],
+            Action::Previous => &["k", "up"],
+            Action::First => &["g"],
+            Action::Last => &["G"],
+            Action::NextError => &["E"],
+            Action::HalfPageDown => &["ctrl-d"],
+            Action::HalfPageUp => &["ctrl-u"],
+            Action::ScrollInfoDown => &["shift-down"],
+            Action::ScrollInfoUp => &["shift-up"],
+            Action::PreviousTab => &["h", "left"],
+            Action::NextTab => &["l", "right"],
+            Action::ToggleTask => &["p"],
+            Action::RetryTask => &["ctrl-r"],
+            Action::RetryErrors => &["ctrl-a"],
+            Action::PauseAll => &["P"],
+            Action::ResumeAll => &["U"],
+            Action::PauseSeeds => &["b"],
+            Action::ResumeSeeds => &["B"],
+            Action::CompleteTask => &["c"],
+            Action::ClearCompleted => &["C"],
+            Action::AddFile => &["a"],
+            Action::AddUrl => &["A"],
+            Action::DeleteTask => &["d"],
+            Action::Refresh => &["r"],
+            Action::PauseRefresh => &["z"],
+            Action::RefreshFaster => &["+"],
+            Action::RefreshSlower => &["-"],
+            Action::ReloadConfig => &["R"],
+            Action::SortName => &["1"],
+            Action::SortSize => &["2"],
+            Action::SortDownloaded => &["3"],
+            Action::SortUploaded => &["4"],
+            Action::SortProgress => &["5"],
+            Action::SortUploadSpeed => &["6"],
+            Action::SortDownloadSpeed => &["7"],
+            Action::SortRatio => &["8"],
+            Action::SortStatus => &["9"],
+            Action::SwitchPanel => &["tab"],
+            Action::ToggleInfoPanel => &["i"],
+            Action::HideFinished => &["H"],
+            Action::Details => &["enter"],
+            Action::SpeedLimit => &["L"],
+            Action::Filter => &["/"],
+            Action::ClearFilter => &["x"],
+            Action::CopyUri => &["y"],
+            Action::CopyTitle => &["ctrl-y"],
+            Action::ExportTask => &["Y"],
+            Action::OpenDestination => &["o"],
+            Action::Rss => &["F"],
+            Action::Destination => &["T"],
+            Action::ServerSettings => &["S"],
+            Action::Profiles => &["N"],
+            Action::Reconnect => &["ctrl-n"],
+            Action::EditSettings => &["e"],
+            Action::EditConfigFile => &["ctrl-e"],
+            Action::BtSearch => &["s"],
+            Action::Help => &["?"],
+            Action::Quit => &["q", "esc"],
+        }
+    }
+}
+
+/// A key with or without Ctrl and Shift. For characters Shift is part of the character
+/// itself, "shift-d" is the same as "D".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    code: KeyCode,
+    ctrl: bool,
+    shift: bool, // only for keys without a character, like the arrows
+}
+
+impl KeyBinding {
+    pub fn from_event(event: &KeyEvent) -> Self {
+        Self {
+            code: event.code,
+            ctrl: event.modifiers.contains(KeyModifiers::CONTROL),
+            shift: event.modifiers.contains(KeyModifiers::SHIFT)
+                && !matches!(event.code, KeyCode::Char(_)),
+        }
+    }
+
+    /// Parse "d", "D", "ctrl-d", "shift-down", "tab", "f5", ...
+    fn parse(key: &str) -> Result<Self> {
+        let (mut ctrl, mut shift, mut name) = (false, false, key);
+        loop {
+            if let Some(rest) = name.strip_prefix("ctrl-").filter(|r| !r.is_empty()) {
+                (ctrl, name) = (true, rest);
+            } else if let Some(rest) = name.strip_prefix("shift-").filter(|r| !r.is_empty()) {
+                (shift, name) = (true, rest);
+            } else {
+                break;
+            }
+        }
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if shift => {
+                shift = false;
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match name.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                other => match other.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => bail!("Unknown key \"{}\"", key),
+                },
+            },
+        };
+        Ok(Self { code, ctrl, shift })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl-")?;
+        }
+        if self.shift {
+            write!(f, "Shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// One key or a list of keys for an action in [keybindings]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn as_slice(&self) -> &[String] {
+        match self {
+            Keys::One(key) => std::slice::from_ref(key),
+            Keys::Many(keys) => keys,
+        }
+    }
+}
+
+pub type KeyMap = HashMap<KeyBinding, Action>;
+
+/// Combine the [keybindings] from the config with the default keys. A key set in the config
+/// wins over a default key of another action, binding one key to two actions in the config
+/// is an error.
+pub fn keymap(bindings: &BTreeMap<String, Keys>) -> Result<KeyMap> {
+    let mut configured: KeyMap = HashMap::new();
+    for (name, keys) in bindings {
+        let Some(action) = Action::ALL.iter().find(|a| a.name() == name) else {
+            bail!("Unknown action \"{}\" in [keybindings]", name);
+        };
+        for key in keys.as_slice() {
+            let binding = KeyBinding::parse(key)?;
+            if let Some(other) = configured.insert(binding, *action)
+                && other != *action
+            {
+                bail!(
+                    "Key \"{}\" is bound to both {} and {} in [keybindings]",
+                    key,
+                    other.name(),
+                    action.name()
+                );
+            }
+        }
+    }
+
+    let mut map = configured.clone();
+    for action in Action::ALL {
+        if bindings.contains_key(action.name()) {
+            continue;
+        }
+        for key in action.default_keys() {
+            let binding = KeyBinding::parse(key).expect("default keys are valid");
+            map.entry(binding).or_insert(action);
+        }
+    }
+    Ok(map)
+}
+
+/// The keys bound to an action, for the help popup
+pub fn keys_for(keymap: &KeyMap, action: Action) -> Vec<String> {
+    let mut keys: Vec<String> = keymap
+        .iter()
+        .filter(|(_, a)| **a == action)
+        .map(|(key, _)| key.to_string())
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(entries: &[(&str, &[&str])]) -> BTreeMap<String, Keys> {
+        entries
+            .iter()
+            .map(|(name, keys)| {
+                (
+                    name.to_string(),
+                    Keys::Many(keys.iter().map(|k| k.to_string()).collect()),
+                )
+            })
+            .collect()
+    }
+
+    fn key(code: KeyCode, ctrl: bool) -> KeyBinding {
+        KeyBinding {
+            code,
+            ctrl,
+            shift: false,
+        }
+    }
+
+    #[test]
+    fn defaults_cover_every_action() {
+        let map = keymap(&BTreeMap::new()).unwrap();
+        for action in Action::ALL {
+            assert!(!keys_for(&map, action).is_empty(), "{:?}", action);
+        }
+        assert_eq!(map[&key(KeyCode::Char('d'), true)], Action::HalfPageDown);
+        assert_eq!(map[&key(KeyCode::Char('d'), false)], Action::DeleteTask);
+    }
+
+    #[test]
+    fn configured_keys_replace_the_defaults() {
+        let map = keymap(&bindings(&[("next", &["n", "j"]), ("delete_task", &["x"])])).unwrap();
+        assert_eq!(map[&key(KeyCode::Char('n'), false)], Action::Next);
+        assert_eq!(map[&key(KeyCode::Char('j'), false)], Action::Next);
+        assert!(!map.contains_key(&key(KeyCode::Down, false)));
+        assert_eq!(map[&key(KeyCode::Up, false)], Action::Previous);
+        // x was clear_filter's default key, d isn't bound to anything any more
+        assert_eq!(map[&key(KeyCode::Char('x'), false)], Action::DeleteTask);
+        assert!(!map.contains_key(&key(KeyCode::Char('d'), false)));
+    }
+
+    #[test]
+    fn conflicts_and_typos_are_rejected() {
+        assert!(keymap(&bindings(&[("next", &["n"]), ("previous", &["n"])])).is_err());
+        assert!(keymap(&bindings(&[("nxet", &["n"])])).is_err());
+        assert!(keymap(&bindings(&[("next", &["ctrl-"])])).is_err());
+        assert!(keymap(&bindings(&[("next", &["f13"])])).is_err());
+    }
+
+    #[test]
+    fn parses_named_keys() {
+        assert_eq!(
+            KeyBinding::parse("ctrl-r").unwrap(),
+            key(KeyCode::Char('r'), true)
+        );
+        assert_eq!(
+            KeyBinding::parse("PageDown").unwrap(),
+            key(KeyCode::PageDown, false)
+        );
+        assert_eq!(KeyBinding::parse("f5").unwrap(), key(KeyCode::F(5), false));
+        assert_eq!(
+            KeyBinding::parse("shift-d").unwrap(),
+            key(KeyCode::Char('D'), false)
+        );
+        assert!(KeyBinding::parse("shift-up").unwrap().shift);
+        assert_eq!(
+            KeyBinding::parse("space").unwrap(),
+            key(KeyCode::Char(' '), false)
+        );
+    }
+}
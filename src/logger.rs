@@ -0,0 +1,87 @@
+use crate::config::config_path;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// The log is moved to dstui.log.1 at startup once it grows past this size
+const LOG_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Minimal `log` backend writing timestamped lines to dstui.log next to the config file.
+///
+/// The syno-download-station crate logs through `log` as well, so its messages end up in the
+/// same file.
+struct FileLogger {
+    file: Mutex<File>,
+    level: LevelFilter,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "{} {:<5} {}: {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Start logging to the file at `level` ("off", "error", "warn", "info", "debug" or "trace").
+/// Nothing is created when logging is off.
+pub fn init(level: &str) -> anyhow::Result<()> {
+    let level = LevelFilter::from_str(level.trim())
+        .map_err(|_| anyhow::anyhow!("Unknown log level \"{}\"", level))?;
+    if level == LevelFilter::Off {
+        return Ok(());
+    }
+
+    let dir = config_path()?
+        .parent()
+        .map(|p| p.to_path_buf())
+        .ok_or_else(|| anyhow::anyhow!("Config path has no parent directory"))?;
+    std::fs::create_dir_all(&dir)?;
+
+    let path = dir.join("dstui.log");
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() > LOG_MAX_BYTES) {
+        std::fs::rename(&path, dir.join("dstui.log.1"))?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+    log::set_boxed_logger(Box::new(FileLogger {
+        file: Mutex::new(file),
+        level,
+    }))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+/// Parameters as "key=value" pairs for the log, with the password and session id hidden
+pub fn redact_params(params: &[(&str, &str)]) -> String {
+    params
+        .iter()
+        .map(|(key, value)| match *key {
+            "passwd" | "_sid" | "password" | "otp_code" => format!("{}=***", key),
+            _ => format!("{}={}", key, value),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
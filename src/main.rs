@@ -1,23 +1,102 @@
-use crate::app::App;
+use crate::app::{App, reset_terminal_title};
 use config::load_config;
-use setup::run_setup;
+use setup::{offer_connection_fix, pick_profile, run_setup};
 use std::io::{self, Write};
+use std::path::PathBuf;
 use tokio::time::{Duration, interval};
 
+mod api;
 pub mod app;
+mod cli;
 mod config;
 pub mod event;
+mod export;
+mod keys;
+mod logger;
 mod setup;
+mod theme;
+mod torrent;
 pub mod ui;
+mod util;
+
+const USAGE: &str =
+    "Usage: dstui [--list | --json | --add-url <url> | --add-file <path>] [--profile <name>]
+
+  --list             print the tasks as a table and exit
+  --json             print the tasks as JSON and exit
+  --add-url <url>    add a task from an http(s)/ftp URL, magnet or ed2k link and exit
+  --add-file <path>  add a task from a .torrent, .nzb or .txt file and exit
+  --profile <name>   server profile to use instead of asking";
+
+/// Command line options, without any the TUI starts as usual
+#[derive(Default)]
+struct Args {
+    list: bool,
+    json: bool,
+    add_url: Option<String>,
+    add_file: Option<PathBuf>,
+    profile: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--list" => args.list = true,
+            "--json" => args.json = true,
+            "--add-url" => {
+                args.add_url = Some(iter.next().ok_or("--add-url needs a URL")?);
+            }
+            "--add-file" => {
+                args.add_file = Some(iter.next().ok_or("--add-file needs a file path")?.into());
+            }
+            "--profile" => {
+                args.profile = Some(iter.next().ok_or("--profile needs a profile name")?);
+            }
+            "-h" | "--help" => return Err(String::new()),
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+    let commands = [
+        args.list || args.json,
+        args.add_url.is_some(),
+        args.add_file.is_some(),
+    ];
+    if commands.iter().filter(|c| **c).count() > 1 {
+        return Err("Only one of --list/--json, --add-url and --add-file at a time".to_string());
+    }
+    Ok(args)
+}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            if e.is_empty() {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            eprintln!("{}", e);
+            eprintln!();
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    let headless = args.list || args.json || args.add_url.is_some() || args.add_file.is_some();
+
     // Load config first, before anything else
-    let config = match load_config() {
+    let mut config = match load_config() {
         Ok(c) => c,
+        // The setup wizard needs someone at the keyboard
+        Err(e) if e.to_string() == "no_config" && headless => {
+            eprintln!("No config file yet, run dstui once without arguments to set it up");
+            std::process::exit(1);
+        }
         Err(e) if e.to_string() == "no_config" => {
             // No config file — run the setup wizard
-            match run_setup() {
+            match run_setup().await {
                 Ok(c) => c,
                 Err(e) => {
                     eprintln!("Setup failed: {}", e);
@@ -39,45 +118,96 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
-    // Spinner setup
-    let spinner_frames = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
-    let mut frame = 0;
-
-    let app_future = tokio::spawn(App::new(config));
+    // A broken log setup shouldn't keep the app from starting
+    if let Err(e) = logger::init(&config.logging.level) {
+        eprintln!("Failed to set up logging: {}", e);
+    }
 
-    let mut ticker = interval(Duration::from_millis(80));
-    loop {
-        ticker.tick().await;
-        print!(
-            "\r {} Connecting to DownloadStation...",
-            spinner_frames[frame % spinner_frames.len()]
-        );
-        io::stdout().flush()?;
-        frame += 1;
+    let profile = match &args.profile {
+        Some(name) => match config.profiles.iter().position(|p| p.name == *name) {
+            Some(i) => i,
+            None => {
+                eprintln!("No profile named \"{}\" in the config", name);
+                std::process::exit(1);
+            }
+        },
+        // Scripts can't answer the profile prompt
+        None if headless => config.default_profile_index(),
+        None => match pick_profile(&config) {
+            Ok(i) => i,
+            Err(e) => {
+                eprintln!("Failed to read the profile choice: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
 
-        if app_future.is_finished() {
-            break;
+    if headless {
+        let (result, failure) = if let Some(url) = &args.add_url {
+            (cli::add_url(&config, profile, url).await, "add the task")
+        } else if let Some(path) = &args.add_file {
+            (cli::add_file(&config, profile, path).await, "add the task")
+        } else {
+            (
+                cli::print_tasks(&config, profile, args.json).await,
+                "list tasks",
+            )
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to {}: {:#}", failure, e);
+            std::process::exit(1);
         }
+        return Ok(());
     }
 
-    // Clear the spinner line
-    print!("\r{}\r", " ".repeat(50));
-    io::stdout().flush()?;
+    let app = loop {
+        // Spinner setup
+        let spinner_frames = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+        let mut frame = 0;
 
-    // Handle connection error cleanly before ratatui takes over
-    let app = match app_future.await? {
-        Ok(app) => app,
-        Err(e) => {
-            eprintln!("Failed to connect to DownloadStation:");
-            eprintln!("  {}", e);
-            eprintln!();
-            eprintln!(
-                "Please check your config file: {}",
-                config::config_path()
-                    .map(|p| p.display().to_string())
-                    .unwrap_or_else(|_| "unknown".to_string())
+        let app_future = tokio::spawn(App::new(config.clone(), profile));
+
+        let mut ticker = interval(Duration::from_millis(80));
+        loop {
+            ticker.tick().await;
+            print!(
+                "\r {} Connecting to DownloadStation...",
+                spinner_frames[frame % spinner_frames.len()]
             );
-            std::process::exit(1);
+            io::stdout().flush()?;
+            frame += 1;
+
+            if app_future.is_finished() {
+                break;
+            }
+        }
+
+        // Clear the spinner line
+        print!("\r{}\r", " ".repeat(50));
+        io::stdout().flush()?;
+
+        // Handle connection error cleanly before ratatui takes over
+        match app_future.await? {
+            Ok(app) => break app,
+            Err(e) => {
+                eprintln!("Failed to connect to DownloadStation:");
+                eprintln!("  {:#}", e);
+                eprintln!();
+                eprintln!(
+                    "Config file: {}",
+                    config::config_path()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_else(|_| "unknown".to_string())
+                );
+                match offer_connection_fix(&mut config, profile).await {
+                    Ok(true) => {}
+                    Ok(false) => std::process::exit(1),
+                    Err(e) => {
+                        eprintln!("Failed to update the config: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
         }
     };
 
@@ -85,6 +215,7 @@ async fn main() -> anyhow::Result<()> {
     let terminal = ratatui::init();
     let result = app.run(terminal).await;
     ratatui::restore();
+    reset_terminal_title();
 
     if let Err(e) = result {
         eprintln!("Application error: {}", e);
@@ -1,4 +1,10 @@
-use crate::config::{Config, ConnectionConfig, DownloadConfig, SortConfig, config_path};
+use crate::api::DsApi;
+use crate::config::{
+    Config, ConnectionConfig, DisplayConfig, DownloadConfig, LogConfig, ServerProfile, SortConfig,
+    config_path, default_adaptive_refresh, default_stalled_after_secs,
+    default_typed_delete_size_mb, save_config,
+};
+use crate::util::normalize_server_url;
 use anyhow::Result;
 use std::io::{self, Write};
 
@@ -54,7 +60,128 @@ fn prompt_optional_u64(label: &str, default: Option<u64>) -> Result<Option<u64>>
     }
 }
 
-pub fn run_setup() -> Result<Config> {
+/// Ask for the connection details, offering `previous` (from a failed connection test) as
+/// the defaults
+fn prompt_connection(previous: Option<&ConnectionConfig>) -> Result<ConnectionConfig> {
+    let url = loop {
+        let default = previous.map_or("http://diskstation:5000", |c| c.url.as_str());
+        match normalize_server_url(&prompt("DiskStation URL", default)?) {
+            Ok(url) => break url,
+            Err(e) => println!("  {}", e),
+        }
+    };
+    let username = prompt(
+        "Username",
+        previous.map_or("admin", |c| c.username.as_str()),
+    )?;
+    let password = match previous {
+        Some(previous) => {
+            let password = prompt_password("Password (Enter to keep the previous one)")?;
+            if password.is_empty() {
+                previous.password.clone()
+            } else {
+                password
+            }
+        }
+        None => prompt_password("Password")?,
+    };
+    let accept_invalid_certs = {
+        let default = if previous.is_some_and(|c| c.accept_invalid_certs) {
+            "y"
+        } else {
+            "n"
+        };
+        let input = prompt("Accept invalid/self-signed certificates? (y/n)", default)?;
+        input.trim().to_lowercase() == "y"
+    };
+    // Only useful for a verified https connection
+    let ca_cert = if !accept_invalid_certs && url.starts_with("https://") {
+        let default = previous.and_then(|c| c.ca_cert.as_deref()).unwrap_or("");
+        Some(prompt(
+            "CA certificate file for a self-signed certificate (optional)",
+            default,
+        )?)
+        .filter(|path| !path.is_empty())
+    } else {
+        None
+    };
+    let timeout_ms = {
+        let default = previous.map_or(3000, |c| c.timeout_ms);
+        let input = prompt("Connection timeout (ms)", &default.to_string())?;
+        input.parse::<u64>().unwrap_or(default)
+    };
+
+    Ok(ConnectionConfig {
+        url,
+        username,
+        password,
+        accept_invalid_certs,
+        ca_cert,
+        // Not asked for, few need it, but a proxy from the config file is kept
+        proxy: previous.and_then(|c| c.proxy.clone()),
+        timeout_ms,
+    })
+}
+
+/// Log in with the entered details without saving anything
+async fn test_connection(connection: &ConnectionConfig) -> Result<()> {
+    let api = DsApi::new(connection)?;
+    api.authorize().await?;
+    // Don't leave a session behind on the DiskStation
+    let _ = api.logout().await;
+    Ok(())
+}
+
+/// Ask for the connection details and offer to test them, asking again with the entered
+/// values as defaults until the test passes or the user keeps them anyway
+async fn prompt_tested_connection(previous: Option<&ConnectionConfig>) -> Result<ConnectionConfig> {
+    let mut connection = prompt_connection(previous)?;
+    loop {
+        let input = prompt("Test the connection now? (y/n)", "y")?;
+        if input.trim().to_lowercase() != "y" {
+            return Ok(connection);
+        }
+        print!("  Connecting...");
+        io::stdout().flush()?;
+        match test_connection(&connection).await {
+            Ok(()) => {
+                println!(" ✓ logged in");
+                return Ok(connection);
+            }
+            Err(e) => {
+                println!(" ✗ {:#}", e);
+                let input = prompt("Change the connection details? (y/n)", "y")?;
+                if input.trim().to_lowercase() != "y" {
+                    return Ok(connection);
+                }
+                println!();
+                connection = prompt_connection(Some(&connection))?;
+            }
+        }
+    }
+}
+
+/// After connecting at startup failed, offer to correct the profile's connection details.
+/// Returns false when the user would rather quit.
+pub async fn offer_connection_fix(config: &mut Config, profile: usize) -> Result<bool> {
+    let input = prompt("Change the connection details? (y/n)", "y")?;
+    if input.trim().to_lowercase() != "y" {
+        return Ok(false);
+    }
+
+    println!();
+    println!(
+        "  ── Connection: {} ──────────────────────────────────────────",
+        config.profiles[profile].name
+    );
+    let connection = prompt_tested_connection(Some(&config.profiles[profile].connection)).await?;
+    config.profiles[profile].connection = connection;
+    save_config(config)?;
+    println!();
+    Ok(true)
+}
+
+pub async fn run_setup() -> Result<Config> {
     println!();
     println!("  ╭─────────────────────────────────────╮");
     println!("  │   dstui — first time setup wizard   │");
@@ -65,17 +192,8 @@ pub fn run_setup() -> Result<Config> {
     println!();
 
     println!("  ── Connection ───────────────────────────────────────────────────");
-    let url = prompt("DiskStation URL", "http://diskstation:5000")?;
-    let username = prompt("Username", "admin")?;
-    let password = prompt_password("Password")?;
-    let accept_invalid_certs = {
-        let input = prompt("Accept invalid/self-signed certificates? (y/n)", "n")?;
-        input.trim().to_lowercase() == "y"
-    };
-    let timeout_ms = {
-        let input = prompt("Connection timeout (ms)", "3000")?;
-        input.parse::<u64>().unwrap_or(3000)
-    };
+    let name = prompt("Profile name", "default")?;
+    let connection = prompt_tested_connection(None).await?;
     println!();
 
     println!("  ── Downloads ────────────────────────────────────────────────────");
@@ -101,42 +219,71 @@ pub fn run_setup() -> Result<Config> {
     println!();
 
     let config = Config {
-        connection: ConnectionConfig {
-            url,
-            username,
-            password,
-            accept_invalid_certs,
-            timeout_ms,
-        },
+        default_profile: name.clone(),
+        use_keyring: true,
+        connection: None,
         downloads: DownloadConfig {
             destination,
             refresh_interval,
+            typed_delete_size_mb: default_typed_delete_size_mb(),
+            adaptive_refresh: default_adaptive_refresh(),
+            seed_ratio_target: None,
+            stalled_after_secs: default_stalled_after_secs(),
+            confirm_add_above_bytes: 0,
         },
         sorting: SortConfig {
             column: sort_column,
             order: sort_order,
         },
+        display: DisplayConfig::default(),
+        logging: LogConfig::default(),
+        profiles: vec![ServerProfile {
+            name,
+            connection,
+            refresh_interval: None,
+        }],
+        keybindings: Default::default(),
     };
 
-    // Write to file
+    save_config(&config)?;
     let path = config_path()?;
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    let toml = toml::to_string_pretty(&config)?;
-    std::fs::write(&path, &toml)?;
-
-    // Change file permissions to owner read/write only
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(&path)?.permissions();
-        perms.set_mode(0o600);
-        std::fs::set_permissions(&path, perms)?;
-    }
 
     println!("  Config saved to: {}", path.display());
     println!();
 
     Ok(config)
 }
+
+/// Ask which server to connect to when there is more than one profile, Enter picks the
+/// default profile
+pub fn pick_profile(config: &Config) -> Result<usize> {
+    let default = config.default_profile_index();
+    if config.profiles.len() < 2 {
+        return Ok(default);
+    }
+
+    println!();
+    for (i, profile) in config.profiles.iter().enumerate() {
+        println!(
+            "  {}) {:<20} {}",
+            i + 1,
+            profile.name,
+            profile.connection.url
+        );
+    }
+    println!();
+
+    loop {
+        let input = prompt("Connect to", &(default + 1).to_string())?;
+        match input.parse::<usize>() {
+            Ok(n) if (1..=config.profiles.len()).contains(&n) => return Ok(n - 1),
+            _ => {
+                // Accept the profile name too
+                if let Some(i) = config.profiles.iter().position(|p| p.name == input) {
+                    return Ok(i);
+                }
+                println!("  Enter a number between 1 and {}", config.profiles.len());
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use ratatui::style::{Color, Style};
+
+/// Colors used by the UI, selectable with the `theme` option in the `[display]` config section
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub accent: Color,
+    pub title: Color,
+    pub filter: Color,
+    pub header_fg: Color,
+    pub header_bg: Color,
+    pub tab_selected_fg: Color,
+    pub text: Color,
+    pub label: Color,
+    pub muted: Color,
+    pub selection: Style,
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+    pub info: Color,
+    pub finishing: Color,
+    pub popup_text: Color,
+    pub error_text: Color,
+    pub notification_text: Color,
+    pub progress_low: Color,
+    pub progress_mid: Color,
+    pub progress_done: Color,
+}
+
+impl Theme {
+    /// Built-in theme by name, unknown names fall back to the default theme
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "dark" => Self::dark(),
+            "light" => Self::light(),
+            "solarized" => Self::solarized(),
+            _ => Self::default(),
+        }
+    }
+
+    fn dark() -> Self {
+        Self {
+            accent: Color::Cyan,
+            title: Color::LightBlue,
+            filter: Color::LightMagenta,
+            header_fg: Color::White,
+            header_bg: Color::Black,
+            tab_selected_fg: Color::Black,
+            text: Color::Gray,
+            label: Color::LightBlue,
+            muted: Color::DarkGray,
+            selection: Style::new().fg(Color::Black).bg(Color::Cyan),
+            success: Color::LightGreen,
+            warning: Color::LightYellow,
+            error: Color::LightRed,
+            info: Color::LightCyan,
+            finishing: Color::Green,
+            popup_text: Color::Gray,
+            error_text: Color::LightRed,
+            notification_text: Color::LightGreen,
+            progress_low: Color::LightRed,
+            progress_mid: Color::LightYellow,
+            progress_done: Color::LightGreen,
+        }
+    }
+
+    fn light() -> Self {
+        Self {
+            accent: Color::Blue,
+            title: Color::Blue,
+            filter: Color::Magenta,
+            header_fg: Color::White,
+            header_bg: Color::Blue,
+            tab_selected_fg: Color::White,
+            text: Color::Black,
+            label: Color::Rgb(0, 95, 135),
+            muted: Color::Gray,
+            selection: Style::new().fg(Color::White).bg(Color::Blue),
+            success: Color::Rgb(0, 128, 0),
+            warning: Color::Rgb(175, 95, 0),
+            error: Color::Rgb(175, 0, 0),
+            info: Color::Rgb(0, 128, 128),
+            finishing: Color::Rgb(0, 128, 0),
+            popup_text: Color::Black,
+            error_text: Color::Rgb(175, 0, 0),
+            notification_text: Color::Rgb(0, 128, 0),
+            progress_low: Color::Rgb(175, 0, 0),
+            progress_mid: Color::Rgb(175, 95, 0),
+            progress_done: Color::Rgb(0, 128, 0),
+        }
+    }
+
+    fn solarized() -> Self {
+        let base01 = Color::Rgb(88, 110, 117);
+        let base02 = Color::Rgb(7, 54, 66);
+        let base0 = Color::Rgb(131, 148, 150);
+        let base1 = Color::Rgb(147, 161, 161);
+        let yellow = Color::Rgb(181, 137, 0);
+        let orange = Color::Rgb(203, 75, 22);
+        let red = Color::Rgb(220, 50, 47);
+        let magenta = Color::Rgb(211, 54, 130);
+        let blue = Color::Rgb(38, 139, 210);
+        let cyan = Color::Rgb(42, 161, 152);
+        let green = Color::Rgb(133, 153, 0);
+
+        Self {
+            accent: yellow,
+            title: blue,
+            filter: magenta,
+            header_fg: base1,
+            header_bg: base02,
+            tab_selected_fg: base02,
+            text: base0,
+            label: cyan,
+            muted: base01,
+            selection: Style::new().fg(base02).bg(base1),
+            success: green,
+            warning: yellow,
+            error: red,
+            info: cyan,
+            finishing: green,
+            popup_text: base1,
+            error_text: orange,
+            notification_text: green,
+            progress_low: red,
+            progress_mid: yellow,
+            progress_done: green,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            accent: Color::Yellow,
+            title: Color::Blue,
+            filter: Color::Magenta,
+            header_fg: Color::White,
+            header_bg: Color::DarkGray,
+            tab_selected_fg: Color::Black,
+            text: Color::White,
+            label: Color::LightCyan,
+            muted: Color::DarkGray,
+            selection: Style::new().reversed(),
+            success: Color::Green,
+            warning: Color::Yellow,
+            error: Color::Red,
+            info: Color::Cyan,
+            finishing: Color::LightGreen,
+            popup_text: Color::LightYellow,
+            error_text: Color::LightRed,
+            notification_text: Color::LightGreen,
+            progress_low: Color::Red,
+            progress_mid: Color::Yellow,
+            progress_done: Color::Green,
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Just enough of a bencode reader to summarize a .torrent file before it is uploaded, the
+//! NAS does the real parsing.
+
+/// What the add confirmation shows about a .torrent file
+#[derive(Debug, PartialEq)]
+pub struct TorrentSummary {
+    pub name: String,
+    pub size: u64,    // sum of all file lengths in bytes
+    pub files: usize, // 1 for a single file torrent
+}
+
+/// A decoded bencode value, byte strings borrow from the file
+#[derive(Debug)]
+enum Value<'a> {
+    Int(i64),
+    Bytes(&'a [u8]),
+    List(Vec<Value<'a>>),
+    Dict(Vec<(&'a [u8], Value<'a>)>),
+}
+
+impl<'a> Value<'a> {
+    fn get(&self, key: &str) -> Option<&Value<'a>> {
+        match self {
+            Value::Dict(entries) => entries
+                .iter()
+                .find(|(k, _)| *k == key.as_bytes())
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn int(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    fn text(&self) -> Option<String> {
+        match self {
+            Value::Bytes(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+            _ => None,
+        }
+    }
+}
+
+// Lists and dictionaries nest, a crafted file shouldn't be able to overflow the stack
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn value(&mut self, depth: usize) -> Result<Value<'a>, String> {
+        if depth > MAX_DEPTH {
+            return Err("nested too deeply".into());
+        }
+        match self.data.get(self.pos) {
+            Some(b'i') => {
+                self.pos += 1;
+                let digits = self.until(b'e')?;
+                std::str::from_utf8(digits)
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .map(Value::Int)
+                    .ok_or_else(|| "invalid integer".into())
+            }
+            Some(b'l') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while self.data.get(self.pos) != Some(&b'e') {
+                    items.push(self.value(depth + 1)?);
+                }
+                self.pos += 1;
+                Ok(Value::List(items))
+            }
+            Some(b'd') => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                while self.data.get(self.pos) != Some(&b'e') {
+                    let Value::Bytes(key) = self.value(depth + 1)? else {
+                        return Err("dictionary key isn't a string".into());
+                    };
+                    entries.push((key, self.value(depth + 1)?));
+                }
+                self.pos += 1;
+                Ok(Value::Dict(entries))
+            }
+            Some(b'0'..=b'9') => {
+                let len: usize = std::str::from_utf8(self.until(b':')?)
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or("invalid string length")?;
+                let bytes = self
+                    .data
+                    .get(self.pos..self.pos.saturating_add(len))
+                    .ok_or("file ends in the middle of a string")?;
+                self.pos += len;
+                Ok(Value::Bytes(bytes))
+            }
+            Some(_) => Err(format!("unexpected byte at offset {}", self.pos)),
+            None => Err("file ends too early".into()),
+        }
+    }
+
+    /// The bytes up to `end`, which is skipped
+    fn until(&mut self, end: u8) -> Result<&'a [u8], String> {
+        let rest = &self.data[self.pos..];
+        let len = rest
+            .iter()
+            .position(|&b| b == end)
+            .ok_or("file ends too early")?;
+        self.pos += len + 1;
+        Ok(&rest[..len])
+    }
+}
+
+/// Name, total size and number of files of a .torrent file, or why it can't be read
+pub fn parse_torrent(data: &[u8]) -> Result<TorrentSummary, String> {
+    let root = Parser { data, pos: 0 }.value(0)?;
+    let info = root.get("info").ok_or("no info section")?;
+    let name = info
+        .get("name.utf-8")
+        .or_else(|| info.get("name"))
+        .and_then(Value::text)
+        .unwrap_or_default();
+
+    let length = |file: &Value| {
+        let length = file.get("length").and_then(Value::int).unwrap_or(0);
+        length.max(0) as u64
+    };
+    match (info.get("length"), info.get("files")) {
+        (Some(_), _) => Ok(TorrentSummary {
+            name,
+            size: length(info),
+            files: 1,
+        }),
+        (None, Some(Value::List(files))) => Ok(TorrentSummary {
+            name,
+            size: files.iter().map(length).sum(),
+            files: files.len(),
+        }),
+        _ => Err("no file list, maybe a BitTorrent v2 only torrent".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_file_torrent() {
+        let data =
+            b"d8:announce3:url4:infod6:lengthi1048576e4:name9:image.iso12:piece lengthi16384eee";
+        assert_eq!(
+            parse_torrent(data),
+            Ok(TorrentSummary {
+                name: "image.iso".into(),
+                size: 1_048_576,
+                files: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn multi_file_torrent() {
+        let data = b"d4:infod5:filesld6:lengthi100e4:pathl5:a.txteed6:lengthi250e4:pathl3:sub5:b.txteee4:name6:folderee";
+        assert_eq!(
+            parse_torrent(data),
+            Ok(TorrentSummary {
+                name: "folder".into(),
+                size: 350,
+                files: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn broken_torrents_are_errors() {
+        assert!(parse_torrent(b"").is_err());
+        assert!(parse_torrent(b"d4:infod4:name3:abc").is_err());
+        assert!(parse_torrent(b"d4:infod4:name99:abcee").is_err());
+        assert!(parse_torrent(b"<html>not a torrent</html>").is_err());
+        assert!(parse_torrent(&[b'l'; 1000]).is_err());
+    }
+}
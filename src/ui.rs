@@ -1,20 +1,76 @@
-use crate::app::{ActivePanel, App, ConnectionStatus, SPINNER_FRAMES, SortColumn, SortOrder};
+use crate::api::{Statistics, is_error_status, status_label};
+use crate::app::{
+    ActivePanel, App, BtSearchView, ColumnId, ConnectionStatus, FolderBrowser, PopupKind,
+    ProfilePicker, RssView, SPINNER_FRAMES, ServerSetting, ServerSettingsView, SettingsEditor,
+    SettingsField, SortColumn, SortOrder, SpeedLimitField, SpeedLimitForm, UrlField,
+};
+use crate::keys::{Action, keys_for};
+use crate::theme::Theme;
+use crate::util::{
+    UNKNOWN, format_bytes, format_date, format_eta, format_ratio, format_relative, format_speed,
+    format_timestamp, has_transfer, parse_magnet, truncate, validate_url,
+};
+use chrono::{DateTime, Utc};
 use ratatui::{
     buffer::Buffer,
-    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
-    style::{Color, Style, Stylize},
-    text::{Line, Span},
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Margin, Position, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
     widgets::{
         Block, BorderType, Cell, Clear, Paragraph, Row, Scrollbar, ScrollbarOrientation,
-        ScrollbarState, StatefulWidget, Table, TableState, Tabs, Widget, WidgetRef, Wrap,
+        ScrollbarState, Sparkline, StatefulWidget, Table, TableState, Tabs, Widget, WidgetRef,
+        Wrap,
     },
 };
+use std::collections::VecDeque;
 use syno_download_station::entities::{Task, TaskStatus};
+use syno_download_station::utils::convert_time_left;
+
+/// Smallest terminal the normal layout is drawn in, below it only a message is shown
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 12;
 
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let chunks =
-            Layout::vertical([Constraint::Percentage(40), Constraint::Percentage(60)]).split(area);
+        // Cloned so the theme stays usable while `self` is mutably borrowed
+        let theme = &self.theme.clone();
+
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            // Nothing on screen to click, point at or animate until the terminal grows
+            self.task_table_area = Rect::default();
+            self.tabs_area = Rect::default();
+            self.info_area = Rect::default();
+            self.spinner_pos = None;
+            self.url_input_cursor_pos = None;
+            self.filter_cursor_pos = None;
+
+            let message = format!(
+                "Terminal too small ({}×{}), need at least {}×{}",
+                area.width, area.height, MIN_WIDTH, MIN_HEIGHT
+            );
+            let lines = message.chars().count() as u16 / area.width.max(1) + 1;
+            let [message_area] = Layout::vertical([Constraint::Length(lines)])
+                .flex(Flex::Center)
+                .areas(area);
+            Paragraph::new(message)
+                .style(Style::default().fg(theme.error))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
+                .render(message_area, buf);
+            return;
+        }
+
+        let table_percent = if self.info_panel_visible() {
+            self.table_percent
+        } else {
+            100
+        };
+        let chunks = Layout::vertical([
+            Constraint::Percentage(table_percent),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .split(area);
 
         // Build headers with sort indicator
         let sort_indicator = |col: &SortColumn| -> &str {
@@ -29,18 +85,24 @@ impl Widget for &mut App {
         };
 
         let connection_indicator = match self.connection_status {
-            ConnectionStatus::Connected => Span::styled(" ● ", Style::default().fg(Color::Green)),
-            ConnectionStatus::Disconnected => Span::styled(" ● ", Style::default().fg(Color::Red)),
+            ConnectionStatus::Connected => Span::styled(" ● ", Style::default().fg(theme.success)),
+            ConnectionStatus::Disconnected => Span::styled(" ● ", Style::default().fg(theme.error)),
         };
 
         let spinner = SPINNER_FRAMES[self.spinner_frame];
 
-        let mut title_spans = vec![connection_indicator];
+        let mut title_spans = vec![
+            connection_indicator,
+            Span::styled(
+                format!("{} · ", self.profile_name),
+                Style::default().fg(theme.muted),
+            ),
+        ];
 
-        if self.loading {
+        if let Some(label) = self.in_flight {
             title_spans.push(Span::styled(
-                format!("{} DownloadStation TUI Client ", spinner),
-                Style::default().fg(Color::Blue).bold(),
+                format!("DownloadStation TUI Client - [{} {}…] ", spinner, label),
+                Style::default().fg(theme.title).bold(),
             ));
         } else {
             match self.refresh_interval {
@@ -50,22 +112,47 @@ impl Widget for &mut App {
                             "DownloadStation TUI Client - [Auto-refresh: {}s] ",
                             ticks / 30
                         ),
-                        Style::default().fg(Color::Blue).bold(),
+                        Style::default().fg(theme.title).bold(),
                     ));
                 }
                 None => {
                     title_spans.push(Span::styled(
                         "DownloadStation TUI Client - [Auto-refresh: off] ",
-                        Style::default().fg(Color::Blue).bold(),
+                        Style::default().fg(theme.title).bold(),
                     ));
                 }
             }
         }
 
+        if self.error_count > 0 {
+            title_spans.push(Span::styled(
+                format!(
+                    "⚠ {} error{} ",
+                    self.error_count,
+                    if self.error_count == 1 { "" } else { "s" }
+                ),
+                Style::default().fg(theme.error).bold(),
+            ));
+            let retry_keys = keys_for(&self.keymap, Action::RetryErrors);
+            if !retry_keys.is_empty() {
+                title_spans.push(Span::styled(
+                    format!("({} to retry) ", retry_keys.join(" / ")),
+                    Style::default().fg(theme.muted),
+                ));
+            }
+        }
+
         if !self.filter_text.is_empty() {
             title_spans.push(Span::styled(
                 format!("[filter: {}] ", self.filter_text),
-                Style::default().fg(Color::Magenta),
+                Style::default().fg(theme.filter),
+            ));
+        }
+
+        if self.hide_finished {
+            title_spans.push(Span::styled(
+                "[finished hidden] ",
+                Style::default().fg(theme.filter),
             ));
         }
 
@@ -76,118 +163,89 @@ impl Widget for &mut App {
             .title_alignment(Alignment::Center)
             .border_type(BorderType::Rounded)
             .border_style(match self.active_panel {
-                ActivePanel::Tasks => Style::default().fg(Color::Yellow),
+                ActivePanel::Tasks => Style::default().fg(theme.accent),
                 _ => Style::default(),
             });
 
         let task_row_index = self.selected_task_index();
 
-        let header = Row::new(vec![
-            Cell::from(format!("Name{}", sort_indicator(&SortColumn::Name)))
-                .style(Style::default().fg(Color::White).bg(Color::DarkGray).bold()),
-            Cell::from(format!("Size{}", sort_indicator(&SortColumn::Size)))
-                .style(Style::default().fg(Color::White).bg(Color::DarkGray).bold()),
-            Cell::from(format!(
-                "Downloaded{}",
-                sort_indicator(&SortColumn::Downloaded)
-            ))
-            .style(Style::default().fg(Color::White).bg(Color::DarkGray).bold()),
-            Cell::from(format!("Uploaded{}", sort_indicator(&SortColumn::Uploaded)))
-                .style(Style::default().fg(Color::White).bg(Color::DarkGray).bold()),
-            Cell::from(format!("Progress{}", sort_indicator(&SortColumn::Progress)))
-                .style(Style::default().fg(Color::White).bg(Color::DarkGray).bold()),
-            Cell::from(format!(
-                "Up Speed{}",
-                sort_indicator(&SortColumn::UploadSpeed)
-            ))
-            .style(Style::default().fg(Color::White).bg(Color::DarkGray).bold()),
-            Cell::from(format!(
-                "Down Speed{}",
-                sort_indicator(&SortColumn::DownloadSpeed)
-            ))
-            .style(Style::default().fg(Color::White).bg(Color::DarkGray).bold()),
-            Cell::from(format!("Ratio{}", sort_indicator(&SortColumn::Ratio)))
-                .style(Style::default().fg(Color::White).bg(Color::DarkGray).bold()),
-            Cell::from(format!("Status{}", sort_indicator(&SortColumn::Status)))
-                .style(Style::default().fg(Color::White).bg(Color::DarkGray).bold()),
-        ]);
-
-        let rows: Vec<Row> = self
-            .sorted_tasks()
+        let header_style = Style::default()
+            .fg(theme.header_fg)
+            .bg(theme.header_bg)
+            .bold();
+        let header = Row::new(self.columns.iter().map(|column| {
+            let indicator = column
+                .sort_column()
+                .map(|c| sort_indicator(&c))
+                .unwrap_or("");
+            Cell::from(format!("{}{}", column.title(), indicator)).style(header_style)
+        }));
+
+        // Only the rows that fit are built, which matters with thousands of tasks. The offset
+        // follows the selection the same way the table would scroll by itself.
+        let sorted = self.sorted_tasks();
+        let row_count = sorted.len();
+        // Never point past the list, whatever shrank it since the last refresh
+        let task_row_index = task_row_index
+            .filter(|_| row_count > 0)
+            .map(|selected| selected.min(row_count - 1));
+        let visible_rows = (chunks[0].height.saturating_sub(3) as usize).max(1);
+        let mut offset = self
+            .selected_task
+            .offset()
+            .min(row_count.saturating_sub(visible_rows));
+        if let Some(selected) = task_row_index {
+            if selected < offset {
+                offset = selected;
+            } else if selected >= offset + visible_rows {
+                offset = selected + 1 - visible_rows;
+            }
+        }
+        let widths: Vec<Constraint> = self
+            .columns
             .iter()
-            .map(|task| {
-                let progress = task.calculate_progress();
-
-                let status_style = match task.status {
-                    TaskStatus::Downloading => Style::default().fg(Color::Green),
-                    TaskStatus::Seeding => Style::default().fg(Color::Cyan),
-                    TaskStatus::Waiting => Style::default().fg(Color::Yellow),
-                    TaskStatus::Paused => Style::default().fg(Color::DarkGray),
-                    TaskStatus::Finishing => Style::default().fg(Color::LightGreen),
-                    TaskStatus::Finished => Style::default().fg(Color::DarkGray),
-                    TaskStatus::HashChecking => Style::default().fg(Color::Yellow),
-                    TaskStatus::Error => Style::default().fg(Color::Red),
-                    _ => Style::default().fg(Color::White),
-                };
+            .map(|column| Constraint::Fill(column.width()))
+            .collect();
+        // Names are cut to the width the table gives their column, the same split it makes
+        let name_width = self
+            .columns
+            .iter()
+            .position(|column| *column == ColumnId::Name)
+            .map_or(0, |index| {
+                Layout::horizontal(widths.clone())
+                    .spacing(1)
+                    .split(table_block.inner(chunks[0]))[index]
+                    .width as usize
+            });
 
+        let rows: Vec<Row> = sorted
+            .iter()
+            .skip(offset)
+            .take(visible_rows)
+            .map(|task| {
                 let row_style = match task.status {
-                    TaskStatus::Paused | TaskStatus::Finished => {
-                        Style::default().fg(Color::DarkGray)
-                    }
+                    TaskStatus::Paused | TaskStatus::Finished => Style::default().fg(theme.muted),
+                    _ if self.is_stalled(task) => Style::default().fg(theme.error).dim(),
                     _ => Style::default(),
                 };
 
-                Row::new(vec![
-                    Cell::from(truncate(&task.title, 40)),
-                    Cell::from(task.calculate_size()),
-                    Cell::from(
-                        task.additional
-                            .as_ref()
-                            .and_then(|a| a.transfer.as_ref())
-                            .map(|t| format!("{:.1} MB", t.size_downloaded as f64 / 1_048_576.0))
-                            .unwrap_or_default(),
-                    ),
-                    Cell::from(
-                        task.additional
-                            .as_ref()
-                            .and_then(|a| a.transfer.as_ref())
-                            .map(|t| format!("{:.1} MB", t.size_uploaded as f64 / 1_048_576.0))
-                            .unwrap_or_default(),
-                    ),
-                    Cell::from(Line::from(render_progress_bar(progress, 8))),
-                    Cell::from(
-                        task.additional
-                            .as_ref()
-                            .and_then(|a| a.transfer.as_ref())
-                            .map(|t| format_speed(t.speed_upload))
-                            .unwrap_or_default(),
-                    ),
-                    Cell::from(
-                        task.additional
-                            .as_ref()
-                            .and_then(|a| a.transfer.as_ref())
-                            .map(|t| format_speed(t.speed_download))
-                            .unwrap_or_default(),
+                let matches = self.filter_matches(&task.title);
+                Row::new(self.columns.iter().map(|column| match column {
+                    ColumnId::Name if !matches.is_empty() => {
+                        highlighted_name(&task.title, &matches, name_width, theme)
+                    }
+                    _ => task_cell(
+                        *column,
+                        task,
+                        name_width,
+                        self.binary_units,
+                        self.use_local_time,
+                        theme,
                     ),
-                    Cell::from(format!("{:.2}", task.calculate_ratio())),
-                    Cell::from(format!("{:?}", task.status)).style(status_style),
-                ])
+                }))
                 .style(row_style)
             })
             .collect();
-        let row_count = rows.len();
-
-        let widths = [
-            Constraint::Percentage(22), // Name
-            Constraint::Percentage(8),  // Size
-            Constraint::Percentage(8),  // Downloaded
-            Constraint::Percentage(8),  // Uploaded
-            Constraint::Percentage(14), // Progress (wider for bar)
-            Constraint::Percentage(10), // Upload Speed
-            Constraint::Percentage(10), // Download Speed
-            Constraint::Percentage(5),  // Ratio
-            Constraint::Percentage(10), // Status
-        ];
 
         let table_scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
             .begin_symbol(Some("↑"))
@@ -199,10 +257,19 @@ impl Widget for &mut App {
         let table = Table::new(rows, widths)
             .block(table_block)
             .header(header)
-            .row_highlight_style(Style::new().reversed())
+            .row_highlight_style(theme.selection)
             .column_spacing(1);
 
-        StatefulWidget::render(table, chunks[0], buf, &mut self.selected_task);
+        // Visible rows: table area minus the borders and the header row
+        self.task_inner_height = chunks[0].height.saturating_sub(3) as usize;
+        self.task_table_area = chunks[0];
+        self.info_area = chunks[1];
+
+        let mut window_state =
+            TableState::default().with_selected(task_row_index.map(|selected| selected - offset));
+        StatefulWidget::render(table, chunks[0], buf, &mut window_state);
+        self.selected_task.select(task_row_index);
+        *self.selected_task.offset_mut() = offset;
         StatefulWidget::render(
             table_scrollbar,
             chunks[0].inner(Margin {
@@ -213,82 +280,86 @@ impl Widget for &mut App {
             &mut table_scrollbar_state,
         );
 
-        // Info panel
-        let info_block = Block::bordered()
-            .title(Line::from(vec![
-                Span::styled(" Info ", Style::default().bold()),
-                Span::styled(
-                    format!("— {} ", self.tabs[self.selected_tab]),
-                    Style::default().fg(Color::Yellow).bold(),
-                ),
-            ]))
-            .title_alignment(Alignment::Center)
-            .title_bottom(" Tab to switch panels ")
-            .border_type(BorderType::Rounded)
-            .border_style(match self.active_panel {
-                ActivePanel::Info => Style::default().fg(Color::Yellow),
-                _ => Style::default(),
-            });
-        info_block.render(chunks[1], buf);
+        if self.info_panel_visible() {
+            self.render_info_panel(chunks[1], buf, theme);
+        } else {
+            // Nothing to click on
+            self.info_area = Rect::default();
+            self.tabs_area = Rect::default();
+        }
 
-        let inner_area = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(1)
-            .constraints([Constraint::Length(1), Constraint::Min(0)])
-            .split(chunks[1]);
+        // Only a hint while the last refresh couldn't reach the DiskStation
+        let reconnect_keys = match self.connection_status {
+            ConnectionStatus::Connected => None,
+            ConnectionStatus::Disconnected => {
+                Some(keys_for(&self.keymap, Action::Reconnect).join(" / "))
+            }
+        };
+        render_status_bar(
+            &self.tasks,
+            chunks[2],
+            buf,
+            self.binary_units,
+            match self.effective_refresh_secs() {
+                None => RefreshState::Off,
+                Some(secs) if self.refresh_paused => RefreshState::Paused(secs),
+                Some(secs) if self.refresh_backed_off() => RefreshState::Idle(secs),
+                Some(secs) => RefreshState::Every(secs),
+            },
+            reconnect_keys.as_deref(),
+            theme,
+        );
 
-        let tab_titles = self
-            .tabs
-            .iter()
-            .enumerate()
-            .map(|(i, t)| {
-                if i == self.selected_tab {
-                    Line::from(Span::styled(
-                        format!(" {} ", t),
-                        Style::default().fg(Color::Black).bg(Color::Yellow).bold(),
-                    ))
-                } else {
-                    Line::from(Span::styled(
-                        format!(" {} ", t),
-                        Style::default().fg(Color::White),
-                    ))
-                }
-            })
-            .collect::<Vec<_>>();
+        if let Some(rss) = &mut self.rss {
+            let rss_area = area.centered(Constraint::Percentage(90), Constraint::Percentage(90));
+            render_rss_view(rss, rss_area, buf, self.binary_units, theme);
+        }
 
-        let tabs = Tabs::new(tab_titles)
-            .select(self.selected_tab)
-            .divider("│")
-            .bg(Color::DarkGray);
-        tabs.render(inner_area[0], buf);
+        if let Some(search) = &mut self.bt_search {
+            let search_area = area.centered(Constraint::Percentage(90), Constraint::Percentage(90));
+            render_bt_search_view(
+                search,
+                search_area,
+                buf,
+                self.binary_units,
+                self.spinner_frame,
+                theme,
+            );
+        }
 
-        // Render tab content for the selected task
-        if let Some(real_idx) = self.selected_task_in_sorted()
-            && let Some(task) = self.tasks.get(real_idx)
-        {
-            match self.selected_tab {
-                0 => render_general_tab(task, inner_area[1], buf),
-                1 => render_transfer_tab(task, inner_area[1], buf),
-                2 => {
-                    self.tracker_inner_height = inner_area[1].height as usize;
-                    render_tracker_tab(
-                        task,
-                        inner_area[1],
-                        buf,
-                        self.tracker_scroll,
-                        self.tracker_count,
-                    );
-                }
-                3 => {
-                    self.peer_inner_height = inner_area[1].height as usize;
-                    render_peers_tab(task, inner_area[1], buf, self.peer_scroll, self.peer_count);
-                }
-                4 => {
-                    self.file_inner_height = inner_area[1].height as usize;
-                    render_files_tab(task, inner_area[1], buf, self.file_scroll, self.file_count);
-                }
-                _ => {}
-            }
+        if let Some(settings) = &mut self.server_settings {
+            let settings_area = area.centered(Constraint::Percentage(50), Constraint::Length(9));
+            render_server_settings(settings, settings_area, buf, theme);
+        }
+
+        if let Some(browser) = &mut self.folder_browser {
+            let browser_area =
+                area.centered(Constraint::Percentage(60), Constraint::Percentage(70));
+            render_folder_browser(browser, &self.destination, browser_area, buf, theme);
+        }
+
+        if let Some(picker) = &mut self.profile_picker {
+            let picker_area = area.centered(
+                Constraint::Percentage(50),
+                Constraint::Length(picker.profiles.len() as u16 + 2),
+            );
+            render_profile_picker(picker, &self.profile_name, picker_area, buf, theme);
+        }
+
+        if let Some(editor) = &mut self.settings_editor {
+            let editor_area = area.centered(
+                Constraint::Percentage(60),
+                Constraint::Length(SettingsField::ALL.len() as u16 + 4),
+            );
+            render_settings_editor(editor, &self.profile_name, editor_area, buf, theme);
+        }
+
+        if let Some(form) = &mut self.speed_limit {
+            let form_area = area.centered(
+                Constraint::Percentage(50),
+                Constraint::Length(SpeedLimitField::ALL.len() as u16 + 5),
+            );
+            render_speed_limit(form, form_area, buf, theme);
         }
 
         // File picker
@@ -296,30 +367,95 @@ impl Widget for &mut App {
             let picker_area = area.centered(Constraint::Percentage(70), Constraint::Percentage(80));
             Clear.render(picker_area, buf);
             explorer.widget().render_ref(picker_area, buf);
+
+            // Errors like an unreadable directory go on the last row inside the border
+            if let Some(message) = &self.picker_message {
+                let message_area = Rect {
+                    x: picker_area.x + 1,
+                    y: picker_area.y + picker_area.height.saturating_sub(2),
+                    width: picker_area.width.saturating_sub(2),
+                    height: 1,
+                };
+                Clear.render(message_area, buf);
+                Paragraph::new(format!(" ✗ {}", message))
+                    .style(Style::default().fg(theme.error))
+                    .render(message_area, buf);
+            }
         }
 
         // URL input field
-        if let Some(input) = &self.url_input {
+        if let Some(form) = &self.url_input {
+            let height = UrlField::ALL.len() as u16 + 2;
             let input_area = Rect {
                 x: area.x,
-                y: area.y + area.height - 3,
+                y: area.y + area.height - height,
                 width: area.width,
-                height: 3,
+                height,
             };
             Clear.render(input_area, buf);
 
+            // Live validation feedback, Enter is ignored while the URL is invalid
+            let url = form.value(UrlField::Url);
+            let validation = if url.trim().is_empty() {
+                Line::default()
+            } else {
+                match validate_url(url) {
+                    Some(kind) => Line::from(Span::styled(
+                        format!(" ✓ {} ", kind),
+                        Style::default().fg(theme.success),
+                    )),
+                    None => Line::from(Span::styled(
+                        " ✗ invalid URL ",
+                        Style::default().fg(theme.error),
+                    )),
+                }
+            };
+
             let input_block = Block::bordered()
-                .title(" Add URL (Enter to confirm · Esc to cancel) ")
+                .title(" Add URL (Tab for the optional fields · Enter to confirm · Esc to cancel) ")
+                .title_bottom(format!(" → {} ", self.destination))
+                .title(validation.right_aligned())
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::Yellow));
+                .border_style(Style::default().fg(theme.accent));
 
             let inner = input_block.inner(input_area);
             input_block.render(input_area, buf);
 
-            Paragraph::new(input.value()).render(inner, buf);
+            let rows =
+                Layout::vertical(vec![Constraint::Length(1); UrlField::ALL.len()]).split(inner);
+            for (i, field) in UrlField::ALL.iter().enumerate() {
+                let input = &form.inputs[i];
+                let value = match field {
+                    UrlField::Password | UrlField::UnzipPassword => {
+                        "*".repeat(input.value().chars().count())
+                    }
+                    _ => input.value().to_string(),
+                };
+                let label_style = if i == form.focused {
+                    Style::default().fg(theme.accent).bold()
+                } else {
+                    Style::default().fg(theme.label)
+                };
+                Paragraph::new(Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "{:<width$}",
+                            field.label(),
+                            width = URL_LABEL_WIDTH as usize
+                        ),
+                        label_style,
+                    ),
+                    Span::styled(value, Style::default().fg(theme.text)),
+                ]))
+                .render(rows[i], buf);
+            }
 
-            // Store cursor position: inner area start + cursor offset within input
-            self.url_input_cursor_pos = Some((inner.x + input.visual_cursor() as u16, inner.y));
+            // Store cursor position: start of the focused field + cursor offset within it
+            let focused = &form.inputs[form.focused];
+            self.url_input_cursor_pos = Some((
+                inner.x + URL_LABEL_WIDTH + focused.visual_cursor() as u16,
+                rows[form.focused].y,
+            ));
         } else {
             self.url_input_cursor_pos = None;
         }
@@ -334,10 +470,15 @@ impl Widget for &mut App {
             };
             Clear.render(input_area, buf);
 
+            let title = if self.filtering_files {
+                " Filter files (Enter to keep · Esc to clear) "
+            } else {
+                " Filter tasks (Enter to apply · Esc to cancel) "
+            };
             let input_block = Block::bordered()
-                .title(" Filter tasks (Enter to apply · Esc to cancel) ")
+                .title(title)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::Yellow));
+                .border_style(Style::default().fg(theme.accent));
 
             let inner = input_block.inner(input_area);
             input_block.render(input_area, buf);
@@ -351,16 +492,26 @@ impl Widget for &mut App {
 
         // Popup
         if let Some(popup) = &self.popup {
-            let popup_area = area.centered(Constraint::Percentage(60), Constraint::Percentage(60));
+            let popup_area = match popup.kind {
+                PopupKind::Details(_) => {
+                    area.centered(Constraint::Percentage(90), Constraint::Percentage(90))
+                }
+                _ => area.centered(Constraint::Percentage(60), Constraint::Percentage(60)),
+            };
             Clear.render(popup_area, buf);
 
-            let border_style = if popup.error {
-                Style::default().fg(Color::Red)
+            let error = popup.kind == PopupKind::Error;
+            let border_style = if error {
+                Style::default().fg(theme.error)
             } else {
-                Style::default().fg(Color::Yellow)
+                Style::default().fg(theme.accent)
             };
 
-            let title = if popup.error { " Error " } else { " Help " };
+            let title = match &popup.kind {
+                PopupKind::Help => " Help ".to_string(),
+                PopupKind::Error => " Error ".to_string(),
+                PopupKind::Details(name) => format!(" {} ", name),
+            };
 
             let lines: Vec<Line> = popup
                 .lines
@@ -368,10 +519,10 @@ impl Widget for &mut App {
                 .map(|l| {
                     Line::from(Span::styled(
                         l.clone(),
-                        if popup.error {
-                            Style::default().fg(Color::LightRed)
+                        if error {
+                            Style::default().fg(theme.error_text)
                         } else {
-                            Style::default().fg(Color::LightYellow)
+                            Style::default().fg(theme.popup_text)
                         },
                     ))
                 })
@@ -379,7 +530,11 @@ impl Widget for &mut App {
 
             let block = Block::bordered()
                 .title(title)
-                .title_bottom(" j / k to scroll · Esc to close ")
+                .title_bottom(if popup.kind == PopupKind::Help {
+                    " j / k to scroll · Esc to close "
+                } else {
+                    " j / k to scroll · y to copy · Esc to close "
+                })
                 .border_type(BorderType::Rounded)
                 .border_style(border_style);
 
@@ -391,16 +546,23 @@ impl Widget for &mut App {
             // Store visible height for scroll clamping
             self.popup_inner_height = chunks[0].height as usize;
 
+            // Long paths and URIs wrap onto several rows, count those so the end can be reached
+            let width = (chunks[0].width as usize).max(1);
+            self.popup_line_count = lines.iter().map(|l| l.width().div_ceil(width).max(1)).sum();
+
             block.render(popup_area, buf);
 
+            // Details keep their indentation, it's what groups the files, peers and trackers
             Paragraph::new(lines.clone())
                 .scroll((popup.scroll as u16, 0))
-                .wrap(Wrap { trim: true })
+                .wrap(Wrap {
+                    trim: !matches!(popup.kind, PopupKind::Details(_)),
+                })
                 .render(chunks[0], buf);
 
             let area_height = chunks[0].height as usize;
             let mut scrollbar_state =
-                ScrollbarState::new(popup.lines.len().saturating_sub(area_height))
+                ScrollbarState::new(self.popup_line_count.saturating_sub(area_height))
                     .position(popup.scroll);
             StatefulWidget::render(
                 Scrollbar::new(ScrollbarOrientation::VerticalRight)
@@ -412,6 +574,38 @@ impl Widget for &mut App {
             );
         }
 
+        // Typed delete confirmation, below the popup explaining it
+        if let Some(confirm) = &mut self.delete_confirm {
+            let input_area = Rect {
+                x: area.x,
+                y: area.y + area.height - 3,
+                width: area.width,
+                height: 3,
+            };
+            Clear.render(input_area, buf);
+
+            let status = if confirm.matches() {
+                Span::styled(" ✓ ", Style::default().fg(theme.success))
+            } else {
+                Span::default()
+            };
+            let input_block = Block::bordered()
+                .title(format!(
+                    " Type \"{}\" to confirm deletion ",
+                    confirm.expected
+                ))
+                .title(Line::from(status).right_aligned())
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.error));
+
+            let inner = input_block.inner(input_area);
+            input_block.render(input_area, buf);
+
+            Paragraph::new(confirm.input.value()).render(inner, buf);
+
+            confirm.cursor_pos = Some((inner.x + confirm.input.visual_cursor() as u16, inner.y));
+        }
+
         if let Some(notification) = &self.notification {
             let message = format!(" {} ", notification.message);
             let width = (message.chars().count() as u16 + 2).min(area.width.saturating_sub(2));
@@ -428,216 +622,1206 @@ impl Widget for &mut App {
 
             let block = Block::bordered()
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::Green));
+                .border_style(Style::default().fg(theme.success));
 
             let inner = block.inner(notif_area);
             block.render(notif_area, buf);
 
             Paragraph::new(message)
-                .style(Style::default().fg(Color::LightGreen))
+                .style(Style::default().fg(theme.notification_text))
                 .alignment(Alignment::Center)
                 .render(inner, buf);
         }
+
+        // Looked up after the overlays so the spinner is never animated on top of a popup
+        self.spinner_pos = self.in_flight.and_then(|_| {
+            (chunks[0].left()..chunks[0].right())
+                .map(|x| Position::new(x, chunks[0].y))
+                .find(|&pos| buf.cell(pos).is_some_and(|cell| cell.symbol() == spinner))
+        });
+    }
+}
+
+impl App {
+    /// The tabs with the details of the selected task, and the global statistics
+    fn render_info_panel(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let mut title_spans = vec![
+            Span::styled(" Info ", Style::default().bold()),
+            Span::styled(
+                format!("— {} ", self.tabs[self.selected_tab]),
+                Style::default().fg(theme.accent).bold(),
+            ),
+        ];
+        if self.selected_tab == 4 && !self.file_filter.is_empty() {
+            title_spans.push(Span::styled(
+                format!("[filter: {}] ", self.file_filter),
+                Style::default().fg(theme.filter),
+            ));
+        }
+        let info_block = Block::bordered()
+            .title(Line::from(title_spans))
+            .title_alignment(Alignment::Center)
+            .title_bottom(" Tab to switch panels ")
+            .border_type(BorderType::Rounded)
+            .border_style(match self.active_panel {
+                ActivePanel::Info => Style::default().fg(theme.accent),
+                _ => Style::default(),
+            });
+        info_block.render(area, buf);
+
+        let inner_area = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(area);
+
+        let tab_titles = self
+            .tabs
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                if i == self.selected_tab {
+                    Line::from(Span::styled(
+                        format!(" {} ", t),
+                        Style::default()
+                            .fg(theme.tab_selected_fg)
+                            .bg(theme.accent)
+                            .bold(),
+                    ))
+                } else {
+                    Line::from(Span::styled(
+                        format!(" {} ", t),
+                        Style::default().fg(theme.text),
+                    ))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let tabs = Tabs::new(tab_titles)
+            .select(self.selected_tab)
+            .divider("│")
+            .bg(theme.header_bg);
+        tabs.render(inner_area[0], buf);
+        self.tabs_area = inner_area[0];
+
+        // Render tab content, statistics are global and don't need a selected task
+        if self.selected_tab == 5 {
+            render_statistics_tab(
+                self.statistics.as_ref(),
+                &self.speed_history,
+                self.session_transferred,
+                inner_area[1],
+                buf,
+                self.binary_units,
+                theme,
+            );
+        } else if let Some(real_idx) = self.selected_task_in_sorted()
+            && let Some(task) = self.tasks.get(real_idx)
+        {
+            match self.selected_tab {
+                0 => render_general_tab(
+                    task,
+                    inner_area[1],
+                    buf,
+                    self.binary_units,
+                    self.use_local_time,
+                    theme,
+                ),
+                1 => render_transfer_tab(
+                    task,
+                    self.task_speed_history.get(&task.id),
+                    inner_area[1],
+                    buf,
+                    self.binary_units,
+                    theme,
+                ),
+                2 => {
+                    let elapsed = self
+                        .details_loaded_at
+                        .map_or(0, |loaded| loaded.elapsed().as_secs());
+                    self.tracker_max_scroll = render_tracker_tab(
+                        task,
+                        elapsed,
+                        inner_area[1],
+                        buf,
+                        self.tracker_scroll,
+                        theme,
+                    );
+                }
+                3 => {
+                    self.peer_inner_height = inner_area[1].height as usize;
+                    render_peers_tab(
+                        task,
+                        inner_area[1],
+                        buf,
+                        self.peer_scroll,
+                        self.max_peers,
+                        self.binary_units,
+                        theme,
+                    );
+                }
+                4 => {
+                    self.file_max_scroll = render_files_tab(
+                        task,
+                        &self.file_filter,
+                        inner_area[1],
+                        buf,
+                        self.file_scroll,
+                        theme,
+                    );
+                }
+                _ => {}
+            }
+        }
     }
 }
 
-fn render_general_tab(task: &Task, area: Rect, buf: &mut Buffer) {
+fn render_rss_view(
+    rss: &mut RssView,
+    area: Rect,
+    buf: &mut Buffer,
+    binary_units: bool,
+    theme: &Theme,
+) {
+    Clear.render(area, buf);
+    let chunks =
+        Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)]).split(area);
+
+    let focused = |is_focused: bool| {
+        if is_focused {
+            Style::default().fg(theme.accent)
+        } else {
+            Style::default()
+        }
+    };
+
+    let sites_block = Block::bordered()
+        .title(" RSS sites ")
+        .title_bottom(" Tab to switch · r to reload · Esc to close ")
+        .border_type(BorderType::Rounded)
+        .border_style(focused(!rss.items_focused));
+    let site_rows: Vec<Row> = rss
+        .sites
+        .iter()
+        .map(|site| Row::new(vec![Cell::from(site.title.clone())]).style(theme.text))
+        .collect();
+    let sites_table = Table::new(site_rows, [Constraint::Fill(1)])
+        .block(sites_block)
+        .row_highlight_style(theme.selection);
+    StatefulWidget::render(sites_table, chunks[0], buf, &mut rss.selected_site);
+
+    let items_block = Block::bordered()
+        .title(" Feed items ")
+        .title_bottom(" Enter to download ")
+        .border_type(BorderType::Rounded)
+        .border_style(focused(rss.items_focused));
+    let header = Row::new(vec![
+        Cell::from("Title").style(Style::default().fg(theme.accent).underlined()),
+        Cell::from("Size").style(Style::default().fg(theme.accent).underlined()),
+    ]);
+    let item_rows: Vec<Row> = rss
+        .items
+        .iter()
+        .map(|item| {
+            Row::new(vec![
+                Cell::from(item.title.clone()).style(Style::default().fg(theme.text)),
+                Cell::from(if item.size > 0 {
+                    format_bytes(item.size, binary_units)
+                } else {
+                    String::new()
+                })
+                .style(Style::default().fg(theme.accent)),
+            ])
+        })
+        .collect();
+    let items_table = Table::new(
+        item_rows,
+        [Constraint::Percentage(85), Constraint::Percentage(15)],
+    )
+    .block(items_block)
+    .header(header)
+    .row_highlight_style(theme.selection)
+    .column_spacing(1);
+    StatefulWidget::render(items_table, chunks[1], buf, &mut rss.selected_item);
+}
+
+fn render_bt_search_view(
+    search: &mut BtSearchView,
+    area: Rect,
+    buf: &mut Buffer,
+    binary_units: bool,
+    spinner_frame: usize,
+    theme: &Theme,
+) {
+    Clear.render(area, buf);
+    let chunks = Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).split(area);
+
+    let focused = |is_focused: bool| {
+        if is_focused {
+            Style::default().fg(theme.accent)
+        } else {
+            Style::default()
+        }
+    };
+
+    let input_block = Block::bordered()
+        .title(" BT search (Enter to search · Tab to switch · Esc to close) ")
+        .border_type(BorderType::Rounded)
+        .border_style(focused(search.input_focused));
+    let inner = input_block.inner(chunks[0]);
+    input_block.render(chunks[0], buf);
+    Paragraph::new(search.input.value()).render(inner, buf);
+    search.cursor_pos = Some((inner.x + search.input.visual_cursor() as u16, inner.y));
+
+    let status = match (&search.task_id, search.finished) {
+        (None, _) => String::new(),
+        (Some(_), false) => format!(
+            " {} Searching… {} results ",
+            SPINNER_FRAMES[spinner_frame % SPINNER_FRAMES.len()],
+            search.results.len()
+        ),
+        (Some(_), true) => format!(" {} results ", search.results.len()),
+    };
+    let results_block = Block::bordered()
+        .title(" Results ")
+        .title(Line::from(Span::styled(status, Style::default().fg(theme.muted))).right_aligned())
+        .title_bottom(" Enter to download ")
+        .border_type(BorderType::Rounded)
+        .border_style(focused(!search.input_focused));
+
+    let header = Row::new(
+        ["Title", "Size", "Seeds", "Peers", "Engine"]
+            .map(|title| Cell::from(title).style(Style::default().fg(theme.accent).underlined())),
+    );
+    let rows: Vec<Row> = search
+        .results
+        .iter()
+        .map(|result| {
+            Row::new(vec![
+                Cell::from(result.title.clone()).style(Style::default().fg(theme.text)),
+                Cell::from(format_bytes(result.size, binary_units))
+                    .style(Style::default().fg(theme.accent)),
+                Cell::from(result.seeds.to_string()).style(Style::default().fg(theme.success)),
+                Cell::from(result.peers.to_string()).style(Style::default().fg(theme.info)),
+                Cell::from(result.module_title.clone()).style(Style::default().fg(theme.muted)),
+            ])
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(6),
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Fill(1),
+        ],
+    )
+    .block(results_block)
+    .header(header)
+    .row_highlight_style(theme.selection)
+    .column_spacing(1);
+    StatefulWidget::render(table, chunks[1], buf, &mut search.selected);
+}
+
+fn render_folder_browser(
+    browser: &mut FolderBrowser,
+    destination: &str,
+    area: Rect,
+    buf: &mut Buffer,
+    theme: &Theme,
+) {
+    Clear.render(area, buf);
+    let block = Block::bordered()
+        .title(format!(
+            " Destination · {} ",
+            browser.path.as_deref().unwrap_or("shared folders")
+        ))
+        .title(
+            Line::from(Span::styled(
+                format!(" current: {} ", destination),
+                Style::default().fg(theme.muted),
+            ))
+            .right_aligned(),
+        )
+        .title_bottom(" Enter to open · h to go up · Space to choose · Esc to cancel ")
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.accent));
+
+    let rows: Vec<Row> = browser
+        .folders
+        .iter()
+        .map(|folder| {
+            Row::new(vec![
+                Cell::from(format!("{}/", folder.name)).style(Style::default().fg(theme.text)),
+            ])
+        })
+        .collect();
+    let table = Table::new(rows, [Constraint::Fill(1)])
+        .block(block)
+        .row_highlight_style(theme.selection);
+    StatefulWidget::render(table, area, buf, &mut browser.selected);
+}
+
+fn render_profile_picker(
+    picker: &mut ProfilePicker,
+    current: &str,
+    area: Rect,
+    buf: &mut Buffer,
+    theme: &Theme,
+) {
+    Clear.render(area, buf);
+    let block = Block::bordered()
+        .title(" Server profiles ")
+        .title_bottom(" Enter to connect · Esc to close ")
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.accent));
+
+    let rows: Vec<Row> = picker
+        .profiles
+        .iter()
+        .map(|(name, url)| {
+            let marker = if name == current { "●" } else { " " };
+            Row::new(vec![
+                Cell::from(marker).style(Style::default().fg(theme.success)),
+                Cell::from(name.clone()).style(Style::default().fg(theme.text)),
+                Cell::from(url.clone()).style(Style::default().fg(theme.muted)),
+            ])
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(1),
+            Constraint::Percentage(35),
+            Constraint::Fill(1),
+        ],
+    )
+    .block(block)
+    .row_highlight_style(theme.selection);
+    StatefulWidget::render(table, area, buf, &mut picker.selected);
+}
+
+/// Width of the labels in front of the settings editor fields
+const SETTINGS_LABEL_WIDTH: u16 = 18;
+const URL_LABEL_WIDTH: u16 = 16;
+
+fn render_settings_editor(
+    editor: &mut SettingsEditor,
+    profile_name: &str,
+    area: Rect,
+    buf: &mut Buffer,
+    theme: &Theme,
+) {
+    Clear.render(area, buf);
+    let block = Block::bordered()
+        .title(format!(" Settings · {} ", profile_name))
+        .title_bottom(" Tab to move · Enter to save and reconnect · Esc to cancel ")
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.accent));
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    let mut constraints = vec![Constraint::Length(1); SettingsField::ALL.len()];
+    constraints.push(Constraint::Length(1)); // spacer
+    constraints.push(Constraint::Length(1)); // error
+    let rows = Layout::vertical(constraints).split(inner);
+
+    editor.cursor_pos = None;
+    for (i, field) in SettingsField::ALL.iter().enumerate() {
+        let input = &editor.inputs[i];
+        let value = if *field == SettingsField::Password {
+            "*".repeat(input.value().chars().count())
+        } else {
+            input.value().to_string()
+        };
+        let label_style = if i == editor.focused {
+            Style::default().fg(theme.accent).bold()
+        } else {
+            Style::default().fg(theme.label)
+        };
+        Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!(
+                    "{:<width$}",
+                    field.label(),
+                    width = SETTINGS_LABEL_WIDTH as usize
+                ),
+                label_style,
+            ),
+            Span::styled(value, Style::default().fg(theme.text)),
+        ]))
+        .render(rows[i], buf);
+
+        if i == editor.focused {
+            editor.cursor_pos = Some((
+                rows[i].x + SETTINGS_LABEL_WIDTH + input.visual_cursor() as u16,
+                rows[i].y,
+            ));
+        }
+    }
+
+    if let Some(error) = &editor.error {
+        Paragraph::new(format!(" ✗ {}", error))
+            .style(Style::default().fg(theme.error))
+            .render(rows[SettingsField::ALL.len() + 1], buf);
+    }
+}
+
+fn render_speed_limit(form: &mut SpeedLimitForm, area: Rect, buf: &mut Buffer, theme: &Theme) {
+    Clear.render(area, buf);
+    let block = Block::bordered()
+        .title(format!(" Speed limit · {} ", form.title))
+        .title_bottom(" Tab to move · Enter to save · Esc to cancel ")
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.accent));
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    let mut constraints = vec![Constraint::Length(1); SpeedLimitField::ALL.len()];
+    constraints.push(Constraint::Length(1)); // spacer
+    constraints.push(Constraint::Length(2)); // error, the unsupported one wraps
+    let rows = Layout::vertical(constraints).split(inner);
+
+    form.cursor_pos = None;
+    for (i, field) in SpeedLimitField::ALL.iter().enumerate() {
+        let input = &form.inputs[i];
+        let label_style = if i == form.focused {
+            Style::default().fg(theme.accent).bold()
+        } else {
+            Style::default().fg(theme.label)
+        };
+        Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!(
+                    "{:<width$}",
+                    field.label(),
+                    width = SETTINGS_LABEL_WIDTH as usize
+                ),
+                label_style,
+            ),
+            Span::styled(input.value(), Style::default().fg(theme.text)),
+        ]))
+        .render(rows[i], buf);
+
+        if i == form.focused {
+            form.cursor_pos = Some((
+                rows[i].x + SETTINGS_LABEL_WIDTH + input.visual_cursor() as u16,
+                rows[i].y,
+            ));
+        }
+    }
+
+    if let Some(error) = &form.error {
+        Paragraph::new(format!(" ✗ {}", error))
+            .style(Style::default().fg(theme.error))
+            .wrap(Wrap { trim: true })
+            .render(rows[SpeedLimitField::ALL.len() + 1], buf);
+    }
+}
+
+fn render_server_settings(
+    settings: &mut ServerSettingsView,
+    area: Rect,
+    buf: &mut Buffer,
+    theme: &Theme,
+) {
+    Clear.render(area, buf);
+    let block = Block::bordered()
+        .title(" Server settings ")
+        .title_bottom(" Space to toggle · Esc to close ")
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.accent));
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    let chunks = Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).split(inner);
+    Paragraph::new(Line::from(vec![
+        Span::styled("Default destination: ", Style::default().fg(theme.label)),
+        Span::styled(
+            settings.config.default_destination.clone(),
+            Style::default().fg(theme.text),
+        ),
+    ]))
+    .render(chunks[0], buf);
+
+    let rows: Vec<Row> = ServerSetting::ALL
+        .iter()
+        .map(|setting| {
+            let (state, color) = if settings.is_enabled(*setting) {
+                ("on", theme.success)
+            } else {
+                ("off", theme.muted)
+            };
+            Row::new(vec![
+                Cell::from(setting.label()).style(Style::default().fg(theme.text)),
+                Cell::from(state).style(Style::default().fg(color)),
+            ])
+        })
+        .collect();
+    let table = Table::new(rows, [Constraint::Fill(1), Constraint::Length(4)])
+        .row_highlight_style(theme.selection);
+    StatefulWidget::render(table, chunks[1], buf, &mut settings.selected);
+}
+
+/// Name cell cut to `width` with the characters the fuzzy filter matched in the filter color
+fn highlighted_name(title: &str, matches: &[usize], width: usize, theme: &Theme) -> Cell<'static> {
+    let name = truncate(title, width);
+    // The ellipsis of a truncated name isn't part of the title
+    let shown = if name == title {
+        usize::MAX
+    } else {
+        name.chars().count() - 1
+    };
+    let spans: Vec<Span> = name
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if i < shown && matches.contains(&i) {
+                Span::styled(c.to_string(), Style::default().fg(theme.filter).bold())
+            } else {
+                Span::raw(c.to_string())
+            }
+        })
+        .collect();
+    Cell::from(Line::from(spans))
+}
+
+/// Build the table cell for a single column of a task row, names are cut to `name_width`
+fn task_cell(
+    column: ColumnId,
+    task: &Task,
+    name_width: usize,
+    binary_units: bool,
+    use_local_time: bool,
+    theme: &Theme,
+) -> Cell<'static> {
+    let transfer = task.additional.as_ref().and_then(|a| a.transfer.as_ref());
+
+    match column {
+        ColumnId::Name => Cell::from(truncate(&task.title, name_width)),
+        ColumnId::Size => Cell::from(format_bytes(task.size, binary_units)),
+        ColumnId::Downloaded => Cell::from(
+            transfer
+                .map(|t| format_bytes(t.size_downloaded, binary_units))
+                .unwrap_or_else(|| UNKNOWN.to_string()),
+        ),
+        ColumnId::Uploaded => Cell::from(
+            transfer
+                .map(|t| format_bytes(t.size_uploaded, binary_units))
+                .unwrap_or_else(|| UNKNOWN.to_string()),
+        ),
+        ColumnId::Progress if transfer.is_none() => Cell::from(UNKNOWN),
+        ColumnId::Progress => Cell::from(Line::from(render_progress_bar(
+            task.calculate_progress(),
+            8,
+            theme,
+        ))),
+        ColumnId::UploadSpeed => Cell::from(
+            transfer
+                .map(|t| format_speed(t.speed_upload, binary_units))
+                .unwrap_or_else(|| UNKNOWN.to_string()),
+        ),
+        ColumnId::DownloadSpeed => Cell::from(
+            transfer
+                .map(|t| format_speed(t.speed_download, binary_units))
+                .unwrap_or_else(|| UNKNOWN.to_string()),
+        ),
+        ColumnId::Ratio => Cell::from(format_ratio(task)),
+        ColumnId::Status => {
+            let status_style = match task.status {
+                TaskStatus::Downloading => Style::default().fg(theme.success),
+                TaskStatus::Seeding => Style::default().fg(theme.info),
+                TaskStatus::Waiting => Style::default().fg(theme.warning),
+                TaskStatus::Paused => Style::default().fg(theme.muted),
+                TaskStatus::Finishing => Style::default().fg(theme.finishing),
+                TaskStatus::Finished => Style::default().fg(theme.muted),
+                TaskStatus::HashChecking => Style::default().fg(theme.warning),
+                TaskStatus::Error => Style::default().fg(theme.error),
+                _ => Style::default().fg(theme.text),
+            };
+            Cell::from(status_label(task)).style(status_style)
+        }
+        ColumnId::Eta => Cell::from(
+            transfer
+                .map(|t| {
+                    format_eta(
+                        task.size.saturating_sub(t.size_downloaded),
+                        t.speed_download,
+                    )
+                })
+                .unwrap_or_else(|| UNKNOWN.to_string()),
+        ),
+        ColumnId::Added => Cell::from(
+            task.additional
+                .as_ref()
+                .and_then(|a| a.detail.as_ref())
+                .map(|d| {
+                    let now = Utc::now();
+                    if now.signed_duration_since(d.created_time).num_days() < 1 {
+                        format_relative(d.created_time, now, use_local_time)
+                    } else {
+                        // The full timestamp doesn't fit the column
+                        format_date(d.created_time, use_local_time)
+                    }
+                })
+                .unwrap_or_else(|| UNKNOWN.to_string()),
+        ),
+    }
+}
+
+/// Auto-refresh as shown in the status bar, with the seconds between refreshes
+enum RefreshState {
+    Off,
+    Paused(u64),
+    Idle(u64), // backed off because no task is transferring
+    Every(u64),
+}
+
+fn render_status_bar(
+    tasks: &[Task],
+    area: Rect,
+    buf: &mut Buffer,
+    binary_units: bool,
+    refresh: RefreshState,
+    reconnect_keys: Option<&str>, // set while disconnected
+    theme: &Theme,
+) {
+    let (mut downloading, mut seeding, mut waiting, mut paused, mut finished, mut errored) =
+        (0, 0, 0, 0, 0, 0);
+    let (mut speed_down, mut speed_up) = (0u64, 0u64);
+
+    for task in tasks {
+        match task.status {
+            TaskStatus::Downloading => downloading += 1,
+            TaskStatus::Seeding => seeding += 1,
+            TaskStatus::Waiting => waiting += 1,
+            TaskStatus::Paused => paused += 1,
+            TaskStatus::Finished => finished += 1,
+            _ if is_error_status(&task.status) => errored += 1,
+            _ => {}
+        }
+        if let Some(t) = task.additional.as_ref().and_then(|a| a.transfer.as_ref()) {
+            speed_down += t.speed_download;
+            speed_up += t.speed_upload;
+        }
+    }
+
+    let counts: Vec<String> = [
+        (downloading, "downloading"),
+        (seeding, "seeding"),
+        (waiting, "waiting"),
+        (paused, "paused"),
+        (finished, "finished"),
+        (errored, "error"),
+    ]
+    .iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, label)| format!("{} {}", count, label))
+    .collect();
+
+    let mut spans = vec![Span::styled(
+        format!(" {} tasks ", tasks.len()),
+        Style::default().fg(theme.accent).bold(),
+    )];
+    if !counts.is_empty() {
+        spans.push(Span::styled(
+            format!("· {} ", counts.join(", ")),
+            Style::default().fg(theme.header_fg),
+        ));
+    }
+    spans.push(Span::styled(
+        format!(
+            "· ↓ {} ↑ {} ",
+            format_speed_or_zero(speed_down, binary_units),
+            format_speed_or_zero(speed_up, binary_units)
+        ),
+        Style::default().fg(theme.success),
+    ));
+    spans.push(match refresh {
+        RefreshState::Off => Span::styled("· ⟳ off ", Style::default().fg(theme.muted)),
+        RefreshState::Paused(secs) => Span::styled(
+            format!("· ⟳ {}s paused ", secs),
+            Style::default().fg(theme.warning),
+        ),
+        RefreshState::Idle(secs) => Span::styled(
+            format!("· ⟳ {}s idle ", secs),
+            Style::default().fg(theme.muted),
+        ),
+        RefreshState::Every(secs) => Span::styled(
+            format!("· ⟳ {}s ", secs),
+            Style::default().fg(theme.header_fg),
+        ),
+    });
+    match reconnect_keys {
+        Some("") => spans.push(Span::styled(
+            "· disconnected ",
+            Style::default().fg(theme.error).bold(),
+        )),
+        Some(keys) => spans.push(Span::styled(
+            format!("· disconnected, {} to reconnect ", keys),
+            Style::default().fg(theme.error).bold(),
+        )),
+        None => {}
+    }
+
+    Paragraph::new(Line::from(spans))
+        .style(Style::default().bg(theme.header_bg))
+        .render(area, buf);
+}
+
+fn format_speed_or_zero(bytes_per_sec: u64, binary_units: bool) -> String {
+    if bytes_per_sec == 0 {
+        "0 B/s".to_string()
+    } else {
+        format_speed(bytes_per_sec, binary_units)
+    }
+}
+
+fn render_statistics_tab(
+    statistics: Option<&Statistics>,
+    history: &VecDeque<(u64, u64)>,
+    session: (u64, u64), // bytes downloaded / uploaded since dstui started
+    area: Rect,
+    buf: &mut Buffer,
+    binary_units: bool,
+    theme: &Theme,
+) {
+    let Some(statistics) = statistics else {
+        Paragraph::new("Statistics are not available")
+            .style(Style::default().fg(theme.muted))
+            .render(area, buf);
+        return;
+    };
+
+    let chunks = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+    ])
+    .split(area);
+
+    let speed_line = |label: &'static str, down: u64, up: u64| {
+        Line::from(vec![
+            Span::styled(label, Style::default().fg(theme.label)),
+            Span::styled(
+                format!("↓ {:<12}", format_speed_or_zero(down, binary_units)),
+                Style::default().fg(theme.success),
+            ),
+            Span::styled(
+                format!("↑ {}", format_speed_or_zero(up, binary_units)),
+                Style::default().fg(theme.info),
+            ),
+        ])
+    };
+    Paragraph::new(vec![
+        speed_line(
+            "BitTorrent / HTTP: ",
+            statistics.speed_download,
+            statistics.speed_upload,
+        ),
+        speed_line(
+            "eMule:             ",
+            statistics.emule_speed_download,
+            statistics.emule_speed_upload,
+        ),
+        speed_line(
+            "Total:             ",
+            statistics.speed_download + statistics.emule_speed_download,
+            statistics.speed_upload + statistics.emule_speed_upload,
+        ),
+        Line::from(vec![
+            Span::styled("This session:      ", Style::default().fg(theme.label)),
+            Span::styled(
+                format!("↓ {:<12}", format_bytes(session.0, binary_units)),
+                Style::default().fg(theme.success),
+            ),
+            Span::styled(
+                format!("↑ {}", format_bytes(session.1, binary_units)),
+                Style::default().fg(theme.info),
+            ),
+        ]),
+    ])
+    .render(chunks[0], buf);
+
+    // Newest sample on the right, as many samples as fit the width
+    let visible = history.len().min(area.width.saturating_sub(2) as usize);
+    let recent = history.iter().skip(history.len() - visible);
+    let downloads: Vec<u64> = recent.clone().map(|(down, _)| *down).collect();
+    let uploads: Vec<u64> = recent.map(|(_, up)| *up).collect();
+
+    let graph = |title: String, data: &[u64], color| {
+        Sparkline::default()
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .border_type(BorderType::Rounded),
+            )
+            .data(data)
+            .style(Style::default().fg(color))
+    };
+    let peak = |data: &[u64]| data.iter().copied().max().unwrap_or_default();
+    graph(
+        format!(
+            " Download · peak {} ",
+            format_speed_or_zero(peak(&downloads), binary_units)
+        ),
+        &downloads,
+        theme.success,
+    )
+    .render(chunks[1], buf);
+    graph(
+        format!(
+            " Upload · peak {} ",
+            format_speed_or_zero(peak(&uploads), binary_units)
+        ),
+        &uploads,
+        theme.info,
+    )
+    .render(chunks[2], buf);
+}
+
+/// Absolute time, followed by how long ago that was for events of the last day
+fn timestamp_with_age(time: DateTime<Utc>, local: bool) -> String {
+    let absolute = format_timestamp(time, local);
+    let relative = format_relative(time, Utc::now(), local);
+    if relative == absolute {
+        absolute
+    } else {
+        format!("{} ({})", absolute, relative)
+    }
+}
+
+fn render_general_tab(
+    task: &Task,
+    area: Rect,
+    buf: &mut Buffer,
+    binary_units: bool,
+    use_local_time: bool,
+    theme: &Theme,
+) {
     let destination = task
         .additional
         .as_ref()
         .and_then(|a| a.detail.as_ref())
         .map(|d| d.destination.clone())
-        .unwrap_or_else(|| "N/A".to_string());
+        .unwrap_or_else(|| UNKNOWN.to_string());
 
-    let created_time = task
+    let (created_time, started_time, completed_time) = task
         .additional
         .as_ref()
         .and_then(|a| a.detail.as_ref())
-        .map(|d| d.created_time.to_string())
-        .unwrap_or_else(|| "N/A".to_string());
+        .map(|d| {
+            (
+                timestamp_with_age(d.created_time, use_local_time),
+                timestamp_with_age(d.started_time, use_local_time),
+                timestamp_with_age(d.completed_time, use_local_time),
+            )
+        })
+        .unwrap_or_else(|| {
+            (
+                UNKNOWN.to_string(),
+                UNKNOWN.to_string(),
+                UNKNOWN.to_string(),
+            )
+        });
 
-    let text = vec![
+    let uri = task
+        .additional
+        .as_ref()
+        .and_then(|a| a.detail.as_ref())
+        .map(|d| d.uri.trim())
+        .filter(|uri| !uri.is_empty());
+
+    let mut text = vec![
         Line::from(vec![
-            Span::styled("Title:       ", Style::default().fg(Color::LightCyan)),
-            Span::styled(task.title.clone(), Style::default().fg(Color::White)),
+            Span::styled("Title:       ", Style::default().fg(theme.label)),
+            Span::styled(task.title.clone(), Style::default().fg(theme.text)),
         ]),
         Line::from(vec![
-            Span::styled("ID:          ", Style::default().fg(Color::LightCyan)),
-            Span::styled(task.id.clone(), Style::default().fg(Color::White)),
+            Span::styled("ID:          ", Style::default().fg(theme.label)),
+            Span::styled(task.id.clone(), Style::default().fg(theme.text)),
         ]),
         Line::from(vec![
-            Span::styled("Destination: ", Style::default().fg(Color::LightCyan)),
-            Span::styled(destination, Style::default().fg(Color::White)),
+            Span::styled("Destination: ", Style::default().fg(theme.label)),
+            Span::styled(destination, Style::default().fg(theme.text)),
         ]),
         Line::from(vec![
-            Span::styled("Created:     ", Style::default().fg(Color::LightCyan)),
-            Span::styled(created_time, Style::default().fg(Color::White)),
+            Span::styled("Created:     ", Style::default().fg(theme.label)),
+            Span::styled(created_time, Style::default().fg(theme.text)),
         ]),
         Line::from(vec![
-            Span::styled("User:        ", Style::default().fg(Color::LightCyan)),
-            Span::styled(task.username.clone(), Style::default().fg(Color::White)),
+            Span::styled("Started:     ", Style::default().fg(theme.label)),
+            Span::styled(started_time, Style::default().fg(theme.text)),
         ]),
         Line::from(vec![
-            Span::styled("Size:        ", Style::default().fg(Color::LightCyan)),
-            Span::styled(task.calculate_size(), Style::default().fg(Color::White)),
+            Span::styled("Completed:   ", Style::default().fg(theme.label)),
+            Span::styled(completed_time, Style::default().fg(theme.text)),
         ]),
         Line::from(vec![
-            Span::styled("Status:      ", Style::default().fg(Color::LightCyan)),
-            Span::styled(
-                format!("{:?}", task.status),
-                Style::default().fg(Color::Yellow),
-            ),
+            Span::styled("User:        ", Style::default().fg(theme.label)),
+            Span::styled(task.username.clone(), Style::default().fg(theme.text)),
         ]),
         Line::from(vec![
-            Span::styled("ETA:         ", Style::default().fg(Color::LightCyan)),
+            Span::styled("Size:        ", Style::default().fg(theme.label)),
             Span::styled(
-                task.calculate_time_left(),
-                Style::default().fg(Color::White),
+                format_bytes(task.size, binary_units),
+                Style::default().fg(theme.text),
             ),
         ]),
         Line::from(vec![
-            Span::styled("Ratio:       ", Style::default().fg(Color::LightCyan)),
-            Span::styled(
-                format!("{:.2}", task.calculate_ratio()),
-                Style::default().fg(Color::White),
-            ),
+            Span::styled("Status:      ", Style::default().fg(theme.label)),
+            Span::styled(status_label(task), Style::default().fg(theme.warning)),
+        ]),
+        Line::from(vec![
+            Span::styled("ETA:         ", Style::default().fg(theme.label)),
+            Span::styled(task.calculate_time_left(), Style::default().fg(theme.text)),
+        ]),
+        Line::from(vec![
+            Span::styled("Ratio:       ", Style::default().fg(theme.label)),
+            Span::styled(format_ratio(task), Style::default().fg(theme.text)),
         ]),
     ];
-    Paragraph::new(text).render(area, buf);
+
+    // Magnet links run to hundreds of characters, show what's in them instead. `y` still
+    // copies the whole link.
+    match uri.map(|uri| (uri, parse_magnet(uri))) {
+        Some((_, Some(magnet))) => {
+            let field = |label: &'static str, value: String| {
+                Line::from(vec![
+                    Span::styled(label, Style::default().fg(theme.label)),
+                    Span::styled(value, Style::default().fg(theme.text)),
+                ])
+            };
+            let na = || "N/A".to_string();
+            text.push(field("Magnet name: ", magnet.name.unwrap_or_else(na)));
+            text.push(field("Info hash:   ", magnet.info_hash.unwrap_or_else(na)));
+            text.push(field("Trackers:    ", magnet.trackers.to_string()));
+        }
+        Some((uri, None)) => text.push(Line::from(vec![
+            Span::styled("URI:         ", Style::default().fg(theme.label)),
+            Span::styled(uri.to_string(), Style::default().fg(theme.text)),
+        ])),
+        None => {}
+    }
+
+    Paragraph::new(text)
+        .wrap(Wrap { trim: false })
+        .render(area, buf);
 }
 
-fn render_transfer_tab(task: &Task, area: Rect, buf: &mut Buffer) {
+fn render_transfer_tab(
+    task: &Task,
+    history: Option<&VecDeque<u64>>,
+    area: Rect,
+    buf: &mut Buffer,
+    binary_units: bool,
+    theme: &Theme,
+) {
     let (downloaded, uploaded, speed_up, speed_down) = task
         .additional
         .as_ref()
         .and_then(|a| a.transfer.as_ref())
         .map(|t| {
             (
-                format!("{:.1} MB", t.size_downloaded as f64 / 1_048_576.0),
-                format!("{:.1} MB", t.size_uploaded as f64 / 1_048_576.0),
-                format_speed(t.speed_upload),
-                format_speed(t.speed_download),
+                format_bytes(t.size_downloaded, binary_units),
+                format_bytes(t.size_uploaded, binary_units),
+                format_speed(t.speed_upload, binary_units),
+                format_speed(t.speed_download, binary_units),
             )
         })
-        .unwrap_or_else(|| ("N/A".into(), "N/A".into(), "N/A".into(), "N/A".into()));
+        .unwrap_or_else(|| {
+            (
+                UNKNOWN.into(),
+                UNKNOWN.into(),
+                UNKNOWN.into(),
+                UNKNOWN.into(),
+            )
+        });
 
-    let progress = task.calculate_progress();
+    let progress = if has_transfer(task) {
+        render_progress_bar(task.calculate_progress(), 20, theme)
+    } else {
+        Span::styled(UNKNOWN, Style::default().fg(theme.text))
+    };
 
     let text = vec![
         Line::from(vec![
-            Span::styled("Downloaded:  ", Style::default().fg(Color::LightCyan)),
-            Span::styled(downloaded, Style::default().fg(Color::White)),
+            Span::styled("Downloaded:  ", Style::default().fg(theme.label)),
+            Span::styled(downloaded, Style::default().fg(theme.text)),
         ]),
         Line::from(vec![
-            Span::styled("Uploaded:    ", Style::default().fg(Color::LightCyan)),
-            Span::styled(uploaded, Style::default().fg(Color::White)),
+            Span::styled("Uploaded:    ", Style::default().fg(theme.label)),
+            Span::styled(uploaded, Style::default().fg(theme.text)),
         ]),
         Line::from(vec![
-            Span::styled("Up Speed:    ", Style::default().fg(Color::LightCyan)),
-            Span::styled(speed_up, Style::default().fg(Color::Green)),
+            Span::styled("Up Speed:    ", Style::default().fg(theme.label)),
+            Span::styled(speed_up, Style::default().fg(theme.success)),
         ]),
         Line::from(vec![
-            Span::styled("Down Speed:  ", Style::default().fg(Color::LightCyan)),
-            Span::styled(speed_down, Style::default().fg(Color::Green)),
+            Span::styled("Down Speed:  ", Style::default().fg(theme.label)),
+            Span::styled(speed_down, Style::default().fg(theme.success)),
         ]),
         Line::from(vec![
-            Span::styled("Progress:    ", Style::default().fg(Color::LightCyan)),
-            render_progress_bar(progress, 20),
+            Span::styled("Progress:    ", Style::default().fg(theme.label)),
+            progress,
         ]),
         Line::from(vec![
-            Span::styled("Ratio:       ", Style::default().fg(Color::LightCyan)),
-            Span::styled(
-                format!("{:.2}", task.calculate_ratio()),
-                Style::default().fg(Color::White),
-            ),
+            Span::styled("Ratio:       ", Style::default().fg(theme.label)),
+            Span::styled(format_ratio(task), Style::default().fg(theme.text)),
         ]),
         Line::from(vec![
-            Span::styled("ETA:         ", Style::default().fg(Color::LightCyan)),
-            Span::styled(
-                task.calculate_time_left(),
-                Style::default().fg(Color::White),
-            ),
+            Span::styled("ETA:         ", Style::default().fg(theme.label)),
+            Span::styled(task.calculate_time_left(), Style::default().fg(theme.text)),
         ]),
     ];
-    Paragraph::new(text).render(area, buf);
+    let chunks =
+        Layout::vertical([Constraint::Length(text.len() as u16), Constraint::Fill(1)]).split(area);
+    let graph_area = chunks[1];
+    Paragraph::new(text)
+        .wrap(Wrap { trim: false })
+        .render(chunks[0], buf);
+
+    // Only worth drawing once there is room for the border and a couple of bars
+    if graph_area.height < 4 {
+        return;
+    }
+    let samples = history.map(|h| h.len()).unwrap_or_default();
+    let visible = samples.min(graph_area.width.saturating_sub(2) as usize);
+    let downloads: Vec<u64> = history
+        .map(|h| h.iter().skip(samples - visible).copied().collect())
+        .unwrap_or_default();
+    let peak = downloads.iter().copied().max().unwrap_or_default();
+    Sparkline::default()
+        .block(
+            Block::bordered()
+                .title(format!(
+                    " Download · peak {} ",
+                    format_speed_or_zero(peak, binary_units)
+                ))
+                .border_type(BorderType::Rounded),
+        )
+        .data(&downloads)
+        .style(Style::default().fg(theme.success))
+        .render(graph_area, buf);
 }
 
-fn render_tracker_tab(task: &Task, area: Rect, buf: &mut Buffer, scroll: usize, count: usize) {
-    let chunks = Layout::horizontal([Constraint::Min(0), Constraint::Length(1)]).split(area);
-
-    let rows: Vec<Row> = task
-        .additional
-        .as_ref()
-        .and_then(|a| a.tracker.as_ref())
+/// Returns the largest useful scroll offset, long URLs wrap so rows can be taller than one line
+/// `elapsed` is the number of seconds since the trackers were fetched, the time to the next
+/// announce counts down by it between refreshes
+fn render_tracker_tab(
+    task: &Task,
+    elapsed: u64,
+    area: Rect,
+    buf: &mut Buffer,
+    scroll: usize,
+    theme: &Theme,
+) -> usize {
+    let trackers = task.additional.as_ref().and_then(|a| a.tracker.as_ref());
+    if trackers.is_none_or(|trackers| trackers.is_empty()) {
+        render_empty_tab(
+            trackers.is_some(),
+            "No trackers for this task",
+            area,
+            buf,
+            theme,
+        );
+        return 0;
+    }
+    let rows: Vec<(String, Cell)> = trackers
         .map(|trackers| {
             trackers
                 .iter()
                 .map(|t| {
-                    Row::new(vec![
-                        Cell::from(t.url.clone()).style(Style::default().fg(Color::White)),
-                        Cell::from(format!("{:?}", t.status))
-                            .style(Style::default().fg(Color::Yellow)),
-                    ])
+                    let next_announce = (t.update_timer as u64).saturating_sub(elapsed);
+                    (
+                        t.url.clone(),
+                        Cell::from(format!(
+                            "{} · {}",
+                            t.status,
+                            convert_time_left(next_announce as i64)
+                        ))
+                        .style(Style::default().fg(theme.accent)),
+                    )
                 })
                 .collect()
         })
         .unwrap_or_default();
 
-    let header = Row::new(vec![
-        Cell::from("URL").style(Style::default().fg(Color::Yellow).underlined()),
-        Cell::from("Status").style(Style::default().fg(Color::Yellow).underlined()),
-    ]);
-
-    let widths = [Constraint::Percentage(80), Constraint::Percentage(20)];
-
-    let area_height = chunks[0].height as usize;
-
-    let table = Table::new(rows, widths).header(header).column_spacing(1);
-
-    let mut state = TableState::default().with_offset(scroll);
-    StatefulWidget::render(table, chunks[0], buf, &mut state);
-
-    let mut scrollbar_state =
-        ScrollbarState::new(count.saturating_sub(area_height)).position(scroll);
-    StatefulWidget::render(
-        Scrollbar::new(ScrollbarOrientation::VerticalRight)
-            .begin_symbol(Some("↑"))
-            .end_symbol(Some("↓")),
-        chunks[1],
+    render_wrapping_table(
+        ["URL", "Status · next announce"],
+        rows,
+        [Constraint::Percentage(70), Constraint::Percentage(30)],
+        area,
         buf,
-        &mut scrollbar_state,
-    );
+        scroll,
+        theme,
+    )
 }
 
-fn render_peers_tab(task: &Task, area: Rect, buf: &mut Buffer, scroll: usize, count: usize) {
+fn render_peers_tab(
+    task: &Task,
+    area: Rect,
+    buf: &mut Buffer,
+    scroll: usize,
+    max_peers: usize,
+    binary_units: bool,
+    theme: &Theme,
+) {
+    let peers = task.additional.as_ref().and_then(|a| a.peer.as_ref());
+    if peers.is_none_or(|peers| peers.is_empty()) {
+        render_empty_tab(peers.is_some(), "No peers connected", area, buf, theme);
+        return;
+    }
     let chunks = Layout::horizontal([Constraint::Min(0), Constraint::Length(1)]).split(area);
 
-    let rows: Vec<Row> = task
-        .additional
-        .as_ref()
-        .and_then(|a| a.peer.as_ref())
-        .map(|peers| {
-            peers
-                .iter()
-                .map(|p| {
-                    Row::new(vec![
-                        Cell::from(p.address.clone()).style(Style::default().fg(Color::White)),
-                        Cell::from(format_speed(p.speed_download))
-                            .style(Style::default().fg(Color::Green)),
-                        Cell::from(format_speed(p.speed_upload))
-                            .style(Style::default().fg(Color::Green)),
-                        Cell::from(p.agent.clone()).style(Style::default().fg(Color::Yellow)),
-                    ])
-                })
-                .collect()
-        })
+    // Fastest first, and only the first `max_peers` of them on well-seeded torrents
+    let mut peers: Vec<_> = peers
+        .map(|peers| peers.iter().collect())
         .unwrap_or_default();
+    peers.sort_by(|a, b| {
+        b.speed_download
+            .cmp(&a.speed_download)
+            .then(b.speed_upload.cmp(&a.speed_upload))
+    });
+    let hidden = match max_peers {
+        0 => 0,
+        max => peers.len().saturating_sub(max),
+    };
+    peers.truncate(peers.len() - hidden);
+
+    let area_height = chunks[0].height as usize;
+    let count = peers.len() + usize::from(hidden > 0);
+    // Busy torrents have hundreds of peers, only build the rows that fit below the header
+    let visible = area_height.saturating_sub(1);
+
+    let speed = |bytes| Line::from(format_speed(bytes, binary_units)).right_aligned();
+    let mut rows: Vec<Row> = peers
+        .iter()
+        .skip(scroll)
+        .take(visible)
+        .map(|p| {
+            Row::new(vec![
+                Cell::from(p.address.clone()).style(Style::default().fg(theme.text)),
+                Cell::from(speed(p.speed_download)).style(Style::default().fg(theme.success)),
+                Cell::from(speed(p.speed_upload)).style(Style::default().fg(theme.success)),
+                Cell::from(p.agent.clone()).style(Style::default().fg(theme.accent)),
+            ])
+        })
+        .collect();
+    if hidden > 0 && scroll <= peers.len() && rows.len() < visible {
+        rows.push(Row::new(vec![
+            Cell::from(format!("…and {} more", hidden)).style(Style::default().fg(theme.muted)),
+        ]));
+    }
 
     let header = Row::new(vec![
-        Cell::from("Address").style(Style::default().fg(Color::Yellow).underlined()),
-        Cell::from("Down").style(Style::default().fg(Color::Yellow).underlined()),
-        Cell::from("Up").style(Style::default().fg(Color::Yellow).underlined()),
-        Cell::from("Client").style(Style::default().fg(Color::Yellow).underlined()),
+        Cell::from("Address").style(Style::default().fg(theme.accent).underlined()),
+        Cell::from(Line::from("Down").right_aligned())
+            .style(Style::default().fg(theme.accent).underlined()),
+        Cell::from(Line::from("Up").right_aligned())
+            .style(Style::default().fg(theme.accent).underlined()),
+        Cell::from("Client").style(Style::default().fg(theme.accent).underlined()),
     ]);
 
     let widths = [
@@ -647,12 +1831,8 @@ fn render_peers_tab(task: &Task, area: Rect, buf: &mut Buffer, scroll: usize, co
         Constraint::Percentage(35),
     ];
 
-    let area_height = chunks[0].height as usize;
-
     let table = Table::new(rows, widths).header(header).column_spacing(1);
-
-    let mut state = TableState::default().with_offset(scroll);
-    StatefulWidget::render(table, chunks[0], buf, &mut state);
+    Widget::render(table, chunks[0], buf);
 
     let mut scrollbar_state =
         ScrollbarState::new(count.saturating_sub(area_height)).position(scroll);
@@ -666,47 +1846,128 @@ fn render_peers_tab(task: &Task, area: Rect, buf: &mut Buffer, scroll: usize, co
     );
 }
 
-fn render_files_tab(task: &Task, area: Rect, buf: &mut Buffer, scroll: usize, count: usize) {
-    let chunks = Layout::horizontal([Constraint::Min(0), Constraint::Length(1)]).split(area);
-
-    let rows: Vec<Row> = task
-        .additional
-        .as_ref()
-        .and_then(|a| a.file.as_ref())
+/// Returns the largest useful scroll offset, long file names wrap so rows can be taller than
+/// one line
+fn render_files_tab(
+    task: &Task,
+    filter: &str,
+    area: Rect,
+    buf: &mut Buffer,
+    scroll: usize,
+    theme: &Theme,
+) -> usize {
+    let files = task.additional.as_ref().and_then(|a| a.file.as_ref());
+    if files.is_none_or(|files| files.is_empty()) {
+        render_empty_tab(files.is_some(), "No file list available", area, buf, theme);
+        return 0;
+    }
+    let filter = filter.to_lowercase();
+    let rows: Vec<(String, Cell)> = files
         .map(|files| {
             files
                 .iter()
+                .filter(|f| f.filename.to_lowercase().contains(&filter))
                 .map(|f| {
                     let progress = if f.size > 0 {
                         format!("{:.1}%", f.size_downloaded as f64 / f.size as f64 * 100.0)
                     } else {
                         "N/A".to_string()
                     };
-                    Row::new(vec![
-                        Cell::from(f.filename.clone()).style(Style::default().fg(Color::White)),
-                        Cell::from(progress).style(Style::default().fg(Color::Yellow)),
-                    ])
+                    (
+                        f.filename.clone(),
+                        Cell::from(progress).style(Style::default().fg(theme.accent)),
+                    )
                 })
                 .collect()
         })
         .unwrap_or_default();
+    if rows.is_empty() {
+        render_empty_tab(true, "No files match the filter", area, buf, theme);
+        return 0;
+    }
 
-    let header = Row::new(vec![
-        Cell::from("Filename").style(Style::default().fg(Color::Yellow).underlined()),
-        Cell::from("Progress").style(Style::default().fg(Color::Yellow).underlined()),
-    ]);
+    render_wrapping_table(
+        ["Filename", "Progress"],
+        rows,
+        [Constraint::Percentage(90), Constraint::Percentage(10)],
+        area,
+        buf,
+        scroll,
+        theme,
+    )
+}
 
-    let widths = [Constraint::Percentage(90), Constraint::Percentage(10)];
+/// Stands in for a tab without rows, telling an empty list apart from one that hasn't been
+/// loaded for the task yet
+fn render_empty_tab(loaded: bool, message: &str, area: Rect, buf: &mut Buffer, theme: &Theme) {
+    let message = if loaded { message } else { "Not loaded yet" };
+    Paragraph::new(message)
+        .style(Style::default().fg(theme.muted))
+        .render(area, buf);
+}
 
-    let area_height = chunks[0].height as usize;
+/// Two column table with a scrollbar where the first column wraps instead of being clipped.
+/// `scroll` is a row offset, the returned value is the largest offset that still fills the
+/// area, i.e. where scrolling should stop.
+fn render_wrapping_table(
+    headers: [&'static str; 2],
+    rows: Vec<(String, Cell<'static>)>,
+    widths: [Constraint; 2],
+    area: Rect,
+    buf: &mut Buffer,
+    scroll: usize,
+    theme: &Theme,
+) -> usize {
+    let chunks = Layout::horizontal([Constraint::Min(0), Constraint::Length(1)]).split(area);
 
-    let table = Table::new(rows, widths).header(header).column_spacing(1);
+    // Split the same way the table does, so the wrap width matches the column exactly
+    let wrap_width = Layout::horizontal(widths).spacing(1).split(chunks[0])[0].width;
 
-    let mut state = TableState::default().with_offset(scroll);
-    StatefulWidget::render(table, chunks[0], buf, &mut state);
+    let heights: Vec<usize> = rows
+        .iter()
+        .map(|(text, _)| wrapped_height(text, wrap_width))
+        .collect();
+    let visible_height = (chunks[0].height as usize).saturating_sub(1); // minus header
+    let max_scroll = max_row_offset(&heights, visible_height);
+    let scroll = scroll.min(max_scroll);
+
+    // Only wrap the rows from the offset down to the bottom of the area, a torrent can have
+    // thousands of files
+    let mut filled = 0;
+    let shown = heights[scroll..]
+        .iter()
+        .take_while(|height| {
+            let fits = filled < visible_height;
+            filled += *height;
+            fits
+        })
+        .count();
+    let rows: Vec<Row> = rows
+        .into_iter()
+        .skip(scroll)
+        .take(shown)
+        .map(|(text, cell)| {
+            let lines = wrap_chars(&text, wrap_width);
+            let height = lines.len() as u16;
+            Row::new(vec![
+                Cell::from(Text::from(lines)).style(Style::default().fg(theme.text)),
+                cell,
+            ])
+            .height(height)
+        })
+        .collect();
 
-    let mut scrollbar_state =
-        ScrollbarState::new(count.saturating_sub(area_height)).position(scroll);
+    let header = Row::new(
+        headers
+            .map(|title| Cell::from(title).style(Style::default().fg(theme.accent).underlined())),
+    );
+
+    let table = Table::new(rows, [Constraint::Length(wrap_width), Constraint::Fill(1)])
+        .header(header)
+        .column_spacing(1);
+    Widget::render(table, chunks[0], buf);
+
+    let mut scrollbar_state = ScrollbarState::new(max_scroll).position(scroll);
     StatefulWidget::render(
         Scrollbar::new(ScrollbarOrientation::VerticalRight)
             .begin_symbol(Some("↑"))
@@ -715,9 +1976,50 @@ fn render_files_tab(task: &Task, area: Rect, buf: &mut Buffer, scroll: usize, co
         buf,
         &mut scrollbar_state,
     );
+
+    max_scroll
 }
 
-fn render_progress_bar(progress: f64, width: usize) -> Span<'static> {
+/// Break `text` into lines of at most `width` characters. URLs and file names rarely have
+/// spaces to break at, so this breaks anywhere.
+fn wrap_chars(text: &str, width: u16) -> Vec<Line<'static>> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.is_empty() || width == 0 {
+        return vec![Line::from(text.to_string())];
+    }
+    chars
+        .chunks(width as usize)
+        .map(|chunk| Line::from(chunk.iter().collect::<String>()))
+        .collect()
+}
+
+/// Number of lines [`wrap_chars`] breaks `text` into, without building them
+fn wrapped_height(text: &str, width: u16) -> usize {
+    match width {
+        0 => 1,
+        width => text.chars().count().div_ceil(width as usize).max(1),
+    }
+}
+
+/// Smallest row offset from which the remaining rows fit into `height` lines
+fn max_row_offset(row_heights: &[usize], height: usize) -> usize {
+    let mut remaining = 0;
+    for (i, row_height) in row_heights.iter().enumerate().rev() {
+        remaining += row_height;
+        if remaining > height {
+            return i + 1;
+        }
+    }
+    0
+}
+
+fn render_progress_bar(progress: f64, width: usize, theme: &Theme) -> Span<'static> {
+    // DSM can over-report the downloaded size, and an empty task divides by zero
+    let progress = if progress.is_nan() {
+        0.0
+    } else {
+        progress.clamp(0.0, 100.0)
+    };
     let filled = (progress / 100.0 * width as f64).round() as usize;
     let label = format!("{:>3.0}%", progress);
 
@@ -737,32 +2039,65 @@ fn render_progress_bar(progress: f64, width: usize) -> Span<'static> {
         .collect();
 
     let color = if progress >= 100.0 {
-        Color::Green
+        theme.progress_done
     } else if progress >= 50.0 {
-        Color::Yellow
+        theme.progress_mid
     } else {
-        Color::Red
+        theme.progress_low
     };
     Span::styled(bar, Style::default().fg(color))
 }
 
-fn format_speed(bytes_per_sec: u64) -> String {
-    if bytes_per_sec >= 1_048_576 {
-        format!("{:.1} MB/s", bytes_per_sec as f64 / 1_048_576.0)
-    } else if bytes_per_sec >= 1024 {
-        format!("{:.0} KB/s", bytes_per_sec as f64 / 1024.0)
-    } else if bytes_per_sec > 0 {
-        format!("{} B/s", bytes_per_sec)
-    } else {
-        String::new() // show nothing when idle
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(progress: f64) -> String {
+        render_progress_bar(progress, 10, &Theme::default())
+            .content
+            .into_owned()
     }
-}
 
-fn truncate(s: &str, max_chars: usize) -> String {
-    if s.chars().count() > max_chars {
-        let truncated: String = s.chars().take(max_chars - 1).collect();
-        format!("{}…", truncated)
-    } else {
-        s.to_string()
+    #[test]
+    fn progress_bar_empty() {
+        assert_eq!(bar(0.0), "░░░  0%░░░");
+    }
+
+    #[test]
+    fn progress_bar_half() {
+        assert_eq!(bar(50.0), "███ 50%░░░");
+    }
+
+    #[test]
+    fn progress_bar_full() {
+        assert_eq!(bar(100.0), "███100%███");
+    }
+
+    #[test]
+    fn progress_bar_clamps_out_of_range_values() {
+        assert_eq!(bar(150.0), bar(100.0));
+        assert_eq!(bar(-20.0), bar(0.0));
+        assert_eq!(bar(f64::NAN), bar(0.0));
+        assert_eq!(bar(150.0).chars().count(), 10);
+    }
+
+    #[test]
+    fn wrapped_height_matches_wrap_chars() {
+        for text in [
+            "",
+            "short",
+            "exactly10!",
+            "https://tracker.example.com/announce",
+        ] {
+            for width in [0, 1, 10, 80] {
+                assert_eq!(
+                    wrapped_height(text, width),
+                    wrap_chars(text, width).len(),
+                    "{:?} at {}",
+                    text,
+                    width
+                );
+            }
+        }
     }
 }
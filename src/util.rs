@@ -0,0 +1,498 @@
+use chrono::{DateTime, Local, Utc};
+use crossterm::{clipboard::CopyToClipboard, execute};
+use std::io::stdout;
+use std::process::{Command, Stdio};
+use syno_download_station::entities::Task;
+use syno_download_station::utils::convert_time_left;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Shown in place of a value that hasn't been loaded yet, so it isn't taken for a real zero
+pub const UNKNOWN: &str = "-";
+
+/// Copy text to the system clipboard.
+///
+/// Uses the OSC 52 escape sequence, so it works over SSH as well, as long as the terminal
+/// supports it.
+pub fn set_clipboard(text: &str) -> anyhow::Result<()> {
+    execute!(stdout(), CopyToClipboard::to_clipboard_from(text))?;
+    Ok(())
+}
+
+/// DSM web UI link that opens File Station in `destination`, a shared folder path such as
+/// "downloads/movies" as reported for a task.
+pub fn file_station_url(server_url: &str, destination: &str) -> anyhow::Result<String> {
+    let url = reqwest::Url::parse_with_params(
+        &format!("{}/", server_url.trim_end_matches('/')),
+        &[
+            ("launchApp", "SYNO.SDS.App.FileStation3.Instance"),
+            (
+                "launchParam",
+                &format!("openfile=/{}/", destination.trim_matches('/')),
+            ),
+        ],
+    )?;
+    Ok(url.to_string())
+}
+
+/// Show a desktop notification. Sent from a blocking task as it waits for the notification
+/// daemon, failures (e.g. no daemon over SSH) only go to the log.
+pub fn desktop_notification(summary: &str, body: &str) {
+    let (summary, body) = (summary.to_string(), body.to_string());
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = notify_rust::Notification::new()
+            .appname("dstui")
+            .summary(&summary)
+            .body(&body)
+            .show()
+        {
+            log::warn!("Desktop notification failed: {}", e);
+        }
+    });
+}
+
+/// Open a URL in the default browser.
+///
+/// Returns false without trying when there is likely no browser to open, i.e. over SSH.
+pub fn open_in_browser(url: &str) -> bool {
+    if std::env::var_os("SSH_CONNECTION").is_some() {
+        return false;
+    }
+
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+
+    // Keep the opener's output from drawing over the UI
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .is_ok()
+}
+
+/// Format a byte count with a unit suffix.
+///
+/// `binary` selects 1024-based units (KiB, MiB, ...) instead of 1000-based ones (KB, MB, ...).
+pub fn format_bytes(bytes: u64, binary: bool) -> String {
+    let (base, units) = if binary {
+        (1024.0, ["B", "KiB", "MiB", "GiB", "TiB"])
+    } else {
+        (1000.0, ["B", "KB", "MB", "GB", "TB"])
+    };
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", value, units[unit])
+    }
+}
+
+/// Format a transfer rate, e.g. "1.5 MB/s". A rate of 0 returns an empty string, so idle
+/// transfers leave their cell blank instead of showing "0 B/s".
+pub fn format_speed(bytes_per_sec: u64, binary: bool) -> String {
+    if bytes_per_sec == 0 {
+        return String::new();
+    }
+    format!("{}/s", format_bytes(bytes_per_sec, binary))
+}
+
+/// Shorten `s` to at most `max_width` terminal columns, ending in "…" when cut. CJK characters
+/// and most emoji take two columns, counting chars would let them run into the next column.
+pub fn truncate(s: &str, max_width: usize) -> String {
+    if s.width() <= max_width {
+        return s.to_string();
+    }
+    let mut width = 0;
+    let truncated: String = s
+        .chars()
+        .take_while(|c| {
+            width += c.width().unwrap_or(0);
+            width < max_width // one column left for the ellipsis
+        })
+        .collect();
+    format!("{}…", truncated)
+}
+
+/// `s` cut with [`truncate`] and padded with spaces to exactly `width` terminal columns, for
+/// plain text tables
+pub fn fit_width(s: &str, width: usize) -> String {
+    let s = truncate(s, width);
+    let padding = width.saturating_sub(s.width());
+    format!("{}{}", s, " ".repeat(padding))
+}
+
+/// Format a task timestamp in local time, or in UTC when `local` is false. DSM reports 0 for
+/// times that haven't happened yet, like the completion of a running task, shown as "-".
+pub fn format_timestamp(time: DateTime<Utc>, local: bool) -> String {
+    if time.timestamp() == 0 {
+        return "-".to_string();
+    }
+    if local {
+        time.with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    } else {
+        time.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+    }
+}
+
+/// Just the day of a task timestamp, for places too narrow for `format_timestamp`
+pub fn format_date(time: DateTime<Utc>, local: bool) -> String {
+    if time.timestamp() == 0 {
+        return "-".to_string();
+    }
+    if local {
+        time.with_timezone(&Local).format("%Y-%m-%d").to_string()
+    } else {
+        time.format("%Y-%m-%d").to_string()
+    }
+}
+
+/// How long before `now` a task event happened, like "3m ago" or "5h 12m ago". Events more
+/// than a day ago get the absolute time from `format_timestamp` instead.
+pub fn format_relative(time: DateTime<Utc>, now: DateTime<Utc>, local: bool) -> String {
+    if time.timestamp() == 0 {
+        return "-".to_string();
+    }
+    let seconds = now.signed_duration_since(time).num_seconds();
+    match seconds {
+        // A little in the future is just the NAS clock being ahead of ours
+        -60..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", seconds / 60),
+        3600..86400 => format!("{}h {}m ago", seconds / 3600, seconds % 3600 / 60),
+        _ => format_timestamp(time, local),
+    }
+}
+
+/// Whether the transfer info of a task has been loaded, without it the progress, ratio and
+/// byte counts would all read as zero
+pub fn has_transfer(task: &Task) -> bool {
+    task.additional
+        .as_ref()
+        .is_some_and(|a| a.transfer.is_some())
+}
+
+/// Upload ratio of a task, `UNKNOWN` until its transfer info has been loaded
+pub fn format_ratio(task: &Task) -> String {
+    if has_transfer(task) {
+        format!("{:.2}", task.calculate_ratio())
+    } else {
+        UNKNOWN.to_string()
+    }
+}
+
+/// Estimated time remaining for a transfer, formatted for display.
+///
+/// Returns "-" when there is nothing left to transfer and "∞" when the transfer is stalled.
+pub fn format_eta(remaining_bytes: u64, speed_bytes_per_sec: u64) -> String {
+    if remaining_bytes == 0 {
+        return "-".to_string();
+    }
+    match remaining_bytes.checked_div(speed_bytes_per_sec) {
+        Some(seconds) => convert_time_left(seconds as i64),
+        None => "∞".to_string(),
+    }
+}
+
+/// Clean up a DiskStation address typed by the user: trims whitespace and trailing slashes
+/// and adds `http://` when there is no scheme, so "192.168.1.5:5000" works. Returns what is
+/// wrong with it otherwise.
+pub fn normalize_server_url(input: &str) -> Result<String, String> {
+    let input = input.trim().trim_end_matches('/');
+    if input.is_empty() {
+        return Err("The URL can't be empty".to_string());
+    }
+    let url = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("http://{}", input)
+    };
+
+    let parsed = reqwest::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("The URL must start with http:// or https://".to_string());
+    }
+    if parsed.host_str().is_none_or(|host| host.is_empty()) {
+        return Err("The URL has no host name".to_string());
+    }
+    if parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err("The URL should only be the address, like http://diskstation:5000".to_string());
+    }
+    Ok(url)
+}
+
+/// Parse a speed limit in KB/s as typed in the speed limit popup. Empty or "unlimited" is 0,
+/// which is what Download Station takes as no limit.
+pub fn parse_speed_limit(input: &str) -> Result<u64, String> {
+    match input.trim().to_lowercase().as_str() {
+        "" | "unlimited" => Ok(0),
+        value => value
+            .parse::<u64>()
+            .map_err(|_| format!("{} isn't a whole number of KB/s", input.trim())),
+    }
+}
+
+/// The interesting parts of a magnet link
+#[derive(Debug, Default, PartialEq)]
+pub struct MagnetInfo {
+    pub name: Option<String>,      // dn
+    pub info_hash: Option<String>, // xt without the "urn:btih:" style prefix
+    pub trackers: usize,           // number of tr parameters
+}
+
+/// Pull the display name, info hash and tracker count out of a magnet link.
+///
+/// Returns `None` for anything that isn't a magnet link so callers can show it unchanged.
+pub fn parse_magnet(uri: &str) -> Option<MagnetInfo> {
+    let url = reqwest::Url::parse(uri.trim()).ok()?;
+    if url.scheme() != "magnet" {
+        return None;
+    }
+    let mut info = MagnetInfo::default();
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "dn" if info.name.is_none() => info.name = Some(value.into_owned()),
+            "xt" if info.info_hash.is_none() => {
+                // urn:btih:<hash>, urn:btmh:<hash> etc., keep only the hash
+                let hash = value.rsplit(':').next().unwrap_or_default();
+                info.info_hash = Some(hash.to_string());
+            }
+            "tr" => info.trackers += 1,
+            _ => {}
+        }
+    }
+    Some(info)
+}
+
+/// Check whether a URL can be used to create a task and return its kind for display.
+///
+/// Download Station accepts http(s), FTP and SFTP URLs, magnet and eD2k links, and the
+/// Thunder, FlashGet and QQDL links of Chinese download sites. Anything else yields `None`.
+pub fn validate_url(url: &str) -> Option<&'static str> {
+    let url = url.trim();
+    let (scheme, rest) = url.split_once(':')?;
+    let has_path = rest.len() > 2 && rest.starts_with("//");
+    match scheme.to_lowercase().as_str() {
+        "magnet" if rest.starts_with('?') && rest.len() > 1 => Some("magnet link"),
+        "http" if has_path => Some("http"),
+        "https" if has_path => Some("https"),
+        "ftp" if has_path => Some("ftp"),
+        "ftps" if has_path => Some("ftps"),
+        "sftp" if has_path => Some("sftp"),
+        "ed2k" if has_path => Some("eD2k link"),
+        "thunder" if has_path => Some("Thunder link"),
+        "flashget" if has_path => Some("FlashGet link"),
+        "qqdl" if has_path => Some("QQDL link"),
+        _ => None,
+    }
+}
+
+/// Fuzzy match for the task filter: the characters of `pattern` have to appear in `text` in
+/// the same order, ignoring case, so "ubsrv" finds "ubuntu-server-24.04.iso". Returns a score,
+/// higher is better, and the character positions in `text` that matched.
+///
+/// Matches right after another one or at the start of a word count more, skipped characters
+/// count against it. Every place the first character occurs is tried as a start, "iso" should
+/// match the extension of "debian.iso" and not the "i" of "debian".
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let lower = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(lower)
+        .collect();
+    let text: Vec<char> = text.chars().map(lower).collect();
+    let Some(&first) = pattern.first() else {
+        return Some((0, vec![]));
+    };
+
+    let match_from = |start: usize| -> Option<(i64, Vec<usize>)> {
+        let mut positions: Vec<usize> = Vec::with_capacity(pattern.len());
+        let mut score = 0;
+        let mut next = start;
+        for &wanted in &pattern {
+            let found = next + text[next..].iter().position(|&c| c == wanted)?;
+            score += 16;
+            let run = positions.last().is_some_and(|&last| last + 1 == found);
+            if run || found == 0 || !text[found - 1].is_alphanumeric() {
+                score += 8;
+            }
+            // Gaps cost, but a match far into a long name shouldn't lose to every short one
+            if !positions.is_empty() {
+                score -= (found - next).min(8) as i64;
+            }
+            positions.push(found);
+            next = found + 1;
+        }
+        Some((score, positions))
+    };
+
+    text.iter()
+        .enumerate()
+        .filter(|&(_, &c)| c == first)
+        .filter_map(|(start, _)| match_from(start))
+        // The first of equally good matches
+        .fold(
+            None,
+            |best: Option<(i64, Vec<usize>)>, candidate| match &best {
+                Some((score, _)) if *score >= candidate.0 => best,
+                _ => Some(candidate),
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn timestamp_zero_is_a_dash() {
+        let epoch = DateTime::<Utc>::from_timestamp(0, 0).unwrap();
+        assert_eq!(format_timestamp(epoch, true), "-");
+        assert_eq!(format_timestamp(epoch, false), "-");
+    }
+
+    #[test]
+    fn timestamp_in_utc() {
+        let time = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(format_timestamp(time, false), "2023-11-14 22:13:20 UTC");
+    }
+
+    #[test]
+    fn relative_times() {
+        let now = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        let ago = |seconds: i64| format_relative(now - TimeDelta::seconds(seconds), now, false);
+        assert_eq!(ago(0), "just now");
+        assert_eq!(ago(-30), "just now");
+        assert_eq!(ago(59), "just now");
+        assert_eq!(ago(3 * 60 + 10), "3m ago");
+        assert_eq!(ago(5 * 3600 + 12 * 60), "5h 12m ago");
+        assert_eq!(ago(86400), "2023-11-13 22:13:20 UTC");
+        assert_eq!(ago(-3600), "2023-11-14 23:13:20 UTC");
+        let epoch = DateTime::<Utc>::from_timestamp(0, 0).unwrap();
+        assert_eq!(format_relative(epoch, now, false), "-");
+    }
+
+    #[test]
+    fn timestamp_in_local_time() {
+        // Whatever the machine's time zone, it's the same instant without a UTC suffix
+        let time = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        let formatted = format_timestamp(time, true);
+        let parsed = chrono::NaiveDateTime::parse_from_str(&formatted, "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .and_local_timezone(Local)
+            .unwrap();
+        assert_eq!(parsed.with_timezone(&Utc), time);
+    }
+
+    #[test]
+    fn speed_limits() {
+        assert_eq!(parse_speed_limit(" 512 "), Ok(512));
+        assert_eq!(parse_speed_limit(""), Ok(0));
+        assert_eq!(parse_speed_limit("Unlimited"), Ok(0));
+        assert!(parse_speed_limit("-1").is_err());
+        assert!(parse_speed_limit("1.5").is_err());
+    }
+
+    #[test]
+    fn truncate_by_display_width() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("abcdefghijkl", 10), "abcdefghi…");
+        // Each of these takes two columns
+        let cjk = truncate("東京の夜景と富士山の写真集", 10);
+        assert_eq!(cjk, "東京の夜…");
+        assert!(cjk.width() <= 10);
+        assert_eq!(truncate("東京の夜景", 10), "東京の夜景");
+    }
+
+    #[test]
+    fn fit_width_pads_by_display_width() {
+        assert_eq!(fit_width("abc", 5), "abc  ");
+        assert_eq!(fit_width("東京", 6), "東京  ");
+        assert_eq!(fit_width("東京の夜景", 6).width(), 6);
+    }
+
+    #[test]
+    fn decimal_and_binary_units() {
+        assert_eq!(format_bytes(0, false), "0 B");
+        assert_eq!(format_bytes(999, false), "999 B");
+        assert_eq!(format_bytes(1000, false), "1.0 KB");
+        assert_eq!(format_bytes(1000, true), "1000 B");
+        assert_eq!(format_bytes(1024, true), "1.0 KiB");
+        assert_eq!(format_bytes(1_500_000, false), "1.5 MB");
+        assert_eq!(format_bytes(1_572_864, true), "1.5 MiB");
+        assert_eq!(format_bytes(u64::MAX, false), "18446744.1 TB");
+    }
+
+    #[test]
+    fn speeds_per_second_and_idle_blank() {
+        assert_eq!(format_speed(0, false), "");
+        assert_eq!(format_speed(0, true), "");
+        assert_eq!(format_speed(512, false), "512 B/s");
+        assert_eq!(format_speed(1_500_000, false), "1.5 MB/s");
+        assert_eq!(format_speed(2048, true), "2.0 KiB/s");
+    }
+
+    #[test]
+    fn ratio_is_unknown_without_transfer_info() {
+        let task = |additional: &str| -> Task {
+            serde_json::from_str(&format!(
+                r#"{{"id":"dbid_1","username":"admin","type":"bt","title":"debian.iso","size":100,"status":8{}}}"#,
+                additional
+            ))
+            .unwrap()
+        };
+        assert_eq!(format_ratio(&task("")), "-");
+        let transfer = r#","additional":{"transfer":{"downloaded_pieces":1,"size_downloaded":100,"size_uploaded":0,"speed_download":0,"speed_upload":0}}"#;
+        assert_eq!(format_ratio(&task(transfer)), "0.00");
+    }
+
+    #[test]
+    fn fuzzy_match_in_order() {
+        let (_, positions) = fuzzy_match("ubsrv", "ubuntu-server-24.04.iso").unwrap();
+        assert_eq!(positions, vec![0, 1, 7, 9, 10]);
+        assert!(fuzzy_match("UBSRV", "ubuntu-server-24.04.iso").is_some());
+        assert!(fuzzy_match("osu", "ubuntu-server-24.04.iso").is_none());
+        assert_eq!(fuzzy_match("", "anything"), Some((0, vec![])));
+    }
+
+    #[test]
+    fn fuzzy_match_prefers_words_and_runs() {
+        let score = |pattern, text| fuzzy_match(pattern, text).unwrap().0;
+        assert!(score("deb", "debian-12.iso") > score("deb", "dark.elephant.bluray"));
+        assert!(score("iso", "debian.iso") > score("iso", "i-want-something-original"));
+    }
+
+    #[test]
+    fn download_station_url_schemes() {
+        assert_eq!(validate_url("magnet:?xt=urn:btih:abc"), Some("magnet link"));
+        assert_eq!(validate_url(" HTTPS://example.com/a.iso "), Some("https"));
+        assert_eq!(validate_url("ftp://mirror.example.com/a.iso"), Some("ftp"));
+        assert_eq!(validate_url("sftp://nas.example.com/a.iso"), Some("sftp"));
+        assert_eq!(
+            validate_url("ed2k://|file|a.iso|1024|0123456789ABCDEF0123456789ABCDEF|/"),
+            Some("eD2k link")
+        );
+        assert_eq!(validate_url("thunder://QUFodHRwOi8v"), Some("Thunder link"));
+        assert_eq!(validate_url("magnet:"), None);
+        assert_eq!(validate_url("http:"), None);
+        assert_eq!(validate_url("file:///etc/passwd"), None);
+        assert_eq!(validate_url("example.com/a.iso"), None);
+    }
+}